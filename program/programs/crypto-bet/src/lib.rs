@@ -1,20 +1,105 @@
+#![allow(unexpected_cfgs)]
+#![allow(deprecated)] // `#[program]` expands to `AccountInfo::realloc`
+#![allow(clippy::manual_is_multiple_of)] // `is_multiple_of` needs a newer rustc than Solana platform-tools ship
+
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
-use solana_program::pubkey;
+use anchor_spl::token::{self, Burn, CloseAccount, Mint, MintTo, Token, TokenAccount, Transfer};
+use anchor_lang::solana_program::pubkey;
+use anchor_lang::solana_program::{ed25519_program, sysvar::instructions as instructions_sysvar};
 
 // Use the correct program ID from Anchor.toml
 declare_id!("4Gd64thyhLeqyLxDz8Ae5Z98qXdqwJrcAYkS6g3Yzy5V");
 
 // Constants for supported tokens
 pub const SOL_MINT: Pubkey = Pubkey::new_from_array([0; 32]); // Native SOL (placeholder)
-pub const USDC_MINT: Pubkey = pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"); // USDC mainnet
+#[cfg(not(feature = "devnet"))]
+pub const USDC_MINT: Pubkey = pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"); // USDC mainnet, the default stable mint
+#[cfg(feature = "devnet")]
+pub const USDC_MINT: Pubkey = pubkey!("4zMMC9srt5Ri5X14GAgXhaHii3GnPAEERYPJgZJDncDU"); // USDC devnet, the default stable mint
+pub const SOL_DECIMALS: u8 = 9;
+pub const USDC_DECIMALS: u8 = 6;
+
+// Pyth pull oracle (price update accounts posted by the Pyth receiver program)
+pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
 
 #[program]
 pub mod crypto_bet {
     use super::*;
 
+    /// Initialize the protocol config holding the admin and treasury
+    /// Only the program's upgrade authority can initialize it
+    pub fn initialize_config(ctx: Context<InitializeConfig>, treasury: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.treasury = treasury;
+        config.bump = ctx.bumps.config;
+        config.min_initial_liquidity = 0;
+        config.stable_mint = USDC_MINT;
+        config.stable_decimals = USDC_DECIMALS;
+
+        emit!(ConfigUpdated {
+            admin: config.admin,
+            treasury: config.treasury,
+        });
+
+        Ok(())
+    }
+
+    /// Update the protocol admin and treasury
+    /// Only the current admin can update the config
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        new_admin: Pubkey,
+        new_treasury: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(config.admin == ctx.accounts.admin.key(), CryptoBetError::UnauthorizedAdmin);
+
+        config.admin = new_admin;
+        config.treasury = new_treasury;
+
+        emit!(ConfigUpdated {
+            admin: config.admin,
+            treasury: config.treasury,
+        });
+
+        Ok(())
+    }
+
+    /// Set the minimum creator-seeded liquidity for new markets, in LIMIT_DECIMALS units
+    /// Only the admin can update it; zero makes seeding optional
+    pub fn set_min_initial_liquidity(
+        ctx: Context<UpdateConfig>,
+        min_initial_liquidity: u64,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.min_initial_liquidity = min_initial_liquidity;
+
+        emit!(MinInitialLiquidityUpdated {
+            min_initial_liquidity,
+        });
+
+        Ok(())
+    }
+
+    /// Set the mint accepted by new Stable markets and pools, e.g. a test mint on localnet or devnet
+    /// Only the admin can update it; existing markets and pools keep the mint they were created with
+    pub fn set_stable_mint(ctx: Context<SetStableMint>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.stable_mint = ctx.accounts.stable_mint.key();
+        config.stable_decimals = ctx.accounts.stable_mint.decimals;
+
+        emit!(StableMintUpdated {
+            stable_mint: config.stable_mint,
+            decimals: config.stable_decimals,
+        });
+
+        Ok(())
+    }
+
     /// Initialize a new prediction market with dual-mode support
     /// Only the program authority can create markets
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize_market(
         ctx: Context<InitializeMarket>,
         market_id: u64,
@@ -23,10 +108,18 @@ pub mod crypto_bet {
         category: String,
         resolution_time: i64,
         market_type: MarketType,
+        creator_fee_bps: u16,
+        claim_deadline: i64,
+        scalar_range: Option<ScalarRange>,
+        parent_outcome: Option<bool>,
+        fixed_odds: Option<FixedOddsConfig>,
+        initial_liquidity: Option<InitialLiquidity>,
+        limits: Option<MarketLimits>,
     ) -> Result<()> {
         require!(title.len() <= 100, CryptoBetError::TitleTooLong);
         require!(description.len() <= 500, CryptoBetError::DescriptionTooLong);
         require!(category.len() <= 50, CryptoBetError::CategoryTooLong);
+        require!(creator_fee_bps <= MAX_CREATOR_FEE_BPS, CryptoBetError::CreatorFeeTooHigh);
         require!(resolution_time > Clock::get()?.unix_timestamp, CryptoBetError::InvalidResolutionTime);
 
         let max_duration = 365 * 24 * 60 * 60; // 1 year in seconds
@@ -34,6 +127,64 @@ pub mod crypto_bet {
             resolution_time <= Clock::get()?.unix_timestamp + max_duration,
             CryptoBetError::MarketDurationTooLong
         );
+        require!(
+            claim_deadline >= resolution_time + MIN_CLAIM_PERIOD,
+            CryptoBetError::InvalidClaimDeadline
+        );
+        if let Some(range) = scalar_range {
+            require!(range.lower_bound < range.upper_bound, CryptoBetError::InvalidScalarRange);
+        }
+
+        // Conditional markets: "if <parent resolves parent_outcome> then <this market>"
+        let parent = match (parent_outcome, ctx.accounts.parent_market.as_ref()) {
+            (Some(outcome), Some(parent_market)) => {
+                require!(parent_market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+                require!(!parent_market.is_scalar(), CryptoBetError::ScalarMarketUnsupported);
+                Some(ParentCondition {
+                    market: parent_market.key(),
+                    outcome,
+                })
+            }
+            (Some(_), None) => return err!(CryptoBetError::MissingParentMarket),
+            (None, Some(_)) => return err!(CryptoBetError::InvalidParentMarket),
+            (None, None) => None,
+        };
+
+        // Fixed-odds markets are priced by the house instead of the pools
+        if let Some(config) = fixed_odds {
+            config.validate()?;
+            require!(scalar_range.is_none() && parent.is_none(), CryptoBetError::FixedOddsUnsupported);
+        }
+
+        // Limits scale with the collateral's decimals, read from its mint (native SOL has none).
+        // Stable collateral lives in a vault the market owns, so the vault must be created with it
+        let (decimals, vault_bump) = match market_type {
+            MarketType::Degen => (SOL_DECIMALS, 0),
+            MarketType::Stable => {
+                let usdc_mint = ctx.accounts.usdc_mint
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingCollateralMint)?;
+                require_keys_eq!(
+                    usdc_mint.key(),
+                    ctx.accounts.config.accepted_mint(market_type),
+                    CryptoBetError::InvalidTokenMint
+                );
+                let vault_bump = ctx.bumps.market_vault.ok_or(CryptoBetError::MissingVault)?;
+                (usdc_mint.decimals, vault_bump)
+            }
+        };
+        let limits = limits.unwrap_or_else(|| MarketLimits::protocol_default(decimals));
+        limits.validate(decimals)?;
+
+        // Creator seed so the first bettor doesn't face an empty pool
+        let min_initial_liquidity = scale_limit(ctx.accounts.config.min_initial_liquidity, decimals);
+        match initial_liquidity {
+            Some(seed) => {
+                require!(fixed_odds.is_none(), CryptoBetError::FixedOddsUnsupported);
+                require!(seed.amount >= min_initial_liquidity, CryptoBetError::InitialLiquidityTooLow);
+            }
+            None => require!(min_initial_liquidity == 0, CryptoBetError::InitialLiquidityTooLow),
+        }
 
         let market = &mut ctx.accounts.market;
         market.authority = ctx.accounts.authority.key();
@@ -46,10 +197,7 @@ pub mod crypto_bet {
         market.market_type = market_type;
         
         // Set accepted mint based on market type
-        market.accepted_mint = match market_type {
-            MarketType::Degen => SOL_MINT,
-            MarketType::Stable => USDC_MINT,
-        };
+        market.accepted_mint = ctx.accounts.config.accepted_mint(market_type);
         
         market.total_yes_amount = 0;
         market.total_no_amount = 0;
@@ -59,6 +207,107 @@ pub mod crypto_bet {
         market.resolved_outcome = None;
         market.created_at = Clock::get()?.unix_timestamp;
         market.bump = ctx.bumps.market;
+        market.creator_fee_bps = creator_fee_bps;
+        market.creator_fees_accrued = 0;
+        market.total_complete_sets = 0;
+        market.yes_mint = Pubkey::default();
+        market.no_mint = Pubkey::default();
+        market.open_positions = 0;
+        market.claim_deadline = claim_deadline;
+        market.total_owed = 0;
+        market.total_claimed = 0;
+        market.total_paid_out = 0;
+        market.shares_settled = 0;
+        market.scalar_range = scalar_range;
+        market.resolved_value = None;
+        market.parent = parent;
+        market.group = Pubkey::default();
+        market.basket_yes_shares = 0;
+        market.basket_no_shares = 0;
+        market.basket_collateral = 0;
+        market.group_settled = false;
+        market.fixed_odds = fixed_odds;
+        market.yes_liability = 0;
+        market.no_liability = 0;
+        market.house_stakes = 0;
+        market.house_reserved = 0;
+        market.house_min_reserved = 0;
+        market.amm_pool = Pubkey::default();
+        market.amm_yes_reserve = 0;
+        market.amm_no_reserve = 0;
+        market.open_parlays = 0;
+        market.open_challenges = 0;
+        market.order_escrow = 0;
+        market.limits = limits;
+        market.decimals = decimals;
+        market.vault_bump = vault_bump;
+
+        if let Some(seed) = initial_liquidity {
+            let (yes_amount, no_amount) = seed.split(decimals)?;
+
+            match market_type {
+                MarketType::Degen => {
+                    let cpi_context = CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        anchor_lang::system_program::Transfer {
+                            from: ctx.accounts.authority.to_account_info(),
+                            to: market.to_account_info(),
+                        },
+                    );
+                    anchor_lang::system_program::transfer(cpi_context, seed.amount)?;
+                }
+                MarketType::Stable => {
+                    let creator_token_account = ctx.accounts.creator_token_account
+                        .as_ref()
+                        .ok_or(CryptoBetError::MissingTokenAccount)?;
+                    let market_vault = ctx.accounts.market_vault
+                        .as_ref()
+                        .ok_or(CryptoBetError::MissingVault)?;
+                    let token_program = ctx.accounts.token_program
+                        .as_ref()
+                        .ok_or(CryptoBetError::MissingTokenProgram)?;
+
+                    require!(
+                        creator_token_account.mint == market.accepted_mint,
+                        CryptoBetError::InvalidTokenMint
+                    );
+
+                    let cpi_accounts = Transfer {
+                        from: creator_token_account.to_account_info(),
+                        to: market_vault.to_account_info(),
+                        authority: ctx.accounts.authority.to_account_info(),
+                    };
+                    let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+                    token::transfer(cpi_ctx, seed.amount)?;
+                }
+            }
+
+            // The seed is an ordinary position, so it is paid out or forfeited like any bet
+            let (yes_side, no_side) = if market.is_scalar() {
+                (BetSide::Long, BetSide::Short)
+            } else {
+                (BetSide::Yes, BetSide::No)
+            };
+            market.record_bet(yes_side, yes_amount, yes_amount)?;
+            market.record_bet(no_side, no_amount, no_amount)?;
+
+            let position = ctx.accounts.creator_position
+                .as_mut()
+                .ok_or(CryptoBetError::InvalidPosition)?;
+            position.user = ctx.accounts.authority.key();
+            position.market = market.key();
+            position.bump = ctx.bumps.creator_position.ok_or(CryptoBetError::InvalidPosition)?;
+            position.record_bet(yes_side, yes_amount, yes_amount)?;
+            position.record_bet(no_side, no_amount, no_amount)?;
+            market.open_positions = 1;
+
+            emit!(InitialLiquidityAdded {
+                market: market.key(),
+                creator: position.user,
+                yes_amount,
+                no_amount,
+            });
+        }
 
         emit!(MarketCreated {
             market: market.key(),
@@ -67,100 +316,201 @@ pub mod crypto_bet {
             category: market.category.clone(),
             resolution_time,
             market_type,
+            creator_fee_bps,
+            claim_deadline,
+            scalar_range,
+            parent,
+            fixed_odds,
+            limits,
         });
 
         Ok(())
     }
 
     /// Place a bet on a market using dual-mode mechanics (SOL or USDC)
-    /// Users can bet YES or NO on the market outcome
+    /// Users can bet YES or NO on the market outcome, optionally bounded by minimum shares,
+    /// a maximum post-bet implied probability and a deadline
     pub fn place_bet(
         ctx: Context<PlaceBet>,
         side: BetSide,
         amount: u64,
+        min_shares_out: Option<u64>,
+        max_implied_probability_bps: Option<u16>,
+        expires_at: Option<i64>,
     ) -> Result<()> {
         require!(amount > 0, CryptoBetError::InvalidAmount);
-        require!(amount >= MIN_BET_AMOUNT, CryptoBetError::BetTooSmall);
-        require!(amount <= MAX_BET_AMOUNT, CryptoBetError::BetTooLarge);
+        if let Some(expires_at) = expires_at {
+            require!(Clock::get()?.unix_timestamp <= expires_at, CryptoBetError::BetRequestExpired);
+        }
 
         let market = &mut ctx.accounts.market;
         require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
         require!(Clock::get()?.unix_timestamp < market.resolution_time, CryptoBetError::MarketExpired);
-
-        let new_total_volume = market.total_volume
-            .checked_add(amount)
-            .ok_or(CryptoBetError::MathOverflow)?;
-        
-        require!(new_total_volume <= MAX_MARKET_VOLUME, CryptoBetError::MarketVolumeTooHigh);
+        require!(side.is_scalar() == market.is_scalar(), CryptoBetError::InvalidBetSide);
+        market.check_bet_limits(amount)?;
 
         // For prediction markets, use 1:1 share ratio (1 token = 1 share)
         let shares = amount;
 
-        // Handle transfer based on market type
-        match market.market_type {
-            MarketType::Degen => {
-                // SOL transfer (native)
-                let cpi_context = CpiContext::new(
-                    ctx.accounts.system_program.to_account_info(),
-                    anchor_lang::system_program::Transfer {
-                        from: ctx.accounts.user.to_account_info(),
-                        to: ctx.accounts.market.to_account_info(),
-                    },
-                );
-                anchor_lang::system_program::transfer(cpi_context, amount)?;
+        // Fixed-odds stakes go to the house, which pays winners at the odds locked in here
+        let mut locked_payout = 0u64;
+        if market.is_fixed_odds() {
+            let house_pool = ctx.accounts.house_pool
+                .as_mut()
+                .ok_or(CryptoBetError::MissingHousePool)?;
+            require!(house_pool.market_type == market.market_type, CryptoBetError::InvalidHousePool);
+            require_keys_eq!(house_pool.accepted_mint, market.accepted_mint, CryptoBetError::InvalidHousePool);
+            locked_payout = market.record_fixed_odds_bet(house_pool, side, amount)?;
+
+            match market.market_type {
+                MarketType::Degen => {
+                    let cpi_context = CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        anchor_lang::system_program::Transfer {
+                            from: ctx.accounts.user.to_account_info(),
+                            to: house_pool.to_account_info(),
+                        },
+                    );
+                    anchor_lang::system_program::transfer(cpi_context, amount)?;
+                }
+                MarketType::Stable => {
+                    let user_token_account = ctx.accounts.user_token_account
+                        .as_ref()
+                        .ok_or(CryptoBetError::MissingTokenAccount)?;
+                    let house_vault = ctx.accounts.house_vault
+                        .as_ref()
+                        .ok_or(CryptoBetError::MissingVault)?;
+                    let token_program = ctx.accounts.token_program
+                        .as_ref()
+                        .ok_or(CryptoBetError::MissingTokenProgram)?;
+
+                    require!(
+                        user_token_account.mint == market.accepted_mint,
+                        CryptoBetError::InvalidTokenMint
+                    );
+
+                    let cpi_accounts = Transfer {
+                        from: user_token_account.to_account_info(),
+                        to: house_vault.to_account_info(),
+                        authority: ctx.accounts.user.to_account_info(),
+                    };
+                    let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+                    token::transfer(cpi_ctx, amount)?;
+                }
             }
-            MarketType::Stable => {
-                // USDC transfer (SPL token)
-                let user_token_account = ctx.accounts.user_token_account
-                    .as_ref()
-                    .ok_or(CryptoBetError::MissingTokenAccount)?;
-                let market_vault = ctx.accounts.market_vault
-                    .as_ref()
-                    .ok_or(CryptoBetError::MissingVault)?;
-                let token_program = ctx.accounts.token_program
-                    .as_ref()
-                    .ok_or(CryptoBetError::MissingTokenProgram)?;
+        } else {
+            // Handle transfer based on market type
+            match market.market_type {
+                MarketType::Degen => {
+                    // SOL transfer (native)
+                    let cpi_context = CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        anchor_lang::system_program::Transfer {
+                            from: ctx.accounts.user.to_account_info(),
+                            to: market.to_account_info(),
+                        },
+                    );
+                    anchor_lang::system_program::transfer(cpi_context, amount)?;
+                }
+                MarketType::Stable => {
+                    // USDC transfer (SPL token)
+                    let user_token_account = ctx.accounts.user_token_account
+                        .as_ref()
+                        .ok_or(CryptoBetError::MissingTokenAccount)?;
+                    let market_vault = ctx.accounts.market_vault
+                        .as_ref()
+                        .ok_or(CryptoBetError::MissingVault)?;
+                    let token_program = ctx.accounts.token_program
+                        .as_ref()
+                        .ok_or(CryptoBetError::MissingTokenProgram)?;
 
-                // Verify token account mint matches market's accepted mint
-                require!(
-                    user_token_account.mint == market.accepted_mint,
-                    CryptoBetError::InvalidTokenMint
-                );
-                require!(
-                    market_vault.mint == market.accepted_mint,
-                    CryptoBetError::InvalidTokenMint
-                );
+                    // Verify token account mint matches market's accepted mint
+                    require!(
+                        user_token_account.mint == market.accepted_mint,
+                        CryptoBetError::InvalidTokenMint
+                    );
+                    require!(
+                        market_vault.mint == market.accepted_mint,
+                        CryptoBetError::InvalidTokenMint
+                    );
 
-                let cpi_accounts = Transfer {
-                    from: user_token_account.to_account_info(),
-                    to: market_vault.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
-                };
-                let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
-                token::transfer(cpi_ctx, amount)?;
+                    let cpi_accounts = Transfer {
+                        from: user_token_account.to_account_info(),
+                        to: market_vault.to_account_info(),
+                        authority: ctx.accounts.user.to_account_info(),
+                    };
+                    let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+                    token::transfer(cpi_ctx, amount)?;
+                }
             }
         }
 
         // Update market totals (same logic for both modes)
-        match side {
-            BetSide::Yes => {
-                market.total_yes_amount = market.total_yes_amount
-                    .checked_add(amount)
-                    .ok_or(CryptoBetError::MathOverflow)?;
-                market.total_yes_shares = market.total_yes_shares
-                    .checked_add(shares)
-                    .ok_or(CryptoBetError::MathOverflow)?;
-            }
-            BetSide::No => {
-                market.total_no_amount = market.total_no_amount
-                    .checked_add(amount)
-                    .ok_or(CryptoBetError::MathOverflow)?;
-                market.total_no_shares = market.total_no_shares
-                    .checked_add(shares)
-                    .ok_or(CryptoBetError::MathOverflow)?;
-            }
+        market.record_bet(side, amount, shares)?;
+
+        // Slippage bounds against the price the bet actually executed at.
+        // Fixed-odds bets are bounded by the payout locked in rather than the shares
+        if let Some(min_shares_out) = min_shares_out {
+            let shares_out = if market.is_fixed_odds() { locked_payout } else { shares };
+            require!(shares_out >= min_shares_out, CryptoBetError::SlippageExceeded);
+        }
+        if let Some(max_implied_probability_bps) = max_implied_probability_bps {
+            require!(
+                market.implied_probability_bps(side)? <= max_implied_probability_bps as u64,
+                CryptoBetError::ImpliedProbabilityTooHigh
+            );
+        }
+
+        // Tokenized markets hand out outcome tokens instead of position shares
+        if market.is_tokenized() {
+            let outcome_mint = ctx.accounts.outcome_mint
+                .as_ref()
+                .ok_or(CryptoBetError::MissingOutcomeMint)?;
+            let user_outcome_token_account = ctx.accounts.user_outcome_token_account
+                .as_ref()
+                .ok_or(CryptoBetError::MissingTokenAccount)?;
+            let token_program = ctx.accounts.token_program
+                .as_ref()
+                .ok_or(CryptoBetError::MissingTokenProgram)?;
+
+            require!(
+                outcome_mint.key() == market.outcome_mint(side == BetSide::Yes),
+                CryptoBetError::InvalidOutcomeMint
+            );
+            require!(
+                user_outcome_token_account.mint == outcome_mint.key(),
+                CryptoBetError::InvalidTokenMint
+            );
+
+            // Outcome tokens move freely once minted, so the limits can only bound
+            // what the receiving token account holds after this bet
+            let held_shares = user_outcome_token_account.amount
+                .checked_add(shares)
+                .ok_or(CryptoBetError::MathOverflow)?;
+            market.check_exposure_limit(held_shares)?;
+            market.check_side_share_limit(side, held_shares)?;
+
+            // Outcome mints are owned by the market PDA, so the market signs
+            let market_id_bytes = market.market_id.to_le_bytes();
+            let seeds = &[
+                b"market".as_ref(),
+                market_id_bytes.as_ref(),
+                &[market.bump]
+            ];
+            let signer = &[&seeds[..]];
+
+            let cpi_accounts = MintTo {
+                mint: outcome_mint.to_account_info(),
+                to: user_outcome_token_account.to_account_info(),
+                authority: market.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                cpi_accounts,
+                signer
+            );
+            token::mint_to(cpi_ctx, shares)?;
         }
-        market.total_volume = new_total_volume;
 
         // Initialize or update user position
         let position = &mut ctx.accounts.position;
@@ -170,27 +520,27 @@ pub mod crypto_bet {
             position.market = market.key();
             position.yes_shares = 0;
             position.no_shares = 0;
+            position.set_yes_shares = 0;
+            position.set_no_shares = 0;
             position.total_invested = 0;
             position.claimed = false;
             position.bump = ctx.bumps.position;
+            position.fixed_yes_payout = 0;
+            position.fixed_no_payout = 0;
+
+            market.open_positions = market.open_positions
+                .checked_add(1)
+                .ok_or(CryptoBetError::MathOverflow)?;
         }
 
-        // Update position
-        match side {
-            BetSide::Yes => {
-                position.yes_shares = position.yes_shares
-                    .checked_add(shares)
-                    .ok_or(CryptoBetError::MathOverflow)?;
-            }
-            BetSide::No => {
-                position.no_shares = position.no_shares
-                    .checked_add(shares)
-                    .ok_or(CryptoBetError::MathOverflow)?;
-            }
+        // Update position (tokenized bets are held as outcome tokens instead)
+        if !market.is_tokenized() {
+            position.record_bet(side, amount, shares)?;
+            market.check_position_limits(position, side)?;
+        }
+        if locked_payout > 0 {
+            position.record_fixed_payout(side, locked_payout)?;
         }
-        position.total_invested = position.total_invested
-            .checked_add(amount)
-            .ok_or(CryptoBetError::MathOverflow)?;
 
         emit!(BetPlaced {
             market: market.key(),
@@ -206,6 +556,238 @@ pub mod crypto_bet {
         Ok(())
     }
 
+    /// Place several bets across markets in one transaction (dual-mode support)
+    /// Takes (market, position, vault) triples in remaining_accounts; the vault is ignored for SOL markets.
+    /// The user is debited once per mint for the sum of its bets, from the signer (SOL) or user_token_account (USDC).
+    /// A batch can mix SOL markets with Stable markets of a single mint, since there is one token account
+    pub fn place_bets<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PlaceBets<'info>>,
+        bets: Vec<BetOrder>,
+    ) -> Result<()> {
+        let remaining_accounts = ctx.remaining_accounts;
+        require!(!bets.is_empty() && bets.len() <= MAX_BATCH_SIZE, CryptoBetError::BatchTooLarge);
+        require!(
+            !remaining_accounts.is_empty() && remaining_accounts.len() % 3 == 0,
+            CryptoBetError::InvalidRemainingAccounts
+        );
+        require!(remaining_accounts.len() / 3 <= MAX_BATCH_SIZE, CryptoBetError::BatchTooLarge);
+
+        let user_key = ctx.accounts.user.key();
+        let now = Clock::get()?.unix_timestamp;
+
+        // Load and validate every market and position before touching any funds
+        let mut markets: Vec<Account<'info, Market>> = Vec::new();
+        let mut positions: Vec<Account<'info, Position>> = Vec::new();
+        for accounts in remaining_accounts.chunks(3) {
+            let (market_info, position_info) = (&accounts[0], &accounts[1]);
+
+            let mut market: Account<'info, Market> = Account::try_from(market_info)?;
+            let expected_market = Pubkey::create_program_address(
+                &[b"market", market.market_id.to_le_bytes().as_ref(), &[market.bump]],
+                ctx.program_id,
+            )
+            .map_err(|_| error!(CryptoBetError::InvalidMarketAccount))?;
+            require_keys_eq!(market_info.key(), expected_market, CryptoBetError::InvalidMarketAccount);
+            require!(
+                markets.iter().all(|other| other.key() != market_info.key()),
+                CryptoBetError::InvalidRemainingAccounts
+            );
+            require!(
+                market.market_type == MarketType::Degen
+                    || markets.iter().all(|other| {
+                        other.market_type == MarketType::Degen || other.accepted_mint == market.accepted_mint
+                    }),
+                CryptoBetError::MixedMintBatch
+            );
+            require!(!market.is_tokenized(), CryptoBetError::TokenizedMarketUnsupported);
+            require!(!market.is_fixed_odds(), CryptoBetError::FixedOddsUnsupported);
+
+            let (expected_position, position_bump) = Pubkey::find_program_address(
+                &[b"position", market_info.key.as_ref(), user_key.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(position_info.key(), expected_position, CryptoBetError::InvalidPosition);
+
+            // First bet on this market: create the position the same way init_if_needed would
+            if position_info.data_is_empty() {
+                let market_key = market_info.key();
+                let seeds = &[
+                    b"position".as_ref(),
+                    market_key.as_ref(),
+                    user_key.as_ref(),
+                    &[position_bump]
+                ];
+                create_pda_account(
+                    &ctx.accounts.user.to_account_info(),
+                    position_info,
+                    &ctx.accounts.system_program,
+                    8 + Position::LEN,
+                    ctx.program_id,
+                    &seeds[..],
+                )?;
+
+                let position = Position {
+                    user: user_key,
+                    market: market_key,
+                    yes_shares: 0,
+                    no_shares: 0,
+                    total_invested: 0,
+                    claimed: false,
+                    bump: position_bump,
+                    set_yes_shares: 0,
+                    set_no_shares: 0,
+                    fixed_yes_payout: 0,
+                    fixed_no_payout: 0,
+                    order_proceeds: 0,
+                    open_orders: 0,
+                    basket_shares: 0,
+                    basket_collateral: 0,
+                };
+                position.try_serialize(&mut &mut position_info.try_borrow_mut_data()?[..])?;
+
+                market.open_positions = market.open_positions
+                    .checked_add(1)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+            }
+
+            let position: Account<'info, Position> = Account::try_from(position_info)?;
+            markets.push(market);
+            positions.push(position);
+        }
+
+        // Apply every bet to its market and position, summing what each market is owed
+        let mut debits = vec![0u64; markets.len()];
+        for bet in bets.iter() {
+            let index = bet.market_index as usize;
+            require!(index < markets.len(), CryptoBetError::InvalidRemainingAccounts);
+            require!(bet.amount > 0, CryptoBetError::InvalidAmount);
+
+            let market = &mut markets[index];
+            let position = &mut positions[index];
+            require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+            require!(now < market.resolution_time, CryptoBetError::MarketExpired);
+            require!(bet.side.is_scalar() == market.is_scalar(), CryptoBetError::InvalidBetSide);
+            require!(!position.claimed, CryptoBetError::AlreadyClaimed);
+            market.check_bet_limits(bet.amount)?;
+
+            // For prediction markets, use 1:1 share ratio (1 token = 1 share)
+            let shares = bet.amount;
+            market.record_bet(bet.side, bet.amount, shares)?;
+            position.record_bet(bet.side, bet.amount, shares)?;
+            market.check_position_limits(position, bet.side)?;
+            debits[index] = debits[index]
+                .checked_add(bet.amount)
+                .ok_or(CryptoBetError::MathOverflow)?;
+
+            emit!(BetPlaced {
+                market: market.key(),
+                user: user_key,
+                side: bet.side,
+                amount: bet.amount,
+                shares,
+                new_yes_total: market.total_yes_amount,
+                new_no_total: market.total_no_amount,
+                market_type: market.market_type,
+            });
+        }
+
+        // Collect funds once per mint: the user pays the mint's total into the first market
+        // that uses it, and that market forwards every other market's share
+        let mut collected = vec![false; markets.len()];
+        for hub in 0..markets.len() {
+            if collected[hub] || debits[hub] == 0 {
+                continue;
+            }
+            let mint = markets[hub].accepted_mint;
+            let members: Vec<usize> = (hub..markets.len())
+                .filter(|&index| debits[index] > 0 && markets[index].accepted_mint == mint)
+                .collect();
+            let mut total = 0u64;
+            for &index in members.iter() {
+                collected[index] = true;
+                total = total
+                    .checked_add(debits[index])
+                    .ok_or(CryptoBetError::MathOverflow)?;
+            }
+            let hub_info = &remaining_accounts[hub * 3];
+
+            match markets[hub].market_type {
+                MarketType::Degen => {
+                    let cpi_context = CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        anchor_lang::system_program::Transfer {
+                            from: ctx.accounts.user.to_account_info(),
+                            to: hub_info.clone(),
+                        },
+                    );
+                    anchor_lang::system_program::transfer(cpi_context, total)?;
+
+                    // Markets are program-owned, so the hub can hand lamports on directly
+                    for &index in members.iter().skip(1) {
+                        transfer_from_market(
+                            &markets[hub],
+                            &remaining_accounts[index * 3],
+                            None,
+                            None,
+                            None,
+                            debits[index],
+                        )?;
+                    }
+                }
+                MarketType::Stable => {
+                    let user_token_account = ctx.accounts.user_token_account
+                        .as_ref()
+                        .ok_or(CryptoBetError::MissingTokenAccount)?;
+                    let token_program = ctx.accounts.token_program
+                        .as_ref()
+                        .ok_or(CryptoBetError::MissingTokenProgram)?;
+                    require!(user_token_account.mint == mint, CryptoBetError::InvalidTokenMint);
+
+                    for &index in members.iter() {
+                        require_keys_eq!(
+                            remaining_accounts[index * 3 + 2].key(),
+                            markets[index].vault_address(remaining_accounts[index * 3].key)?,
+                            CryptoBetError::MissingVault
+                        );
+                    }
+                    let hub_vault = &remaining_accounts[hub * 3 + 2];
+
+                    let cpi_accounts = Transfer {
+                        from: user_token_account.to_account_info(),
+                        to: hub_vault.clone(),
+                        authority: ctx.accounts.user.to_account_info(),
+                    };
+                    let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+                    token::transfer(cpi_ctx, total)?;
+
+                    // The hub market then pays each member's share into that member's vault
+                    let hub_vault: Account<'info, TokenAccount> = Account::try_from(hub_vault)?;
+                    for &index in members.iter().skip(1) {
+                        let member_vault: Account<'info, TokenAccount> =
+                            Account::try_from(&remaining_accounts[index * 3 + 2])?;
+                        transfer_from_market(
+                            &markets[hub],
+                            &remaining_accounts[index * 3],
+                            Some(&member_vault),
+                            Some(&hub_vault),
+                            Some(token_program),
+                            debits[index],
+                        )?;
+                    }
+                }
+            }
+        }
+
+        for market in markets.iter() {
+            market.exit(ctx.program_id)?;
+        }
+        for position in positions.iter() {
+            position.exit(ctx.program_id)?;
+        }
+
+        Ok(())
+    }
+
     /// Resolve a market with the final outcome
     /// Only the market authority can resolve markets
     pub fn resolve_market(
@@ -216,9 +798,36 @@ pub mod crypto_bet {
         require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
         require!(Clock::get()?.unix_timestamp >= market.resolution_time, CryptoBetError::MarketNotExpired);
         require!(market.authority == ctx.accounts.authority.key(), CryptoBetError::UnauthorizedResolver);
+        require!(!market.is_scalar(), CryptoBetError::ScalarMarketUnsupported);
+        market.require_parent_condition(ctx.accounts.parent_market.as_ref())?;
+
+        // Grouped markets: at most one market in the group resolves YES
+        if market.group != Pubkey::default() {
+            let group = ctx.accounts.market_group
+                .as_mut()
+                .ok_or(CryptoBetError::MissingMarketGroup)?;
+            require_keys_eq!(group.key(), market.group, CryptoBetError::InvalidMarketGroup);
+            if outcome {
+                require!(group.winner.is_none(), CryptoBetError::GroupAlreadyHasWinner);
+                group.winner = Some(market.key());
+            }
+        }
 
         market.state = MarketState::Resolved;
         market.resolved_outcome = Some(outcome);
+        market.total_owed = market.total_collateral()?;
+        market.extend_claim_deadline()?;
+
+        // Fixed-odds markets: the house only needs to keep the winning side's payouts
+        if market.is_fixed_odds() {
+            let house_pool = ctx.accounts.house_pool
+                .as_mut()
+                .ok_or(CryptoBetError::MissingHousePool)?;
+            require!(house_pool.market_type == market.market_type, CryptoBetError::InvalidHousePool);
+            require_keys_eq!(house_pool.accepted_mint, market.accepted_mint, CryptoBetError::InvalidHousePool);
+            let liability = if outcome { market.yes_liability } else { market.no_liability };
+            market.set_house_reserved(house_pool, liability)?;
+        }
 
         emit!(MarketResolved {
             market: market.key(),
@@ -230,14 +839,82 @@ pub mod crypto_bet {
         Ok(())
     }
 
+    /// Resolve a scalar market with the observed value
+    /// Only the market authority can resolve markets
+    pub fn resolve_scalar_market(
+        ctx: Context<ResolveMarket>,
+        value: i64,
+    ) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+        require!(Clock::get()?.unix_timestamp >= market.resolution_time, CryptoBetError::MarketNotExpired);
+        require!(market.authority == ctx.accounts.authority.key(), CryptoBetError::UnauthorizedResolver);
+        require!(market.is_scalar(), CryptoBetError::NotScalarMarket);
+        market.require_parent_condition(ctx.accounts.parent_market.as_ref())?;
+
+        market.state = MarketState::Resolved;
+        market.resolved_value = Some(value);
+        market.total_owed = market.total_collateral()?;
+        market.extend_claim_deadline()?;
+
+        let (long_pot, short_pot) = market.scalar_pots()?;
+        emit!(ScalarMarketResolved {
+            market: market.key(),
+            value,
+            long_pot,
+            short_pot,
+            market_type: market.market_type,
+        });
+
+        Ok(())
+    }
+
     /// Cancel a market before resolution
     /// Only the market authority can cancel markets
     pub fn cancel_market(ctx: Context<CancelMarket>) -> Result<()> {
         let market = &mut ctx.accounts.market;
         require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
         require!(market.authority == ctx.accounts.authority.key(), CryptoBetError::UnauthorizedResolver);
+        require!(!market.has_basket_activity(), CryptoBetError::GroupBasketOutstanding);
+
+        market.state = MarketState::Cancelled;
+        market.total_owed = market.total_collateral()?;
+        market.extend_claim_deadline()?;
+
+        // Fixed-odds markets: the house refunds every stake
+        if market.is_fixed_odds() {
+            let house_pool = ctx.accounts.house_pool
+                .as_mut()
+                .ok_or(CryptoBetError::MissingHousePool)?;
+            require!(house_pool.market_type == market.market_type, CryptoBetError::InvalidHousePool);
+            require_keys_eq!(house_pool.accepted_mint, market.accepted_mint, CryptoBetError::InvalidHousePool);
+            let stakes = market.house_stakes;
+            market.set_house_reserved(house_pool, stakes)?;
+        }
+
+        emit!(MarketCancelled {
+            market: market.key(),
+            total_volume: market.total_volume,
+            market_type: market.market_type,
+        });
+
+        Ok(())
+    }
+
+    /// Void a conditional market whose parent condition failed
+    /// Permissionless: anyone can void once the parent resolves the other way or is cancelled
+    pub fn void_conditional_market(ctx: Context<VoidConditionalMarket>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+        require!(
+            market.parent_condition(Some(&ctx.accounts.parent_market))? == Some(false),
+            CryptoBetError::ParentConditionNotFailed
+        );
+        require!(!market.has_basket_activity(), CryptoBetError::GroupBasketOutstanding);
 
         market.state = MarketState::Cancelled;
+        market.total_owed = market.total_collateral()?;
+        market.extend_claim_deadline()?;
 
         emit!(MarketCancelled {
             market: market.key(),
@@ -251,178 +928,488 @@ pub mod crypto_bet {
     /// Claim winnings from a resolved market (dual-mode support)
     /// Users can claim their share of the winning pool
     pub fn claim_winnings(ctx: Context<ClaimWinnings>) -> Result<()> {
-        let market = &ctx.accounts.market;
-        let position = &mut ctx.accounts.position;
+        let market = &mut ctx.accounts.market;
 
         require!(market.state == MarketState::Resolved, CryptoBetError::MarketNotResolved);
-        require!(position.user == ctx.accounts.user.key(), CryptoBetError::InvalidPosition);
+        require!(Clock::get()?.unix_timestamp < market.claim_deadline, CryptoBetError::ClaimPeriodEnded);
+        require!(!market.awaiting_group_settlement(), CryptoBetError::GroupNotSettled);
+        require!(!market.is_fixed_odds(), CryptoBetError::FixedOddsUnsupported);
 
-        require!(!position.claimed, CryptoBetError::AlreadyClaimed);
+        let (payout, creator_fee, settled_shares, settled_outcome) = if market.is_scalar() {
+            // Scalar markets are never tokenized, so everything lives on the position
+            let position = ctx.accounts.position
+                .as_ref()
+                .ok_or(CryptoBetError::MissingPosition)?;
+            require!(position.user == ctx.accounts.user.key(), CryptoBetError::InvalidPosition);
+            require!(!position.claimed, CryptoBetError::AlreadyClaimed);
 
-        let resolved_outcome = market.resolved_outcome.ok_or(CryptoBetError::MarketNotResolved)?;
-        
-        // Calculate winnings based on the resolved outcome
-        let winning_shares = if resolved_outcome {
-            position.yes_shares
+            let (payout, creator_fee, settled_shares) = market.scalar_settlement(position)?;
+            (payout, creator_fee, settled_shares, None)
         } else {
-            position.no_shares
-        };
+            let resolved_outcome = market.resolved_outcome.ok_or(CryptoBetError::MarketNotResolved)?;
 
-        require!(winning_shares > 0, CryptoBetError::NoWinningShares);
+            // Tokenized markets: burn whatever winning outcome tokens the caller holds
+            let mut token_shares = 0u64;
+            if market.is_tokenized() {
+                if let (Some(winning_mint), Some(user_outcome_token_account)) = (
+                    ctx.accounts.winning_mint.as_ref(),
+                    ctx.accounts.user_outcome_token_account.as_ref(),
+                ) {
+                    require!(
+                        winning_mint.key() == market.outcome_mint(resolved_outcome),
+                        CryptoBetError::InvalidOutcomeMint
+                    );
+                    require!(
+                        user_outcome_token_account.mint == winning_mint.key(),
+                        CryptoBetError::InvalidTokenMint
+                    );
 
-        // Use total pool for winnings calculation
-        let total_winning_pool = market.total_yes_amount
-            .checked_add(market.total_no_amount)
-            .ok_or(CryptoBetError::MathOverflow)?;
+                    token_shares = user_outcome_token_account.amount;
+                    if token_shares > 0 {
+                        let token_program = ctx.accounts.token_program
+                            .as_ref()
+                            .ok_or(CryptoBetError::MissingTokenProgram)?;
 
-        // Use correct share totals for calculation
-        let total_winning_shares = if resolved_outcome {
-            market.total_yes_shares
-        } else {
-            market.total_no_shares
-        };
+                        let cpi_accounts = Burn {
+                            mint: winning_mint.to_account_info(),
+                            from: user_outcome_token_account.to_account_info(),
+                            authority: ctx.accounts.user.to_account_info(),
+                        };
+                        let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+                        token::burn(cpi_ctx, token_shares)?;
+                    }
+                }
+            }
 
-        require!(total_winning_shares > 0, CryptoBetError::NoWinningShares);
+            // Untokenized shares and complete sets live on the position
+            let mut position_shares = 0u64;
+            let mut winning_set_shares = 0u64;
+            match ctx.accounts.position.as_ref() {
+                Some(position) => {
+                    require!(position.user == ctx.accounts.user.key(), CryptoBetError::InvalidPosition);
+                    require!(!position.claimed || token_shares > 0, CryptoBetError::AlreadyClaimed);
 
-        // Calculate proportional winnings: (user_winning_shares / total_winning_shares) * total_pool
-        let winnings = (winning_shares as u128)
-            .checked_mul(total_winning_pool as u128)
-            .ok_or(CryptoBetError::MathOverflow)?
-            .checked_div(total_winning_shares as u128)
-            .ok_or(CryptoBetError::DivisionByZero)? as u64;
+                    if !position.claimed {
+                        // Resting asks escrow set shares that cancel_order can only return to an unclaimed position
+                        require!(position.open_orders == 0, CryptoBetError::OpenOrdersOutstanding);
+                        (position_shares, winning_set_shares) = position.winning_shares(resolved_outcome);
+                    }
+                }
+                None => require!(token_shares > 0, CryptoBetError::MissingPosition),
+            }
 
-        require!(winnings > 0, CryptoBetError::NoWinningsAvailable);
+            // Calculate winnings based on the resolved outcome
+            let winning_shares = position_shares
+                .checked_add(token_shares)
+                .ok_or(CryptoBetError::MathOverflow)?;
 
-        // Use correct share totals for calculation
-        let total_winning_shares = if resolved_outcome {
-            market.total_yes_shares
-        } else {
-            market.total_no_shares
+            require!(winning_shares > 0 || winning_set_shares > 0, CryptoBetError::NoWinningShares);
+
+            let (payout, creator_fee) = market.winning_payout(winning_shares, winning_set_shares)?;
+            let settled_shares = winning_shares
+                .checked_add(winning_set_shares)
+                .ok_or(CryptoBetError::MathOverflow)?;
+            (payout, creator_fee, settled_shares, Some(resolved_outcome))
         };
+        require!(payout > 0, CryptoBetError::NoWinningsAvailable);
 
-        require!(total_winning_shares > 0, CryptoBetError::NoWinningShares);
+        // Stable winnings leave the vault under the market's signature
+        transfer_from_market(
+            market,
+            &ctx.accounts.user.to_account_info(),
+            ctx.accounts.user_token_account.as_ref(),
+            ctx.accounts.market_vault.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+            payout,
+        )?;
 
-        // Calculate proportional winnings: (user_winning_shares / total_winning_shares) * total_pool
-        let winnings = (winning_shares as u128)
-            .checked_mul(total_winning_pool as u128)
-            .ok_or(CryptoBetError::MathOverflow)?
-            .checked_div(total_winning_shares as u128)
-            .ok_or(CryptoBetError::DivisionByZero)? as u64;
+        // Mark position as claimed to prevent double claiming
+        if let Some(position) = ctx.accounts.position.as_mut() {
+            position.mark_settled(settled_outcome);
+        }
 
-        require!(winnings > 0, CryptoBetError::NoWinningsAvailable);
+        market.record_payout(payout, creator_fee, settled_shares)?;
 
-        // Prevent double claiming
-        require!(
-            (resolved_outcome && position.yes_shares > 0) || (!resolved_outcome && position.no_shares > 0),
-            CryptoBetError::AlreadyClaimed
-        );
+        emit!(WinningsClaimed {
+            market: market.key(),
+            user: ctx.accounts.user.key(),
+            amount: payout,
+            creator_fee,
+            market_type: market.market_type,
+        });
 
-        // Transfer winnings based on market type
-        match market.market_type {
-            MarketType::Degen => {
-                // SOL transfer from market to user
-                **market.to_account_info().try_borrow_mut_lamports()? = market
-                    .to_account_info()
-                    .lamports()
-                    .checked_sub(winnings)
-                    .ok_or(CryptoBetError::InsufficientFunds)?;
+        Ok(())
+    }
+
+    /// Claim refund from a cancelled market (dual-mode support)
+    /// Users can get their original bet amount back
+    pub fn claim_refund(ctx: Context<ClaimRefund>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+
+        require!(market.state == MarketState::Cancelled, CryptoBetError::MarketNotCancelled);
+        require!(Clock::get()?.unix_timestamp < market.claim_deadline, CryptoBetError::ClaimPeriodEnded);
+        require!(!market.is_fixed_odds(), CryptoBetError::FixedOddsUnsupported);
+
+        // Tokenized markets: every outcome token was bought at par, so burn and refund 1:1
+        let mut token_refund = 0u64;
+        if market.is_tokenized() {
+            let outcome_accounts = [
+                (market.yes_mint, &ctx.accounts.yes_mint, &ctx.accounts.user_yes_token_account),
+                (market.no_mint, &ctx.accounts.no_mint, &ctx.accounts.user_no_token_account),
+            ];
+            for (expected_mint, outcome_mint, user_outcome_token_account) in outcome_accounts {
+                let (Some(outcome_mint), Some(user_outcome_token_account)) =
+                    (outcome_mint.as_ref(), user_outcome_token_account.as_ref())
+                else {
+                    continue;
+                };
+                require!(outcome_mint.key() == expected_mint, CryptoBetError::InvalidOutcomeMint);
+                require!(
+                    user_outcome_token_account.mint == outcome_mint.key(),
+                    CryptoBetError::InvalidTokenMint
+                );
+
+                let amount = user_outcome_token_account.amount;
+                if amount == 0 {
+                    continue;
+                }
 
-                **ctx.accounts.user.to_account_info().try_borrow_mut_lamports()? = ctx
-                    .accounts
-                    .user
-                    .to_account_info()
-                    .lamports()
-                    .checked_add(winnings)
-                    .ok_or(CryptoBetError::MathOverflow)?;
-            }
-            MarketType::Stable => {
-                // USDC transfer from vault to user
-                let user_token_account = ctx.accounts.user_token_account
-                    .as_ref()
-                    .ok_or(CryptoBetError::MissingTokenAccount)?;
-                let market_vault = ctx.accounts.market_vault
-                    .as_ref()
-                    .ok_or(CryptoBetError::MissingVault)?;
                 let token_program = ctx.accounts.token_program
                     .as_ref()
                     .ok_or(CryptoBetError::MissingTokenProgram)?;
 
-                // Create PDA signer for vault
-                let market_key = market.key();
-                let seeds = &[
-                    b"vault",
-                    market_key.as_ref(),
-                    &[ctx.bumps.market_vault.unwrap_or(0)]
-                ];
-                let signer = &[&seeds[..]];
-
-                let cpi_accounts = Transfer {
-                    from: market_vault.to_account_info(),
-                    to: user_token_account.to_account_info(),
-                    authority: market_vault.to_account_info(), // Vault is its own authority
+                let cpi_accounts = Burn {
+                    mint: outcome_mint.to_account_info(),
+                    from: user_outcome_token_account.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
                 };
-                let cpi_ctx = CpiContext::new_with_signer(
-                    token_program.to_account_info(),
-                    cpi_accounts,
-                    signer
-                );
-                token::transfer(cpi_ctx, winnings)?;
+                let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+                token::burn(cpi_ctx, amount)?;
+
+                token_refund = token_refund
+                    .checked_add(amount)
+                    .ok_or(CryptoBetError::MathOverflow)?;
             }
         }
 
-        // Mark position as claimed to prevent double claiming
-        if resolved_outcome {
-            position.yes_shares = 0;
-        } else {
-            position.no_shares = 0;
+        let mut position_refund = 0u64;
+        let mut position_shares = 0u64;
+        match ctx.accounts.position.as_ref() {
+            Some(position) => {
+                require!(position.user == ctx.accounts.user.key(), CryptoBetError::InvalidPosition);
+                require!(!position.claimed || token_refund > 0, CryptoBetError::AlreadyClaimed);
+
+                if !position.claimed {
+                    // Resting asks escrow set shares that cancel_order can only return to an unclaimed position
+                    require!(position.open_orders == 0, CryptoBetError::OpenOrdersOutstanding);
+                    (position_refund, position_shares) = position.refund()?;
+                }
+            }
+            None => require!(token_refund > 0, CryptoBetError::MissingPosition),
         }
 
-        position.claimed = true;
+        let refund_amount = position_refund
+            .checked_add(token_refund)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        require!(refund_amount > 0, CryptoBetError::NoRefundAvailable);
+
+        // Stable refunds leave the vault under the market's signature
+        transfer_from_market(
+            market,
+            &ctx.accounts.user.to_account_info(),
+            ctx.accounts.user_token_account.as_ref(),
+            ctx.accounts.market_vault.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+            refund_amount,
+        )?;
 
-        emit!(WinningsClaimed {
+        // Mark position as refunded
+        if let Some(position) = ctx.accounts.position.as_mut() {
+            position.mark_settled(None);
+        }
+
+        // Each outcome token is one share
+        let settled_shares = position_shares
+            .checked_add(token_refund)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        market.record_payout(refund_amount, 0, settled_shares)?;
+
+        emit!(RefundClaimed {
             market: market.key(),
             user: ctx.accounts.user.key(),
-            amount: winnings,
+            amount: refund_amount,
             market_type: market.market_type,
         });
 
         Ok(())
     }
 
-    /// Claim refund from a cancelled market (dual-mode support)
-    /// Users can get their original bet amount back
-    pub fn claim_refund(ctx: Context<ClaimRefund>) -> Result<()> {
-        let market = &ctx.accounts.market;
-        let position = &mut ctx.accounts.position;
+    /// Claim winnings and refunds across many markets in one transaction (dual-mode support)
+    /// Takes (market, position, vault) triples in remaining_accounts; the vault is ignored for SOL markets
+    pub fn claim_many<'info>(ctx: Context<'_, '_, 'info, 'info, ClaimMany<'info>>) -> Result<()> {
+        let remaining_accounts = ctx.remaining_accounts;
+        require!(
+            !remaining_accounts.is_empty() && remaining_accounts.len() % 3 == 0,
+            CryptoBetError::InvalidRemainingAccounts
+        );
+        require!(remaining_accounts.len() / 3 <= MAX_BATCH_SIZE, CryptoBetError::BatchTooLarge);
 
-        require!(market.state == MarketState::Cancelled, CryptoBetError::MarketNotCancelled);
-        require!(position.user == ctx.accounts.user.key(), CryptoBetError::InvalidPosition);
-        require!(position.total_invested > 0, CryptoBetError::NoRefundAvailable);
+        let user_key = ctx.accounts.user.key();
+        let now = Clock::get()?.unix_timestamp;
 
-        require!(!position.claimed, CryptoBetError::AlreadyClaimed);
+        for accounts in remaining_accounts.chunks(3) {
+            let (market_info, position_info, vault_info) = (&accounts[0], &accounts[1], &accounts[2]);
+
+            // Account::try_from checks owner and discriminator; the PDA checks bind them together
+            let mut market: Account<'info, Market> = Account::try_from(market_info)?;
+            let market_id_bytes = market.market_id.to_le_bytes();
+            let expected_market = Pubkey::create_program_address(
+                &[b"market", market_id_bytes.as_ref(), &[market.bump]],
+                ctx.program_id,
+            )
+            .map_err(|_| error!(CryptoBetError::InvalidMarketAccount))?;
+            require_keys_eq!(market_info.key(), expected_market, CryptoBetError::InvalidMarketAccount);
+
+            let mut position: Account<'info, Position> = Account::try_from(position_info)?;
+            let expected_position = Pubkey::create_program_address(
+                &[b"position", market_info.key.as_ref(), user_key.as_ref(), &[position.bump]],
+                ctx.program_id,
+            )
+            .map_err(|_| error!(CryptoBetError::InvalidPosition))?;
+            require_keys_eq!(position_info.key(), expected_position, CryptoBetError::InvalidPosition);
+
+            // Skip anything with nothing to pay so callers can pass every position they hold
+            if position.claimed
+                || position.open_orders > 0
+                || now >= market.claim_deadline
+                || market.awaiting_group_settlement()
+                || market.is_fixed_odds()
+            {
+                continue;
+            }
+            let (amount, creator_fee, settled_shares, outcome) = match market.state {
+                MarketState::Resolved if market.is_scalar() => {
+                    let (payout, creator_fee, settled_shares) = market.scalar_settlement(&position)?;
+                    (payout, creator_fee, settled_shares, None)
+                }
+                MarketState::Resolved => {
+                    let outcome = market.resolved_outcome.ok_or(CryptoBetError::MarketNotResolved)?;
+                    let (winning_shares, winning_set_shares) = position.winning_shares(outcome);
+                    if winning_shares == 0 && winning_set_shares == 0 {
+                        continue;
+                    }
+                    let (payout, creator_fee) = market.winning_payout(winning_shares, winning_set_shares)?;
+                    let settled_shares = winning_shares
+                        .checked_add(winning_set_shares)
+                        .ok_or(CryptoBetError::MathOverflow)?;
+                    (payout, creator_fee, settled_shares, Some(outcome))
+                }
+                MarketState::Cancelled => {
+                    let (refund, settled_shares) = position.refund()?;
+                    (refund, 0, settled_shares, None)
+                }
+                MarketState::Active => continue,
+            };
+            if amount == 0 {
+                continue;
+            }
+
+            // The vault comes from remaining_accounts, so bind it to this market first
+            let market_vault: Option<Account<'info, TokenAccount>> = match market.market_type {
+                MarketType::Degen => None,
+                MarketType::Stable => {
+                    require_keys_eq!(
+                        vault_info.key(),
+                        market.vault_address(market_info.key)?,
+                        CryptoBetError::MissingVault
+                    );
+                    Some(Account::try_from(vault_info)?)
+                }
+            };
+            transfer_from_market(
+                &market,
+                &ctx.accounts.user.to_account_info(),
+                ctx.accounts.user_token_account.as_ref(),
+                market_vault.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+                amount,
+            )?;
+
+            position.mark_settled(outcome);
+            market.record_payout(amount, creator_fee, settled_shares)?;
+            position.exit(ctx.program_id)?;
+            market.exit(ctx.program_id)?;
+
+            match outcome {
+                Some(_) => emit!(WinningsClaimed {
+                    market: market_info.key(),
+                    user: user_key,
+                    amount,
+                    creator_fee,
+                    market_type: market.market_type,
+                }),
+                None => emit!(RefundClaimed {
+                    market: market_info.key(),
+                    user: user_key,
+                    amount,
+                    market_type: market.market_type,
+                }),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Transfer some or all of a position's shares to another wallet
+    /// Invested amount moves proportionally so refunds stay correct
+    pub fn transfer_position(
+        ctx: Context<TransferPosition>,
+        yes_shares: u64,
+        no_shares: u64,
+    ) -> Result<()> {
+        require!(yes_shares > 0 || no_shares > 0, CryptoBetError::InvalidAmount);
+        require!(
+            ctx.accounts.recipient.key() != ctx.accounts.user.key(),
+            CryptoBetError::InvalidRecipient
+        );
+
+        let market = &mut ctx.accounts.market;
+        require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+        require!(!market.is_fixed_odds(), CryptoBetError::FixedOddsUnsupported);
+
+        let from_position = &mut ctx.accounts.from_position;
+        require!(from_position.user == ctx.accounts.user.key(), CryptoBetError::InvalidPosition);
+        require!(!from_position.claimed, CryptoBetError::AlreadyClaimed);
+        require!(
+            from_position.yes_shares >= yes_shares && from_position.no_shares >= no_shares,
+            CryptoBetError::InsufficientShares
+        );
+
+        // Move total_invested in proportion to the shares being moved
+        let held_shares = from_position.yes_shares
+            .checked_add(from_position.no_shares)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        let moved_shares = yes_shares
+            .checked_add(no_shares)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        let invested_moved = (from_position.total_invested as u128)
+            .checked_mul(moved_shares as u128)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_div(held_shares as u128)
+            .ok_or(CryptoBetError::DivisionByZero)? as u64;
+
+        from_position.yes_shares = from_position.yes_shares
+            .checked_sub(yes_shares)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        from_position.no_shares = from_position.no_shares
+            .checked_sub(no_shares)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        from_position.total_invested = from_position.total_invested
+            .checked_sub(invested_moved)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        let to_position = &mut ctx.accounts.to_position;
+        if to_position.user == Pubkey::default() {
+            // First time the recipient holds shares in this market
+            to_position.user = ctx.accounts.recipient.key();
+            to_position.market = market.key();
+            to_position.yes_shares = 0;
+            to_position.no_shares = 0;
+            to_position.set_yes_shares = 0;
+            to_position.set_no_shares = 0;
+            to_position.total_invested = 0;
+            to_position.claimed = false;
+            to_position.bump = ctx.bumps.to_position;
+            to_position.fixed_yes_payout = 0;
+            to_position.fixed_no_payout = 0;
+
+            market.open_positions = market.open_positions
+                .checked_add(1)
+                .ok_or(CryptoBetError::MathOverflow)?;
+        }
+        require!(!to_position.claimed, CryptoBetError::AlreadyClaimed);
 
-        let refund_amount = position.total_invested;
+        to_position.yes_shares = to_position.yes_shares
+            .checked_add(yes_shares)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        to_position.no_shares = to_position.no_shares
+            .checked_add(no_shares)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        to_position.total_invested = to_position.total_invested
+            .checked_add(invested_moved)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        // The recipient takes on the moved stake, so it has to fit within their limits
+        if yes_shares > 0 {
+            market.check_position_limits(to_position, BetSide::Yes)?;
+        }
+        if no_shares > 0 {
+            market.check_position_limits(to_position, BetSide::No)?;
+        }
+
+        emit!(PositionTransferred {
+            market: market.key(),
+            from: ctx.accounts.user.key(),
+            to: ctx.accounts.recipient.key(),
+            yes_shares,
+            no_shares,
+            invested_moved,
+        });
+
+        Ok(())
+    }
+
+    /// Create YES and NO SPL mints so the market's shares can be held in any wallet
+    /// Only the market authority can tokenize, and only before the first bet
+    pub fn create_outcome_mints(ctx: Context<CreateOutcomeMints>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+        require!(market.authority == ctx.accounts.authority.key(), CryptoBetError::UnauthorizedResolver);
+        require!(!market.is_tokenized(), CryptoBetError::AlreadyTokenized);
+        require!(!market.is_scalar(), CryptoBetError::ScalarMarketUnsupported);
+        require!(!market.is_fixed_odds(), CryptoBetError::FixedOddsUnsupported);
+        require!(
+            market.total_volume == 0 && market.total_complete_sets == 0,
+            CryptoBetError::MarketHasActivity
+        );
+
+        market.yes_mint = ctx.accounts.yes_mint.key();
+        market.no_mint = ctx.accounts.no_mint.key();
+
+        emit!(OutcomeMintsCreated {
+            market: market.key(),
+            yes_mint: market.yes_mint,
+            no_mint: market.no_mint,
+            market_type: market.market_type,
+        });
+
+        Ok(())
+    }
+
+    /// Split collateral into complete sets (dual-mode support)
+    /// Each unit of the accepted mint becomes one YES and one NO share that redeem at par
+    pub fn split(ctx: Context<Split>, amount: u64) -> Result<()> {
+        require!(amount > 0, CryptoBetError::InvalidAmount);
+
+        let market = &mut ctx.accounts.market;
+        require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+        require!(!market.is_scalar(), CryptoBetError::ScalarMarketUnsupported);
+        require!(!market.is_fixed_odds(), CryptoBetError::FixedOddsUnsupported);
+        require!(Clock::get()?.unix_timestamp < market.resolution_time, CryptoBetError::MarketExpired);
 
-        // Transfer refund based on market type
+        // Collateral goes to the same place as bets for the market type
         match market.market_type {
             MarketType::Degen => {
-                // SOL refund
-                **market.to_account_info().try_borrow_mut_lamports()? = market
-                    .to_account_info()
-                    .lamports()
-                    .checked_sub(refund_amount)
-                    .ok_or(CryptoBetError::InsufficientFunds)?;
-
-                **ctx.accounts.user.to_account_info().try_borrow_mut_lamports()? = ctx
-                    .accounts
-                    .user
-                    .to_account_info()
-                    .lamports()
-                    .checked_add(refund_amount)
-                    .ok_or(CryptoBetError::MathOverflow)?;
+                let cpi_context = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.user.to_account_info(),
+                        to: market.to_account_info(),
+                    },
+                );
+                anchor_lang::system_program::transfer(cpi_context, amount)?;
             }
             MarketType::Stable => {
-                // USDC transfer from vault to user
                 let user_token_account = ctx.accounts.user_token_account
                     .as_ref()
                     .ok_or(CryptoBetError::MissingTokenAccount)?;
@@ -433,272 +1420,7063 @@ pub mod crypto_bet {
                     .as_ref()
                     .ok_or(CryptoBetError::MissingTokenProgram)?;
 
-                // Create PDA signer for vault
-                let market_key = market.key();
-                let seeds = &[
-                    b"vault",
-                    market_key.as_ref(),
-                    &[ctx.bumps.market_vault.unwrap_or(0)]
-                ];
-                let signer = &[&seeds[..]];
+                require!(
+                    user_token_account.mint == market.accepted_mint,
+                    CryptoBetError::InvalidTokenMint
+                );
 
                 let cpi_accounts = Transfer {
-                    from: market_vault.to_account_info(),
-                    to: user_token_account.to_account_info(),
-                    authority: market_vault.to_account_info(),
+                    from: user_token_account.to_account_info(),
+                    to: market_vault.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
                 };
-                let cpi_ctx = CpiContext::new_with_signer(
-                    token_program.to_account_info(),
-                    cpi_accounts,
-                    signer
-                );
-                token::transfer(cpi_ctx, refund_amount)?;
+                let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+                token::transfer(cpi_ctx, amount)?;
             }
         }
 
-        // Mark position as refunded
-        position.yes_shares = 0;
-        position.no_shares = 0;
-        position.total_invested = 0;
+        market.total_complete_sets = market.total_complete_sets
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        let position = &mut ctx.accounts.position;
+        if position.user == Pubkey::default() {
+            position.user = ctx.accounts.user.key();
+            position.market = market.key();
+            position.yes_shares = 0;
+            position.no_shares = 0;
+            position.set_yes_shares = 0;
+            position.set_no_shares = 0;
+            position.total_invested = 0;
+            position.claimed = false;
+            position.bump = ctx.bumps.position;
+            position.fixed_yes_payout = 0;
+            position.fixed_no_payout = 0;
 
-        position.claimed = true;
+            market.open_positions = market.open_positions
+                .checked_add(1)
+                .ok_or(CryptoBetError::MathOverflow)?;
+        }
 
-        emit!(RefundClaimed {
+        position.set_yes_shares = position.set_yes_shares
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        position.set_no_shares = position.set_no_shares
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        market.check_exposure_limit(position.exposure()?)?;
+
+        emit!(SharesSplit {
             market: market.key(),
             user: ctx.accounts.user.key(),
-            amount: refund_amount,
+            amount,
+            total_complete_sets: market.total_complete_sets,
             market_type: market.market_type,
         });
 
         Ok(())
     }
-}
 
-// Context Structs
+    /// Merge complete sets back into collateral (dual-mode support)
+    /// Burns one YES and one NO set share per unit returned
+    pub fn merge(ctx: Context<Merge>, amount: u64) -> Result<()> {
+        require!(amount > 0, CryptoBetError::InvalidAmount);
 
-#[derive(Accounts)]
-#[instruction(market_id: u64, market_type: MarketType)]
-pub struct InitializeMarket<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + Market::LEN,
-        seeds = [b"market", &market_id.to_le_bytes()],
-        bump
-    )]
-    pub market: Account<'info, Market>,
-    
-    // Optional vault for USDC markets
-    #[account(
-        init_if_needed,
-        payer = authority,
-        token::mint = usdc_mint,
-        token::authority = market,
-        seeds = [b"vault", market.key().as_ref()],
-        bump
-    )]
-    pub market_vault: Option<Account<'info, TokenAccount>>,
-    
-    /// CHECK: USDC mint address
-    pub usdc_mint: Option<AccountInfo<'info>>,
-    
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    pub system_program: Program<'info, System>,
-    pub token_program: Option<Program<'info, Token>>,
-    pub rent: Sysvar<'info, Rent>,
-}
+        let market = &mut ctx.accounts.market;
+        let position = &mut ctx.accounts.position;
+        require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+        require!(position.user == ctx.accounts.user.key(), CryptoBetError::InvalidPosition);
+        // Basket YES shares have no collateral behind them until the group settles, so only
+        // the position's own complete sets can be merged
+        let mergeable_yes_shares = position.set_yes_shares
+            .checked_sub(position.basket_shares)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        require!(
+            mergeable_yes_shares >= amount && position.set_no_shares >= amount,
+            CryptoBetError::InsufficientShares
+        );
 
-#[derive(Accounts)]
-pub struct PlaceBet<'info> {
-    #[account(
-        mut,
-        seeds = [b"market", &market.market_id.to_le_bytes()],
-        bump = market.bump
-    )]
-    pub market: Account<'info, Market>,
-    
-    #[account(
-        init_if_needed,
-        payer = user,
-        space = 8 + Position::LEN,
-        seeds = [b"position", market.key().as_ref(), user.key().as_ref()],
-        bump
-    )]
-    pub position: Account<'info, Position>,
-    
-    #[account(mut)]
-    pub user: Signer<'info>,
-    
-    // Optional accounts for USDC markets
-    #[account(mut)]
-    pub user_token_account: Option<Account<'info, TokenAccount>>,
-    
-    #[account(
-        mut,
-        seeds = [b"vault", market.key().as_ref()],
+        transfer_from_market(
+            market,
+            &ctx.accounts.user.to_account_info(),
+            ctx.accounts.user_token_account.as_ref(),
+            ctx.accounts.market_vault.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+            amount,
+        )?;
+
+        position.set_yes_shares = position.set_yes_shares
+            .checked_sub(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        position.set_no_shares = position.set_no_shares
+            .checked_sub(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        market.total_complete_sets = market.total_complete_sets
+            .checked_sub(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        emit!(SharesMerged {
+            market: market.key(),
+            user: ctx.accounts.user.key(),
+            amount,
+            total_complete_sets: market.total_complete_sets,
+            market_type: market.market_type,
+        });
+
+        Ok(())
+    }
+
+    /// Close a settled position and return its rent to the user
+    /// Allowed once claimed, or when there is nothing left to claim
+    pub fn close_position(ctx: Context<ClosePosition>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let position = &ctx.accounts.position;
+        require!(position.user == ctx.accounts.user.key(), CryptoBetError::InvalidPosition);
+
+        let is_empty = position.yes_shares == 0
+            && position.no_shares == 0
+            && position.set_yes_shares == 0
+            && position.set_no_shares == 0
+            && position.total_invested == 0;
+
+        // Losing positions on a resolved market never claim, so they are settled as-is
+        let has_nothing_to_claim = match (market.state, market.resolved_outcome) {
+            (MarketState::Resolved, _) if market.is_scalar() && !position.claimed => {
+                // A scalar value at a bound leaves one side with an empty pot
+                let (payout, creator_fee, settled_shares) = market.scalar_settlement(position)?;
+                let nothing = payout == 0 && creator_fee == 0;
+                if nothing {
+                    // Shares that round down to nothing still count as settled so dust can be swept
+                    market.record_payout(0, 0, settled_shares)?;
+                }
+                nothing
+            }
+            (MarketState::Resolved, Some(true)) => position.yes_shares == 0 && position.set_yes_shares == 0,
+            (MarketState::Resolved, Some(false)) => position.no_shares == 0 && position.set_no_shares == 0,
+            _ => false,
+        };
+
+        require!(
+            position.claimed || is_empty || has_nothing_to_claim,
+            CryptoBetError::PositionNotSettled
+        );
+        require!(
+            position.open_orders == 0 && position.order_proceeds == 0,
+            CryptoBetError::OpenOrdersOutstanding
+        );
+        require!(
+            position.basket_shares == 0 && position.basket_collateral == 0,
+            CryptoBetError::GroupBasketOutstanding
+        );
+
+        market.open_positions = market.open_positions
+            .checked_sub(1)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        emit!(PositionClosed {
+            market: market.key(),
+            user: ctx.accounts.user.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Close a finished market and return its rent to the authority (dual-mode support)
+    /// Requires every position to be closed, or unclaimed funds to have been swept,
+    /// creator fees to have been withdrawn, and every parlay and challenge on it settled
+    pub fn close_market(ctx: Context<CloseMarket>) -> Result<()> {
+        let market = &ctx.accounts.market;
+        require!(market.authority == ctx.accounts.authority.key(), CryptoBetError::UnauthorizedResolver);
+        require!(market.state != MarketState::Active, CryptoBetError::MarketStillActive);
+        require!(market.order_escrow == 0, CryptoBetError::OpenOrdersOutstanding);
+        require!(market.creator_fees_accrued == 0, CryptoBetError::CreatorFeesOutstanding);
+        require!(market.open_parlays == 0, CryptoBetError::ParlaysOutstanding);
+        require!(market.open_challenges == 0, CryptoBetError::ChallengesOutstanding);
+
+        // Outcome tokens are not tracked per position, so tokenized markets wait for the sweep
+        let all_settled = market.open_positions == 0 && !market.is_tokenized();
+        let past_deadline = Clock::get()?.unix_timestamp >= market.claim_deadline;
+        require!(all_settled || past_deadline, CryptoBetError::PositionsOutstanding);
+
+        // Unclaimed funds and rounding dust belong to the treasury, so they must be swept first
+        require!(market.total_claimed >= market.total_owed, CryptoBetError::PositionsOutstanding);
+
+        if market.market_type == MarketType::Stable {
+            let market_vault = ctx.accounts.market_vault
+                .as_ref()
+                .ok_or(CryptoBetError::MissingVault)?;
+            let token_program = ctx.accounts.token_program
+                .as_ref()
+                .ok_or(CryptoBetError::MissingTokenProgram)?;
+
+            // Anything left in the vault was never owed to bettors, so like dust it goes to the treasury
+            if market_vault.amount > 0 {
+                let treasury_token_account = ctx.accounts.treasury_token_account
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenAccount)?;
+                require!(
+                    treasury_token_account.owner == ctx.accounts.config.treasury,
+                    CryptoBetError::InvalidTreasury
+                );
+
+                transfer_from_market(
+                    market,
+                    &treasury_token_account.to_account_info(),
+                    Some(treasury_token_account),
+                    Some(market_vault),
+                    Some(token_program),
+                    market_vault.amount,
+                )?;
+            }
+
+            // The vault is owned by the market PDA, so the market signs
+            let market_id_bytes = market.market_id.to_le_bytes();
+            let seeds = &[
+                b"market".as_ref(),
+                market_id_bytes.as_ref(),
+                &[market.bump]
+            ];
+            let signer = &[&seeds[..]];
+
+            let cpi_accounts = CloseAccount {
+                account: market_vault.to_account_info(),
+                destination: ctx.accounts.authority.to_account_info(),
+                authority: market.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                cpi_accounts,
+                signer
+            );
+            token::close_account(cpi_ctx)?;
+        }
+
+        emit!(MarketClosed {
+            market: market.key(),
+            authority: ctx.accounts.authority.key(),
+            open_positions: market.open_positions,
+            market_type: market.market_type,
+        });
+
+        Ok(())
+    }
+
+    /// Sweep funds nobody claimed before the claim deadline to the treasury (dual-mode support)
+    /// Only the protocol admin can sweep
+    pub fn sweep_unclaimed(ctx: Context<SweepUnclaimed>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let config = &ctx.accounts.config;
+        require!(config.admin == ctx.accounts.admin.key(), CryptoBetError::UnauthorizedAdmin);
+        require!(market.state != MarketState::Active, CryptoBetError::MarketStillActive);
+        require!(Clock::get()?.unix_timestamp >= market.claim_deadline, CryptoBetError::ClaimPeriodNotEnded);
+
+        // Everything owed at settlement minus everything already paid out or assigned as fees
+        let amount = market.total_owed
+            .checked_sub(market.total_claimed)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        require!(amount > 0, CryptoBetError::NothingToSweep);
+
+        // Degen sweeps pay the treasury wallet, Stable sweeps a token account it owns
+        match market.market_type {
+            MarketType::Degen => require!(
+                ctx.accounts.treasury.key() == config.treasury,
+                CryptoBetError::InvalidTreasury
+            ),
+            MarketType::Stable => {
+                let treasury_token_account = ctx.accounts.treasury_token_account
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenAccount)?;
+                require!(
+                    treasury_token_account.owner == config.treasury,
+                    CryptoBetError::InvalidTreasury
+                );
+            }
+        }
+        transfer_from_market(
+            market,
+            &ctx.accounts.treasury.to_account_info(),
+            ctx.accounts.treasury_token_account.as_ref(),
+            ctx.accounts.market_vault.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+            amount,
+        )?;
+
+        market.total_claimed = market.total_claimed
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        emit!(UnclaimedSwept {
+            market: market.key(),
+            treasury: config.treasury,
+            amount,
+            market_type: market.market_type,
+        });
+
+        Ok(())
+    }
+
+    /// Sweep rounding dust left after every share has been settled (dual-mode support)
+    /// Callable by anyone, typically the final claimant or the admin
+    pub fn sweep_dust(ctx: Context<SweepDust>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let config = &ctx.accounts.config;
+        require!(market.state != MarketState::Active, CryptoBetError::MarketStillActive);
+        require!(market.is_fully_settled()?, CryptoBetError::SharesOutstanding);
+
+        // With every share settled, whatever is still owed is exactly the floor-division residual
+        let amount = market.total_owed
+            .checked_sub(market.total_claimed)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        require!(amount > 0, CryptoBetError::NothingToSweep);
+
+        // Degen sweeps pay the treasury wallet, Stable sweeps a token account it owns
+        match market.market_type {
+            MarketType::Degen => require!(
+                ctx.accounts.treasury.key() == config.treasury,
+                CryptoBetError::InvalidTreasury
+            ),
+            MarketType::Stable => {
+                let treasury_token_account = ctx.accounts.treasury_token_account
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenAccount)?;
+                require!(
+                    treasury_token_account.owner == config.treasury,
+                    CryptoBetError::InvalidTreasury
+                );
+            }
+        }
+        transfer_from_market(
+            market,
+            &ctx.accounts.treasury.to_account_info(),
+            ctx.accounts.treasury_token_account.as_ref(),
+            ctx.accounts.market_vault.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+            amount,
+        )?;
+
+        market.total_claimed = market.total_claimed
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        emit!(DustSwept {
+            market: market.key(),
+            treasury: config.treasury,
+            amount,
+            total_paid_out: market.total_paid_out,
+            market_type: market.market_type,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw creator fees accrued from winning claims (dual-mode support)
+    /// Only the market authority (creator) can withdraw
+    pub fn withdraw_creator_fees(ctx: Context<WithdrawCreatorFees>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        require!(market.authority == ctx.accounts.authority.key(), CryptoBetError::UnauthorizedResolver);
+
+        let amount = market.creator_fees_accrued;
+        require!(amount > 0, CryptoBetError::NoCreatorFees);
+
+        transfer_from_market(
+            market,
+            &ctx.accounts.authority.to_account_info(),
+            ctx.accounts.authority_token_account.as_ref(),
+            ctx.accounts.market_vault.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+            amount,
+        )?;
+
+        market.creator_fees_accrued = 0;
+
+        emit!(CreatorFeesWithdrawn {
+            market: market.key(),
+            authority: ctx.accounts.authority.key(),
+            amount,
+            market_type: market.market_type,
+        });
+
+        Ok(())
+    }
+
+    /// Create the parlay liquidity pool for a market type (dual-mode support)
+    /// Only the protocol admin can create pools
+    pub fn initialize_parlay_pool(
+        ctx: Context<InitializeParlayPool>,
+        market_type: MarketType,
+    ) -> Result<()> {
+        require!(ctx.accounts.config.admin == ctx.accounts.admin.key(), CryptoBetError::UnauthorizedAdmin);
+
+        let config = &ctx.accounts.config;
+        config.check_collateral_mint(market_type, ctx.accounts.usdc_mint.as_ref())?;
+
+        let pool = &mut ctx.accounts.parlay_pool;
+        pool.market_type = market_type;
+        pool.accepted_mint = config.accepted_mint(market_type);
+        pool.decimals = config.decimals(market_type);
+        pool.total_liquidity = 0;
+        pool.reserved = 0;
+        pool.open_parlays = 0;
+        pool.bump = ctx.bumps.parlay_pool;
+
+        Ok(())
+    }
+
+    /// Add liquidity to a parlay pool (dual-mode support)
+    /// Only the protocol admin can fund pools
+    pub fn fund_parlay_pool(ctx: Context<FundParlayPool>, amount: u64) -> Result<()> {
+        require!(amount > 0, CryptoBetError::InvalidAmount);
+        require!(ctx.accounts.config.admin == ctx.accounts.admin.key(), CryptoBetError::UnauthorizedAdmin);
+
+        let pool = &mut ctx.accounts.parlay_pool;
+        match pool.market_type {
+            MarketType::Degen => {
+                let cpi_context = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.admin.to_account_info(),
+                        to: pool.to_account_info(),
+                    },
+                );
+                anchor_lang::system_program::transfer(cpi_context, amount)?;
+            }
+            MarketType::Stable => {
+                let admin_token_account = ctx.accounts.admin_token_account
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenAccount)?;
+                let pool_vault = ctx.accounts.pool_vault
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingVault)?;
+                let token_program = ctx.accounts.token_program
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenProgram)?;
+
+                require!(
+                    admin_token_account.mint == pool.accepted_mint,
+                    CryptoBetError::InvalidTokenMint
+                );
+
+                let cpi_accounts = Transfer {
+                    from: admin_token_account.to_account_info(),
+                    to: pool_vault.to_account_info(),
+                    authority: ctx.accounts.admin.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+                token::transfer(cpi_ctx, amount)?;
+            }
+        }
+
+        pool.total_liquidity = pool.total_liquidity
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        emit!(ParlayPoolUpdated {
+            parlay_pool: pool.key(),
+            total_liquidity: pool.total_liquidity,
+            reserved: pool.reserved,
+            market_type: pool.market_type,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw liquidity not reserved for open parlays (dual-mode support)
+    /// Only the protocol admin can withdraw, and only to itself
+    pub fn withdraw_parlay_pool(ctx: Context<WithdrawParlayPool>, amount: u64) -> Result<()> {
+        require!(amount > 0, CryptoBetError::InvalidAmount);
+        require!(ctx.accounts.config.admin == ctx.accounts.admin.key(), CryptoBetError::UnauthorizedAdmin);
+
+        let pool = &mut ctx.accounts.parlay_pool;
+        require!(amount <= pool.free_liquidity()?, CryptoBetError::InsufficientPoolLiquidity);
+
+        match pool.market_type {
+            MarketType::Degen => {
+                **pool.to_account_info().try_borrow_mut_lamports()? = pool
+                    .to_account_info()
+                    .lamports()
+                    .checked_sub(amount)
+                    .ok_or(CryptoBetError::InsufficientFunds)?;
+
+                **ctx.accounts.admin.to_account_info().try_borrow_mut_lamports()? = ctx
+                    .accounts
+                    .admin
+                    .to_account_info()
+                    .lamports()
+                    .checked_add(amount)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+            }
+            MarketType::Stable => {
+                let admin_token_account = ctx.accounts.admin_token_account
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenAccount)?;
+                let pool_vault = ctx.accounts.pool_vault
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingVault)?;
+                let token_program = ctx.accounts.token_program
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenProgram)?;
+
+                require!(
+                    admin_token_account.mint == pool.accepted_mint,
+                    CryptoBetError::InvalidTokenMint
+                );
+
+                // The pool vault is owned by the pool PDA, so the pool signs
+                let seeds = &[
+                    b"parlay_pool".as_ref(),
+                    pool.accepted_mint.as_ref(),
+                    &[pool.bump]
+                ];
+                let signer = &[&seeds[..]];
+
+                let cpi_accounts = Transfer {
+                    from: pool_vault.to_account_info(),
+                    to: admin_token_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    cpi_accounts,
+                    signer
+                );
+                token::transfer(cpi_ctx, amount)?;
+            }
+        }
+
+        pool.total_liquidity = pool.total_liquidity
+            .checked_sub(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        emit!(ParlayPoolUpdated {
+            parlay_pool: pool.key(),
+            total_liquidity: pool.total_liquidity,
+            reserved: pool.reserved,
+            market_type: pool.market_type,
+        });
+
+        Ok(())
+    }
+
+    /// Place a parlay across 2..=MAX_PARLAY_LEGS markets (dual-mode support)
+    /// Leg markets are passed, writable, in remaining_accounts in the same order as `sides`.
+    /// Each leg is priced at its market's current implied odds and the payout is reserved in the pool
+    pub fn place_parlay<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PlaceParlay<'info>>,
+        parlay_id: u64,
+        sides: Vec<BetSide>,
+        stake: u64,
+    ) -> Result<()> {
+        let decimals = ctx.accounts.parlay_pool.decimals;
+        require!(stake >= scale_limit(MIN_BET_AMOUNT, decimals), CryptoBetError::BetTooSmall);
+        require!(stake <= scale_limit(MAX_BET_AMOUNT, decimals), CryptoBetError::BetTooLarge);
+        require!(
+            sides.len() >= 2 && sides.len() <= MAX_PARLAY_LEGS,
+            CryptoBetError::InvalidParlayLegs
+        );
+        require!(
+            ctx.remaining_accounts.len() == sides.len(),
+            CryptoBetError::InvalidRemainingAccounts
+        );
+
+        let pool = &mut ctx.accounts.parlay_pool;
+        let now = Clock::get()?.unix_timestamp;
+
+        // Price every leg from its market's pools at placement time
+        let mut legs: Vec<ParlayLeg> = Vec::with_capacity(sides.len());
+        let mut payout = stake as u128;
+        for (market_info, side) in ctx.remaining_accounts.iter().zip(sides.iter()) {
+            let mut market: Account<'info, Market> = Account::try_from(market_info)?;
+            let expected_market = Pubkey::create_program_address(
+                &[b"market", market.market_id.to_le_bytes().as_ref(), &[market.bump]],
+                ctx.program_id,
+            )
+            .map_err(|_| error!(CryptoBetError::InvalidMarketAccount))?;
+            require_keys_eq!(market_info.key(), expected_market, CryptoBetError::InvalidMarketAccount);
+            require!(
+                legs.iter().all(|leg| leg.market != market_info.key()),
+                CryptoBetError::InvalidParlayLegs
+            );
+            require!(market.market_type == pool.market_type, CryptoBetError::InvalidParlayLegs);
+            require_keys_eq!(market.accepted_mint, pool.accepted_mint, CryptoBetError::InvalidParlayLegs);
+            require!(!market.is_scalar() && !side.is_scalar(), CryptoBetError::InvalidParlayLegs);
+            require!(!market.is_fixed_odds(), CryptoBetError::InvalidParlayLegs);
+            require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+            require!(now < market.resolution_time, CryptoBetError::MarketExpired);
+
+            // Thin pools give odds a single small bet can swing, so legs need depth on both sides
+            // and each leg's odds are capped
+            let min_depth = scale_limit(MIN_PARLAY_SIDE_DEPTH, market.decimals);
+            require!(
+                market.total_yes_amount >= min_depth && market.total_no_amount >= min_depth,
+                CryptoBetError::ParlayMarketTooThin
+            );
+            let odds_bps = market.implied_odds_bps(*side)?.min(MAX_PARLAY_LEG_ODDS_BPS);
+            payout = payout
+                .checked_mul(odds_bps as u128)
+                .ok_or(CryptoBetError::MathOverflow)?
+                .checked_div(BPS_DENOMINATOR as u128)
+                .ok_or(CryptoBetError::DivisionByZero)?;
+
+            legs.push(ParlayLeg {
+                market: market_info.key(),
+                side: *side,
+                odds_bps,
+            });
+
+            // The market can't close while this parlay still needs to read it at settlement
+            market.open_parlays = market.open_parlays
+                .checked_add(1)
+                .ok_or(CryptoBetError::MathOverflow)?;
+            market.exit(ctx.program_id)?;
+        }
+
+        // Combined odds are capped too, then the house keeps an edge on them
+        let max_payout = (stake as u128)
+            .checked_mul(MAX_PARLAY_ODDS_BPS as u128)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_div(BPS_DENOMINATOR as u128)
+            .ok_or(CryptoBetError::DivisionByZero)?;
+        let potential_payout: u64 = payout
+            .min(max_payout)
+            .checked_mul((BPS_DENOMINATOR - PARLAY_HOUSE_EDGE_BPS) as u128)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_div(BPS_DENOMINATOR as u128)
+            .ok_or(CryptoBetError::DivisionByZero)?
+            .try_into()
+            .map_err(|_| error!(CryptoBetError::MathOverflow))?;
+        require!(potential_payout > stake, CryptoBetError::NoOddsAvailable);
+
+        // The stake joins the pool, and the pool must be able to cover the full payout
+        let free_after_stake = pool.free_liquidity()?
+            .checked_add(stake)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        require!(potential_payout <= free_after_stake, CryptoBetError::InsufficientPoolLiquidity);
+
+        match pool.market_type {
+            MarketType::Degen => {
+                let cpi_context = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.user.to_account_info(),
+                        to: pool.to_account_info(),
+                    },
+                );
+                anchor_lang::system_program::transfer(cpi_context, stake)?;
+            }
+            MarketType::Stable => {
+                let user_token_account = ctx.accounts.user_token_account
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenAccount)?;
+                let pool_vault = ctx.accounts.pool_vault
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingVault)?;
+                let token_program = ctx.accounts.token_program
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenProgram)?;
+
+                require!(
+                    user_token_account.mint == pool.accepted_mint,
+                    CryptoBetError::InvalidTokenMint
+                );
+
+                let cpi_accounts = Transfer {
+                    from: user_token_account.to_account_info(),
+                    to: pool_vault.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+                token::transfer(cpi_ctx, stake)?;
+            }
+        }
+
+        pool.total_liquidity = pool.total_liquidity
+            .checked_add(stake)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        pool.reserved = pool.reserved
+            .checked_add(potential_payout)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        pool.open_parlays = pool.open_parlays
+            .checked_add(1)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        let parlay = &mut ctx.accounts.parlay;
+        parlay.user = ctx.accounts.user.key();
+        parlay.parlay_id = parlay_id;
+        parlay.market_type = pool.market_type;
+        parlay.accepted_mint = pool.accepted_mint;
+        parlay.legs = legs;
+        parlay.stake = stake;
+        parlay.potential_payout = potential_payout;
+        parlay.state = ParlayState::Open;
+        parlay.created_at = now;
+        parlay.bump = ctx.bumps.parlay;
+
+        emit!(ParlayPlaced {
+            parlay: parlay.key(),
+            user: parlay.user,
+            legs: parlay.legs.len() as u8,
+            stake,
+            potential_payout,
+            market_type: parlay.market_type,
+        });
+
+        Ok(())
+    }
+
+    /// Settle a parlay once its legs are decided (dual-mode support)
+    /// Permissionless, so lost parlays release their reservation; any payout goes to the parlay's owner.
+    /// Leg markets are passed, writable, in remaining_accounts in leg order. Any losing leg loses the parlay;
+    /// cancelled legs are dropped and the payout repriced without them, down to a stake refund
+    pub fn settle_parlay<'info>(ctx: Context<'_, '_, 'info, 'info, SettleParlay<'info>>) -> Result<()> {
+        let pool = &mut ctx.accounts.parlay_pool;
+        let parlay = &mut ctx.accounts.parlay;
+        require!(parlay.state == ParlayState::Open, CryptoBetError::ParlayAlreadySettled);
+        require!(
+            ctx.remaining_accounts.len() == parlay.legs.len(),
+            CryptoBetError::InvalidRemainingAccounts
+        );
+
+        let mut lost = false;
+        let mut undecided = false;
+        let mut payout = parlay.potential_payout as u128;
+        for (market_info, leg) in ctx.remaining_accounts.iter().zip(parlay.legs.iter()) {
+            require_keys_eq!(market_info.key(), leg.market, CryptoBetError::InvalidMarketAccount);
+            let mut market: Account<'info, Market> = Account::try_from(market_info)?;
+
+            match market.state {
+                MarketState::Active => undecided = true,
+                MarketState::Resolved => {
+                    let outcome = market.resolved_outcome.ok_or(CryptoBetError::MarketNotResolved)?;
+                    if outcome != (leg.side == BetSide::Yes) {
+                        lost = true;
+                    }
+                }
+                MarketState::Cancelled => {
+                    // Reprice without this leg
+                    payout = payout
+                        .checked_mul(BPS_DENOMINATOR as u128)
+                        .ok_or(CryptoBetError::MathOverflow)?
+                        .checked_div(leg.odds_bps as u128)
+                        .ok_or(CryptoBetError::DivisionByZero)?;
+                }
+            }
+
+            market.open_parlays = market.open_parlays
+                .checked_sub(1)
+                .ok_or(CryptoBetError::MathOverflow)?;
+            market.exit(ctx.program_id)?;
+        }
+
+        // A single losing leg settles the parlay even while other legs are open
+        require!(lost || !undecided, CryptoBetError::ParlayNotSettled);
+
+        let amount: u64 = if lost {
+            0
+        } else {
+            // Never pay less than the stake back when every remaining leg was cancelled
+            let repriced: u64 = payout
+                .try_into()
+                .map_err(|_| error!(CryptoBetError::MathOverflow))?;
+            repriced.max(parlay.stake).min(parlay.potential_payout)
+        };
+
+        if amount > 0 {
+            match pool.market_type {
+                MarketType::Degen => {
+                    **pool.to_account_info().try_borrow_mut_lamports()? = pool
+                        .to_account_info()
+                        .lamports()
+                        .checked_sub(amount)
+                        .ok_or(CryptoBetError::InsufficientFunds)?;
+
+                    **ctx.accounts.user.to_account_info().try_borrow_mut_lamports()? = ctx
+                        .accounts
+                        .user
+                        .to_account_info()
+                        .lamports()
+                        .checked_add(amount)
+                        .ok_or(CryptoBetError::MathOverflow)?;
+                }
+                MarketType::Stable => {
+                    let user_token_account = ctx.accounts.user_token_account
+                        .as_ref()
+                        .ok_or(CryptoBetError::MissingTokenAccount)?;
+                    let pool_vault = ctx.accounts.pool_vault
+                        .as_ref()
+                        .ok_or(CryptoBetError::MissingVault)?;
+                    let token_program = ctx.accounts.token_program
+                        .as_ref()
+                        .ok_or(CryptoBetError::MissingTokenProgram)?;
+
+                    require!(
+                        user_token_account.mint == pool.accepted_mint,
+                        CryptoBetError::InvalidTokenMint
+                    );
+                    require_keys_eq!(user_token_account.owner, parlay.user, CryptoBetError::InvalidTokenAccountOwner);
+
+                    // The pool vault is owned by the pool PDA, so the pool signs
+                    let seeds = &[
+                        b"parlay_pool".as_ref(),
+                        pool.accepted_mint.as_ref(),
+                        &[pool.bump]
+                    ];
+                    let signer = &[&seeds[..]];
+
+                    let cpi_accounts = Transfer {
+                        from: pool_vault.to_account_info(),
+                        to: user_token_account.to_account_info(),
+                        authority: pool.to_account_info(),
+                    };
+                    let cpi_ctx = CpiContext::new_with_signer(
+                        token_program.to_account_info(),
+                        cpi_accounts,
+                        signer
+                    );
+                    token::transfer(cpi_ctx, amount)?;
+                }
+            }
+        }
+
+        pool.total_liquidity = pool.total_liquidity
+            .checked_sub(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        pool.reserved = pool.reserved
+            .checked_sub(parlay.potential_payout)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        pool.open_parlays = pool.open_parlays.saturating_sub(1);
+
+        parlay.state = if lost {
+            ParlayState::Lost
+        } else if amount > parlay.stake {
+            ParlayState::Won
+        } else {
+            ParlayState::Refunded
+        };
+
+        emit!(ParlaySettled {
+            parlay: parlay.key(),
+            user: parlay.user,
+            state: parlay.state,
+            amount,
+            market_type: parlay.market_type,
+        });
+
+        Ok(())
+    }
+
+    /// Create a group of mutually exclusive binary markets (dual-mode support)
+    /// At most one market in the group may resolve YES
+    pub fn initialize_market_group(
+        ctx: Context<InitializeMarketGroup>,
+        group_id: u64,
+        market_type: MarketType,
+    ) -> Result<()> {
+        let group = &mut ctx.accounts.market_group;
+        group.authority = ctx.accounts.authority.key();
+        group.group_id = group_id;
+        group.market_type = market_type;
+        group.markets = Vec::new();
+        group.winner = None;
+        group.settled = false;
+        group.bump = ctx.bumps.market_group;
+        group.basket_converted = false;
+
+        emit!(MarketGroupCreated {
+            market_group: group.key(),
+            group_id,
+            authority: group.authority,
+            market_type,
+        });
+
+        Ok(())
+    }
+
+    /// Add a fresh binary market to a group
+    /// The signer must own both the group and the market
+    pub fn add_market_to_group(ctx: Context<AddMarketToGroup>) -> Result<()> {
+        let group = &mut ctx.accounts.market_group;
+        let market = &mut ctx.accounts.market;
+        require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+        require!(market.group == Pubkey::default(), CryptoBetError::MarketAlreadyGrouped);
+        require!(!market.is_scalar(), CryptoBetError::ScalarMarketUnsupported);
+        require!(!market.is_tokenized(), CryptoBetError::TokenizedMarketUnsupported);
+        require!(!market.is_fixed_odds(), CryptoBetError::FixedOddsUnsupported);
+        require!(market.market_type == group.market_type, CryptoBetError::InvalidMarketGroup);
+        require!(group.markets.len() < MAX_GROUP_MARKETS, CryptoBetError::MarketGroupFull);
+        require!(!group.basket_converted, CryptoBetError::MarketGroupConverted);
+
+        market.group = group.key();
+        group.markets.push(market.key());
+
+        emit!(MarketAddedToGroup {
+            market_group: group.key(),
+            market: market.key(),
+            index: (group.markets.len() - 1) as u8,
+        });
+
+        Ok(())
+    }
+
+    /// Convert NO complete-set shares on every other market in a group into YES on one market
+    /// Takes (market, position) pairs in remaining_accounts for every group market, in group order.
+    /// With n markets, `amount` NO on each of the other n-1 is worth `amount` YES on the target plus
+    /// (n-2) * `amount` of collateral, since at most one of those n-1 markets can resolve YES.
+    /// Both are backed by the converted NO collateral once the group settles
+    pub fn convert_no_basket<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ConvertNoBasket<'info>>,
+        target_index: u8,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, CryptoBetError::InvalidAmount);
+
+        let group = &mut ctx.accounts.market_group;
+        let target_index = target_index as usize;
+        require!(group.markets.len() >= 2, CryptoBetError::InvalidMarketGroup);
+        require!(target_index < group.markets.len(), CryptoBetError::InvalidMarketGroup);
+        require!(
+            ctx.remaining_accounts.len() == group.markets.len() * 2,
+            CryptoBetError::InvalidRemainingAccounts
+        );
+
+        let user_key = ctx.accounts.user.key();
+        let now = Clock::get()?.unix_timestamp;
+        let collateral = amount
+            .checked_mul((group.markets.len() - 2) as u64)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        for (index, accounts) in ctx.remaining_accounts.chunks(2).enumerate() {
+            let (market_info, position_info) = (&accounts[0], &accounts[1]);
+            require_keys_eq!(market_info.key(), group.markets[index], CryptoBetError::InvalidMarketAccount);
+
+            let mut market: Account<'info, Market> = Account::try_from(market_info)?;
+            require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+            require!(now < market.resolution_time, CryptoBetError::MarketExpired);
+
+            let mut position: Account<'info, Position> = Account::try_from(position_info)?;
+            let expected_position = Pubkey::create_program_address(
+                &[b"position", market_info.key.as_ref(), user_key.as_ref(), &[position.bump]],
+                ctx.program_id,
+            )
+            .map_err(|_| error!(CryptoBetError::InvalidPosition))?;
+            require_keys_eq!(position_info.key(), expected_position, CryptoBetError::InvalidPosition);
+
+            if index == target_index {
+                position.set_yes_shares = position.set_yes_shares
+                    .checked_add(amount)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+                position.basket_shares = position.basket_shares
+                    .checked_add(amount)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+                position.basket_collateral = position.basket_collateral
+                    .checked_add(collateral)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+                market.basket_yes_shares = market.basket_yes_shares
+                    .checked_add(amount)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+                market.basket_collateral = market.basket_collateral
+                    .checked_add(collateral)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+            } else {
+                position.set_no_shares = position.set_no_shares
+                    .checked_sub(amount)
+                    .ok_or(CryptoBetError::InsufficientShares)?;
+                market.basket_no_shares = market.basket_no_shares
+                    .checked_add(amount)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+            }
+
+            market.exit(ctx.program_id)?;
+            position.exit(ctx.program_id)?;
+        }
+        group.basket_converted = true;
+
+        emit!(NoBasketConverted {
+            market_group: group.key(),
+            user: user_key,
+            target_market: group.markets[target_index],
+            amount,
+            collateral,
+        });
+
+        Ok(())
+    }
+
+    /// Settle a group once every market is decided (dual-mode support)
+    /// Takes (market, vault) pairs in remaining_accounts in group order; the vault is ignored for SOL markets.
+    /// Moves the collateral freed by converted NO shares into the winning market to back its basket YES shares,
+    /// and into every market that owes converters basket collateral
+    pub fn settle_group<'info>(ctx: Context<'_, '_, 'info, 'info, SettleGroup<'info>>) -> Result<()> {
+        let group = &mut ctx.accounts.market_group;
+        require!(!group.settled, CryptoBetError::GroupAlreadySettled);
+        require!(
+            ctx.remaining_accounts.len() == group.markets.len() * 2,
+            CryptoBetError::InvalidRemainingAccounts
+        );
+
+        let mut markets: Vec<Account<'info, Market>> = Vec::with_capacity(group.markets.len());
+        for (index, accounts) in ctx.remaining_accounts.chunks(2).enumerate() {
+            require_keys_eq!(accounts[0].key(), group.markets[index], CryptoBetError::InvalidMarketAccount);
+            let market: Account<'info, Market> = Account::try_from(&accounts[0])?;
+            require!(market.state != MarketState::Active, CryptoBetError::MarketStillActive);
+            markets.push(market);
+        }
+
+        // What each market owes converters, and what converted NO shares freed up in each.
+        // Every market except the winner resolved NO, so its converted NO collateral is surplus
+        let winner_index = group.winner.and_then(|winner| group.markets.iter().position(|key| *key == winner));
+        let mut needs = vec![0u64; markets.len()];
+        let mut supplies = vec![0u64; markets.len()];
+        for (index, market) in markets.iter_mut().enumerate() {
+            if group.winner.is_none() {
+                // No market won, so basket YES shares are refunded at par as collateral instead
+                market.basket_collateral = market.basket_collateral
+                    .checked_add(market.basket_yes_shares)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+            }
+            needs[index] = market.basket_collateral;
+            if Some(index) == winner_index {
+                needs[index] = needs[index]
+                    .checked_add(market.basket_yes_shares)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+            } else if market.state == MarketState::Resolved {
+                supplies[index] = market.basket_no_shares;
+            }
+
+            // Surplus on a market that also owes converters stays where it is
+            let kept = needs[index].min(supplies[index]);
+            needs[index] -= kept;
+            supplies[index] -= kept;
+        }
+
+        // Move the remaining surplus into the markets that still owe converters
+        let mut moved = 0u64;
+        for target in 0..markets.len() {
+            for source in 0..markets.len() {
+                let amount = needs[target].min(supplies[source]);
+                if amount == 0 {
+                    continue;
+                }
+
+                let source_info = &ctx.remaining_accounts[source * 2];
+                let target_info = &ctx.remaining_accounts[target * 2];
+                let (source_vault, target_vault) = match group.market_type {
+                    MarketType::Degen => (None, None),
+                    MarketType::Stable => {
+                        let source_vault = &ctx.remaining_accounts[source * 2 + 1];
+                        let target_vault = &ctx.remaining_accounts[target * 2 + 1];
+                        for (market_info, vault_info, vault_market) in [
+                            (source_info, source_vault, &markets[source]),
+                            (target_info, target_vault, &markets[target]),
+                        ] {
+                            require_keys_eq!(
+                                vault_info.key(),
+                                vault_market.vault_address(market_info.key)?,
+                                CryptoBetError::MissingVault
+                            );
+                        }
+                        (
+                            Some(Account::<TokenAccount>::try_from(source_vault)?),
+                            Some(Account::<TokenAccount>::try_from(target_vault)?),
+                        )
+                    }
+                };
+                transfer_from_market(
+                    &markets[source],
+                    target_info,
+                    target_vault.as_ref(),
+                    source_vault.as_ref(),
+                    ctx.accounts.token_program.as_ref(),
+                    amount,
+                )?;
+
+                markets[source].total_owed = markets[source].total_owed
+                    .checked_sub(amount)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+                markets[target].total_owed = markets[target].total_owed
+                    .checked_add(amount)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+                needs[target] -= amount;
+                supplies[source] -= amount;
+                moved = moved
+                    .checked_add(amount)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+            }
+        }
+        require!(needs.iter().all(|need| *need == 0), CryptoBetError::InsufficientFunds);
+
+        for market in markets.iter_mut() {
+            market.group_settled = true;
+            market.exit(ctx.program_id)?;
+        }
+        group.settled = true;
+
+        emit!(MarketGroupSettled {
+            market_group: group.key(),
+            winner: group.winner,
+            collateral_moved: moved,
+        });
+
+        Ok(())
+    }
+
+    /// Claim the collateral a position was credited by NO-basket conversions (dual-mode support)
+    /// If no market in the group won, the basket YES shares are paid back at par here too.
+    /// Also clears the basket record so the position can be closed
+    pub fn claim_basket_collateral(ctx: Context<ClaimBasketCollateral>) -> Result<()> {
+        let group = &ctx.accounts.market_group;
+        let market = &mut ctx.accounts.market;
+        let position = &mut ctx.accounts.position;
+        require!(market.group_settled, CryptoBetError::GroupNotSettled);
+        require!(Clock::get()?.unix_timestamp < market.claim_deadline, CryptoBetError::ClaimPeriodEnded);
+        require!(
+            position.basket_shares > 0 || position.basket_collateral > 0,
+            CryptoBetError::NoBasketCollateral
+        );
+
+        let mut amount = position.basket_collateral;
+        if group.winner.is_none() {
+            amount = amount
+                .checked_add(position.basket_shares)
+                .ok_or(CryptoBetError::MathOverflow)?;
+        }
+
+        if amount > 0 {
+            transfer_from_market(
+                market,
+                &ctx.accounts.user.to_account_info(),
+                ctx.accounts.user_token_account.as_ref(),
+                ctx.accounts.market_vault.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+                amount,
+            )?;
+            market.basket_collateral = market.basket_collateral
+                .checked_sub(amount)
+                .ok_or(CryptoBetError::MathOverflow)?;
+            market.record_payout(amount, 0, 0)?;
+        }
+
+        position.basket_shares = 0;
+        position.basket_collateral = 0;
+
+        emit!(BasketCollateralClaimed {
+            market: market.key(),
+            user: ctx.accounts.user.key(),
+            amount,
+            market_type: market.market_type,
+        });
+
+        Ok(())
+    }
+
+    /// Create a series of recurring up/down price rounds (dual-mode support)
+    /// Rounds are opened, locked and resolved by the permissionless `crank_round`
+    pub fn initialize_round_series(
+        ctx: Context<InitializeRoundSeries>,
+        series_id: u64,
+        market_type: MarketType,
+        round_length: i64,
+        buffer_seconds: i64,
+    ) -> Result<()> {
+        require!(
+            (MIN_ROUND_LENGTH..=MAX_ROUND_LENGTH).contains(&round_length),
+            CryptoBetError::InvalidRoundLength
+        );
+        require!(
+            buffer_seconds > 0 && buffer_seconds < round_length,
+            CryptoBetError::InvalidRoundLength
+        );
+        require!(
+            *ctx.accounts.price_feed.owner == PYTH_RECEIVER_PROGRAM_ID,
+            CryptoBetError::InvalidPriceFeed
+        );
+
+        let series = &mut ctx.accounts.round_series;
+        series.authority = ctx.accounts.authority.key();
+        series.series_id = series_id;
+        let config = &ctx.accounts.config;
+        config.check_collateral_mint(market_type, ctx.accounts.usdc_mint.as_ref())?;
+        series.market_type = market_type;
+        series.accepted_mint = config.accepted_mint(market_type);
+        series.decimals = config.decimals(market_type);
+        series.price_feed = ctx.accounts.price_feed.key();
+        series.round_length = round_length;
+        series.buffer_seconds = buffer_seconds;
+        series.current_epoch = 0;
+        series.bump = ctx.bumps.round_series;
+
+        emit!(RoundSeriesCreated {
+            round_series: series.key(),
+            series_id,
+            price_feed: series.price_feed,
+            round_length,
+            market_type,
+        });
+
+        Ok(())
+    }
+
+    /// Advance a round series (permissionless)
+    /// Locks the current round at the oracle price, resolves the previous round from the oracle,
+    /// and opens the next round. Rounds cranked outside their buffer are cancelled and refunded
+    pub fn crank_round(ctx: Context<CrankRound>) -> Result<()> {
+        let series = &mut ctx.accounts.round_series;
+        let now = Clock::get()?.unix_timestamp;
+        let epoch = series.current_epoch;
+
+        if epoch > 0 {
+            let price = read_oracle_price(&ctx.accounts.price_feed, now)?;
+
+            let current = ctx.accounts.current_round
+                .as_mut()
+                .ok_or(CryptoBetError::MissingRound)?;
+            require!(current.state == RoundState::Open, CryptoBetError::RoundNotOpen);
+            require!(now >= current.lock_time, CryptoBetError::RoundNotLockable);
+            let lock_deadline = current.lock_time
+                .checked_add(series.buffer_seconds)
+                .ok_or(CryptoBetError::MathOverflow)?;
+            if now <= lock_deadline {
+                current.lock_price = price;
+                current.state = RoundState::Locked;
+            } else {
+                current.state = RoundState::Cancelled;
+            }
+            emit_round_update(current);
+
+            if epoch > 1 {
+                let previous = ctx.accounts.previous_round
+                    .as_mut()
+                    .ok_or(CryptoBetError::MissingRound)?;
+                if previous.state == RoundState::Locked {
+                    let close_deadline = previous.close_time
+                        .checked_add(series.buffer_seconds)
+                        .ok_or(CryptoBetError::MathOverflow)?;
+                    if now <= close_deadline {
+                        previous.close_price = price;
+                        previous.resolve();
+                    } else {
+                        previous.state = RoundState::Cancelled;
+                    }
+                    emit_round_update(previous);
+                }
+            }
+        }
+
+        let next_epoch = epoch
+            .checked_add(1)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        let lock_time = now
+            .checked_add(series.round_length)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        let close_time = lock_time
+            .checked_add(series.round_length)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        let next = &mut ctx.accounts.next_round;
+        next.series = series.key();
+        next.epoch = next_epoch;
+        next.start_time = now;
+        next.lock_time = lock_time;
+        next.close_time = close_time;
+        next.lock_price = 0;
+        next.close_price = 0;
+        next.state = RoundState::Open;
+        next.outcome = None;
+        next.total_up_amount = 0;
+        next.total_down_amount = 0;
+        next.bump = ctx.bumps.next_round;
+        // Late cranks can cancel a round after its close time, so the claim window runs from there
+        next.claim_deadline = close_time
+            .checked_add(series.buffer_seconds)
+            .and_then(|time| time.checked_add(MIN_CLAIM_PERIOD))
+            .ok_or(CryptoBetError::MathOverflow)?;
+        next.total_claimed = 0;
+        emit_round_update(next);
+
+        series.current_epoch = next_epoch;
+
+        Ok(())
+    }
+
+    /// Bet on an open round (dual-mode support)
+    /// YES bets on the price closing above the lock price, NO below
+    pub fn place_round_bet(
+        ctx: Context<PlaceRoundBet>,
+        side: BetSide,
+        amount: u64,
+    ) -> Result<()> {
+        let series = &ctx.accounts.round_series;
+        let decimals = series.decimals;
+        require!(amount >= scale_limit(MIN_BET_AMOUNT, decimals), CryptoBetError::BetTooSmall);
+        require!(amount <= scale_limit(MAX_BET_AMOUNT, decimals), CryptoBetError::BetTooLarge);
+        require!(!side.is_scalar(), CryptoBetError::InvalidBetSide);
+
+        let round = &mut ctx.accounts.round;
+        require!(round.state == RoundState::Open, CryptoBetError::RoundNotOpen);
+        require!(Clock::get()?.unix_timestamp < round.lock_time, CryptoBetError::MarketExpired);
+
+        // Collateral for every round is held by the series
+        match series.market_type {
+            MarketType::Degen => {
+                let cpi_context = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.user.to_account_info(),
+                        to: series.to_account_info(),
+                    },
+                );
+                anchor_lang::system_program::transfer(cpi_context, amount)?;
+            }
+            MarketType::Stable => {
+                let user_token_account = ctx.accounts.user_token_account
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenAccount)?;
+                let series_vault = ctx.accounts.series_vault
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingVault)?;
+                let token_program = ctx.accounts.token_program
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenProgram)?;
+
+                require!(
+                    user_token_account.mint == series.accepted_mint,
+                    CryptoBetError::InvalidTokenMint
+                );
+
+                let cpi_accounts = Transfer {
+                    from: user_token_account.to_account_info(),
+                    to: series_vault.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+                token::transfer(cpi_ctx, amount)?;
+            }
+        }
+
+        round.record_bet(side, amount)?;
+
+        // Rounds reuse Position accounting: YES shares are UP, NO shares are DOWN
+        let position = &mut ctx.accounts.position;
+        if position.user == Pubkey::default() {
+            position.user = ctx.accounts.user.key();
+            position.market = round.key();
+            position.bump = ctx.bumps.position;
+        }
+        position.record_bet(side, amount, amount)?;
+
+        emit!(BetPlaced {
+            market: round.key(),
+            user: ctx.accounts.user.key(),
+            side,
+            amount,
+            shares: amount,
+            new_yes_total: round.total_up_amount,
+            new_no_total: round.total_down_amount,
+            market_type: series.market_type,
+        });
+
+        Ok(())
+    }
+
+    /// Claim winnings or a refund from a finished round (dual-mode support)
+    pub fn claim_round(ctx: Context<ClaimRound>) -> Result<()> {
+        let series = &ctx.accounts.round_series;
+        let round = &mut ctx.accounts.round;
+        let position = &mut ctx.accounts.position;
+        require!(position.user == ctx.accounts.user.key(), CryptoBetError::InvalidPosition);
+        require!(!position.claimed, CryptoBetError::AlreadyClaimed);
+        require!(Clock::get()?.unix_timestamp < round.claim_deadline, CryptoBetError::ClaimPeriodEnded);
+
+        let amount = match (round.state, round.outcome) {
+            (RoundState::Resolved, Some(up)) => {
+                let (winning_shares, _) = position.winning_shares(up);
+                require!(winning_shares > 0, CryptoBetError::NoWinningShares);
+                let total_winning = if up { round.total_up_amount } else { round.total_down_amount };
+                let pool = round.total_up_amount
+                    .checked_add(round.total_down_amount)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+                (winning_shares as u128)
+                    .checked_mul(pool as u128)
+                    .ok_or(CryptoBetError::MathOverflow)?
+                    .checked_div(total_winning as u128)
+                    .ok_or(CryptoBetError::DivisionByZero)? as u64
+            }
+            (RoundState::Cancelled, _) => position.refund()?.0,
+            _ => return err!(CryptoBetError::RoundNotFinished),
+        };
+        require!(amount > 0, CryptoBetError::NoWinningsAvailable);
+
+        transfer_from_series(
+            series,
+            &ctx.accounts.user.to_account_info(),
+            ctx.accounts.user_token_account.as_ref(),
+            ctx.accounts.series_vault.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+            amount,
+        )?;
+        round.total_claimed = round.total_claimed
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        position.mark_settled(None);
+
+        emit!(WinningsClaimed {
+            market: round.key(),
+            user: ctx.accounts.user.key(),
+            amount,
+            creator_fee: 0,
+            market_type: series.market_type,
+        });
+
+        Ok(())
+    }
+
+    /// Sweep round funds nobody claimed before the claim deadline to the treasury (dual-mode support)
+    /// Only the protocol admin can sweep
+    pub fn sweep_round(ctx: Context<SweepRound>) -> Result<()> {
+        let series = &ctx.accounts.round_series;
+        let round = &mut ctx.accounts.round;
+        let config = &ctx.accounts.config;
+        require!(
+            matches!(round.state, RoundState::Resolved | RoundState::Cancelled),
+            CryptoBetError::RoundNotFinished
+        );
+        require!(Clock::get()?.unix_timestamp >= round.claim_deadline, CryptoBetError::ClaimPeriodNotEnded);
+        require!(ctx.accounts.treasury.key() == config.treasury, CryptoBetError::InvalidTreasury);
+        if let Some(treasury_token_account) = ctx.accounts.treasury_token_account.as_ref() {
+            require!(
+                treasury_token_account.owner == config.treasury,
+                CryptoBetError::InvalidTreasury
+            );
+        }
+
+        // Both sides' stakes minus everything already paid out, including rounding dust
+        let amount = round.total_up_amount
+            .checked_add(round.total_down_amount)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_sub(round.total_claimed)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        require!(amount > 0, CryptoBetError::NothingToSweep);
+
+        transfer_from_series(
+            series,
+            &ctx.accounts.treasury.to_account_info(),
+            ctx.accounts.treasury_token_account.as_ref(),
+            ctx.accounts.series_vault.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+            amount,
+        )?;
+        round.total_claimed = round.total_claimed
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        emit!(UnclaimedSwept {
+            market: round.key(),
+            treasury: config.treasury,
+            amount,
+            market_type: series.market_type,
+        });
+
+        Ok(())
+    }
+
+    /// Close a round position and return its rent to the user
+    /// Allowed once claimed, when it lost, or once the claim deadline has passed
+    pub fn close_round_position(ctx: Context<CloseRoundPosition>) -> Result<()> {
+        let round = &ctx.accounts.round;
+        let position = &ctx.accounts.position;
+
+        let has_nothing_to_claim = match (round.state, round.outcome) {
+            (RoundState::Resolved, Some(up)) => position.winning_shares(up).0 == 0,
+            _ => false,
+        };
+        let finished = matches!(round.state, RoundState::Resolved | RoundState::Cancelled);
+        let past_deadline = finished && Clock::get()?.unix_timestamp >= round.claim_deadline;
+        require!(
+            position.claimed || has_nothing_to_claim || past_deadline,
+            CryptoBetError::PositionNotSettled
+        );
+
+        emit!(PositionClosed {
+            market: round.key(),
+            user: ctx.accounts.user.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Create the house liquidity pool that backs fixed-odds markets (dual-mode support)
+    /// Only the protocol admin can create pools
+    pub fn initialize_house_pool(
+        ctx: Context<InitializeHousePool>,
+        market_type: MarketType,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        config.check_collateral_mint(market_type, ctx.accounts.usdc_mint.as_ref())?;
+
+        let house_pool = &mut ctx.accounts.house_pool;
+        house_pool.market_type = market_type;
+        house_pool.accepted_mint = config.accepted_mint(market_type);
+        house_pool.total_liquidity = 0;
+        house_pool.reserved = 0;
+        house_pool.total_shares = 0;
+        house_pool.bump = ctx.bumps.house_pool;
+        house_pool.min_reserved = 0;
+
+        Ok(())
+    }
+
+    /// Deposit liquidity into the house pool for pool shares (dual-mode support)
+    /// Shares are priced net of best-case payouts, so depositors pay for open markets' upside
+    pub fn deposit_house_liquidity(ctx: Context<DepositHouseLiquidity>, amount: u64) -> Result<()> {
+        require!(amount > 0, CryptoBetError::InvalidAmount);
+
+        let house_pool = &mut ctx.accounts.house_pool;
+        let shares = house_pool.shares_for_deposit(amount)?;
+        require!(shares > 0, CryptoBetError::InvalidAmount);
+
+        match house_pool.market_type {
+            MarketType::Degen => {
+                let cpi_context = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.depositor.to_account_info(),
+                        to: house_pool.to_account_info(),
+                    },
+                );
+                anchor_lang::system_program::transfer(cpi_context, amount)?;
+            }
+            MarketType::Stable => {
+                let depositor_token_account = ctx.accounts.depositor_token_account
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenAccount)?;
+                let house_vault = ctx.accounts.house_vault
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingVault)?;
+                let token_program = ctx.accounts.token_program
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenProgram)?;
+
+                require!(
+                    depositor_token_account.mint == house_pool.accepted_mint,
+                    CryptoBetError::InvalidTokenMint
+                );
+
+                let cpi_accounts = Transfer {
+                    from: depositor_token_account.to_account_info(),
+                    to: house_vault.to_account_info(),
+                    authority: ctx.accounts.depositor.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+                token::transfer(cpi_ctx, amount)?;
+            }
+        }
+
+        house_pool.total_liquidity = house_pool.total_liquidity
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        house_pool.total_shares = house_pool.total_shares
+            .checked_add(shares)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        let deposit = &mut ctx.accounts.house_deposit;
+        if deposit.owner == Pubkey::default() {
+            deposit.owner = ctx.accounts.depositor.key();
+            deposit.bump = ctx.bumps.house_deposit;
+        }
+        deposit.shares = deposit.shares
+            .checked_add(shares)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        emit!(HouseLiquidityChanged {
+            house_pool: house_pool.key(),
+            owner: deposit.owner,
+            amount,
+            shares,
+            deposit: true,
+            market_type: house_pool.market_type,
+        });
+
+        Ok(())
+    }
+
+    /// Redeem house pool shares for their pro-rata share of free liquidity (dual-mode support)
+    pub fn withdraw_house_liquidity(ctx: Context<WithdrawHouseLiquidity>, shares: u64) -> Result<()> {
+        require!(shares > 0, CryptoBetError::InvalidAmount);
+
+        let house_pool = &mut ctx.accounts.house_pool;
+        let deposit = &mut ctx.accounts.house_deposit;
+        require!(deposit.owner == ctx.accounts.depositor.key(), CryptoBetError::InvalidPosition);
+        require!(deposit.shares >= shares, CryptoBetError::InsufficientShares);
+
+        let amount = house_pool.amount_for_shares(shares)?;
+        require!(amount > 0, CryptoBetError::InsufficientPoolLiquidity);
+
+        match house_pool.market_type {
+            MarketType::Degen => {
+                **house_pool.to_account_info().try_borrow_mut_lamports()? = house_pool
+                    .to_account_info()
+                    .lamports()
+                    .checked_sub(amount)
+                    .ok_or(CryptoBetError::InsufficientFunds)?;
+
+                **ctx.accounts.depositor.to_account_info().try_borrow_mut_lamports()? = ctx
+                    .accounts
+                    .depositor
+                    .to_account_info()
+                    .lamports()
+                    .checked_add(amount)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+            }
+            MarketType::Stable => {
+                let depositor_token_account = ctx.accounts.depositor_token_account
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenAccount)?;
+                let house_vault = ctx.accounts.house_vault
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingVault)?;
+                let token_program = ctx.accounts.token_program
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenProgram)?;
+
+                require!(
+                    depositor_token_account.mint == house_pool.accepted_mint,
+                    CryptoBetError::InvalidTokenMint
+                );
+
+                // The house vault is owned by the house pool PDA, so the pool signs
+                let seeds = &[
+                    b"house_pool".as_ref(),
+                    house_pool.accepted_mint.as_ref(),
+                    &[house_pool.bump]
+                ];
+                let signer = &[&seeds[..]];
+
+                let cpi_accounts = Transfer {
+                    from: house_vault.to_account_info(),
+                    to: depositor_token_account.to_account_info(),
+                    authority: house_pool.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    cpi_accounts,
+                    signer
+                );
+                token::transfer(cpi_ctx, amount)?;
+            }
+        }
+
+        house_pool.total_liquidity = house_pool.total_liquidity
+            .checked_sub(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        house_pool.total_shares = house_pool.total_shares
+            .checked_sub(shares)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        deposit.shares = deposit.shares
+            .checked_sub(shares)
+            .ok_or(CryptoBetError::InsufficientShares)?;
+
+        emit!(HouseLiquidityChanged {
+            house_pool: house_pool.key(),
+            owner: deposit.owner,
+            amount,
+            shares,
+            deposit: false,
+            market_type: house_pool.market_type,
+        });
+
+        Ok(())
+    }
+
+    /// Update the odds offered on a fixed-odds market
+    /// Only the market authority can reprice; bets already placed keep their locked odds
+    pub fn set_fixed_odds(ctx: Context<SetFixedOdds>, config: FixedOddsConfig) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+        require!(market.is_fixed_odds(), CryptoBetError::NotFixedOddsMarket);
+        config.validate()?;
+
+        market.fixed_odds = Some(config);
+
+        emit!(FixedOddsUpdated {
+            market: market.key(),
+            yes_odds_bps: config.yes_odds_bps,
+            no_odds_bps: config.no_odds_bps,
+            max_exposure: config.max_exposure,
+        });
+
+        Ok(())
+    }
+
+    /// Claim a fixed-odds payout or refund from the house pool (dual-mode support)
+    /// Winners receive the payouts locked in when they bet; cancelled markets refund stakes
+    pub fn claim_fixed_odds(ctx: Context<ClaimFixedOdds>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let house_pool = &mut ctx.accounts.house_pool;
+        let position = &mut ctx.accounts.position;
+        require!(market.is_fixed_odds(), CryptoBetError::NotFixedOddsMarket);
+        require!(house_pool.market_type == market.market_type, CryptoBetError::InvalidHousePool);
+        require_keys_eq!(house_pool.accepted_mint, market.accepted_mint, CryptoBetError::InvalidHousePool);
+        require!(Clock::get()?.unix_timestamp < market.claim_deadline, CryptoBetError::ClaimPeriodEnded);
+        require!(position.user == ctx.accounts.user.key(), CryptoBetError::InvalidPosition);
+        require!(!position.claimed, CryptoBetError::AlreadyClaimed);
+
+        let (amount, outcome) = match (market.state, market.resolved_outcome) {
+            (MarketState::Resolved, Some(true)) => (position.fixed_yes_payout, Some(true)),
+            (MarketState::Resolved, Some(false)) => (position.fixed_no_payout, Some(false)),
+            (MarketState::Cancelled, _) => (position.total_invested, None),
+            _ => return err!(CryptoBetError::MarketNotResolved),
+        };
+        require!(amount > 0, CryptoBetError::NoWinningsAvailable);
+
+        match house_pool.market_type {
+            MarketType::Degen => {
+                **house_pool.to_account_info().try_borrow_mut_lamports()? = house_pool
+                    .to_account_info()
+                    .lamports()
+                    .checked_sub(amount)
+                    .ok_or(CryptoBetError::InsufficientFunds)?;
+
+                **ctx.accounts.user.to_account_info().try_borrow_mut_lamports()? = ctx
+                    .accounts
+                    .user
+                    .to_account_info()
+                    .lamports()
+                    .checked_add(amount)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+            }
+            MarketType::Stable => {
+                let user_token_account = ctx.accounts.user_token_account
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenAccount)?;
+                let house_vault = ctx.accounts.house_vault
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingVault)?;
+                let token_program = ctx.accounts.token_program
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenProgram)?;
+
+                require!(
+                    user_token_account.mint == house_pool.accepted_mint,
+                    CryptoBetError::InvalidTokenMint
+                );
+
+                // The house vault is owned by the house pool PDA, so the pool signs
+                let seeds = &[
+                    b"house_pool".as_ref(),
+                    house_pool.accepted_mint.as_ref(),
+                    &[house_pool.bump]
+                ];
+                let signer = &[&seeds[..]];
+
+                let cpi_accounts = Transfer {
+                    from: house_vault.to_account_info(),
+                    to: user_token_account.to_account_info(),
+                    authority: house_pool.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    cpi_accounts,
+                    signer
+                );
+                token::transfer(cpi_ctx, amount)?;
+            }
+        }
+
+        house_pool.total_liquidity = house_pool.total_liquidity
+            .checked_sub(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        let remaining = market.house_reserved
+            .checked_sub(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        market.set_house_reserved(house_pool, remaining)?;
+        position.mark_settled(outcome);
+
+        if outcome.is_some() {
+            emit!(WinningsClaimed {
+                market: market.key(),
+                user: ctx.accounts.user.key(),
+                amount,
+                creator_fee: 0,
+                market_type: market.market_type,
+            });
+        } else {
+            emit!(RefundClaimed {
+                market: market.key(),
+                user: ctx.accounts.user.key(),
+                amount,
+                market_type: market.market_type,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Release a fixed-odds market's unclaimed reservation back to the house pool (permissionless)
+    /// Anyone can release once the claim deadline has passed
+    pub fn release_house_reservation(ctx: Context<ReleaseHouseReservation>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        require!(market.is_fixed_odds(), CryptoBetError::NotFixedOddsMarket);
+        require!(market.state != MarketState::Active, CryptoBetError::MarketStillActive);
+        require!(
+            Clock::get()?.unix_timestamp >= market.claim_deadline,
+            CryptoBetError::ClaimPeriodNotEnded
+        );
+        require!(
+            ctx.accounts.house_pool.market_type == market.market_type,
+            CryptoBetError::InvalidHousePool
+        );
+        require_keys_eq!(
+            ctx.accounts.house_pool.accepted_mint,
+            market.accepted_mint,
+            CryptoBetError::InvalidHousePool
+        );
+
+        market.set_house_reserved(&mut ctx.accounts.house_pool, 0)?;
+
+        Ok(())
+    }
+
+    /// Create the liquidity provider pool for an accepted mint (dual-mode support)
+    /// Only the protocol admin can create pools; depositors receive LP tokens from the pool's mint
+    pub fn initialize_lp_pool(
+        ctx: Context<InitializeLpPool>,
+        market_type: MarketType,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        config.check_collateral_mint(market_type, ctx.accounts.usdc_mint.as_ref())?;
+
+        let lp_pool = &mut ctx.accounts.lp_pool;
+        lp_pool.market_type = market_type;
+        lp_pool.accepted_mint = config.accepted_mint(market_type);
+        lp_pool.lp_mint = ctx.accounts.lp_mint.key();
+        lp_pool.idle_liquidity = 0;
+        lp_pool.markets = Vec::new();
+        lp_pool.bump = ctx.bumps.lp_pool;
+
+        Ok(())
+    }
+
+    /// Deposit collateral into the LP pool for LP tokens priced at pool NAV (dual-mode support)
+    /// Takes (market, position) pairs in remaining_accounts for every market the pool has seeded
+    pub fn deposit_lp<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DepositLp<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, CryptoBetError::InvalidAmount);
+
+        let lp_pool = &mut ctx.accounts.lp_pool;
+        let nav = lp_pool.nav(ctx.remaining_accounts, ctx.program_id)?;
+        let supply = ctx.accounts.lp_mint.supply;
+        let lp_amount = if supply == 0 || nav == 0 {
+            amount
+        } else {
+            (amount as u128)
+                .checked_mul(supply as u128)
+                .ok_or(CryptoBetError::MathOverflow)?
+                .checked_div(nav as u128)
+                .ok_or(CryptoBetError::DivisionByZero)? as u64
+        };
+        require!(lp_amount > 0, CryptoBetError::InvalidAmount);
+
+        match lp_pool.market_type {
+            MarketType::Degen => {
+                let cpi_context = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.depositor.to_account_info(),
+                        to: lp_pool.to_account_info(),
+                    },
+                );
+                anchor_lang::system_program::transfer(cpi_context, amount)?;
+            }
+            MarketType::Stable => {
+                let depositor_token_account = ctx.accounts.depositor_token_account
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenAccount)?;
+                let lp_vault = ctx.accounts.lp_vault
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingVault)?;
+
+                require!(
+                    depositor_token_account.mint == lp_pool.accepted_mint,
+                    CryptoBetError::InvalidTokenMint
+                );
+
+                let cpi_accounts = Transfer {
+                    from: depositor_token_account.to_account_info(),
+                    to: lp_vault.to_account_info(),
+                    authority: ctx.accounts.depositor.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+                token::transfer(cpi_ctx, amount)?;
+            }
+        }
+
+        // The LP mint is owned by the pool PDA, so the pool signs
+        let accepted_mint = lp_pool.accepted_mint;
+        let seeds = &[
+            b"lp_pool".as_ref(),
+            accepted_mint.as_ref(),
+            &[lp_pool.bump]
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.lp_mint.to_account_info(),
+            to: ctx.accounts.depositor_lp_token_account.to_account_info(),
+            authority: lp_pool.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer
+        );
+        token::mint_to(cpi_ctx, lp_amount)?;
+
+        lp_pool.idle_liquidity = lp_pool.idle_liquidity
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        emit!(LpDeposited {
+            lp_pool: lp_pool.key(),
+            depositor: ctx.accounts.depositor.key(),
+            amount,
+            lp_amount,
+            nav,
+        });
+
+        Ok(())
+    }
+
+    /// Queue LP tokens for withdrawal (dual-mode support)
+    /// The tokens are escrowed by the pool and can be redeemed after the cooldown
+    pub fn request_lp_withdrawal(ctx: Context<RequestLpWithdrawal>, lp_amount: u64) -> Result<()> {
+        require!(lp_amount > 0, CryptoBetError::InvalidAmount);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.depositor_lp_token_account.to_account_info(),
+            to: ctx.accounts.lp_escrow.to_account_info(),
+            authority: ctx.accounts.depositor.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, lp_amount)?;
+
+        let withdrawal = &mut ctx.accounts.lp_withdrawal;
+        withdrawal.owner = ctx.accounts.depositor.key();
+        withdrawal.lp_pool = ctx.accounts.lp_pool.key();
+        withdrawal.lp_amount = lp_amount;
+        withdrawal.unlock_at = Clock::get()?.unix_timestamp
+            .checked_add(LP_WITHDRAWAL_COOLDOWN)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        withdrawal.bump = ctx.bumps.lp_withdrawal;
+
+        emit!(LpWithdrawalRequested {
+            lp_pool: withdrawal.lp_pool,
+            owner: withdrawal.owner,
+            lp_amount,
+            unlock_at: withdrawal.unlock_at,
+        });
+
+        Ok(())
+    }
+
+    /// Redeem a queued withdrawal for its pro-rata share of pool NAV (dual-mode support)
+    /// Takes (market, position) pairs in remaining_accounts for every market the pool has seeded.
+    /// Only idle liquidity can be paid out; harvest settled markets first if it falls short
+    pub fn complete_lp_withdrawal<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CompleteLpWithdrawal<'info>>,
+    ) -> Result<()> {
+        let lp_pool = &mut ctx.accounts.lp_pool;
+        let withdrawal = &ctx.accounts.lp_withdrawal;
+        require!(
+            Clock::get()?.unix_timestamp >= withdrawal.unlock_at,
+            CryptoBetError::WithdrawalCooldown
+        );
+
+        let nav = lp_pool.nav(ctx.remaining_accounts, ctx.program_id)?;
+        let amount = (withdrawal.lp_amount as u128)
+            .checked_mul(nav as u128)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_div(ctx.accounts.lp_mint.supply as u128)
+            .ok_or(CryptoBetError::DivisionByZero)? as u64;
+        require!(amount <= lp_pool.idle_liquidity, CryptoBetError::InsufficientPoolLiquidity);
+
+        let accepted_mint = lp_pool.accepted_mint;
+        let seeds = &[
+            b"lp_pool".as_ref(),
+            accepted_mint.as_ref(),
+            &[lp_pool.bump]
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.lp_mint.to_account_info(),
+            from: ctx.accounts.lp_escrow.to_account_info(),
+            authority: lp_pool.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer
+        );
+        token::burn(cpi_ctx, withdrawal.lp_amount)?;
+
+        match lp_pool.market_type {
+            MarketType::Degen => {
+                **lp_pool.to_account_info().try_borrow_mut_lamports()? = lp_pool
+                    .to_account_info()
+                    .lamports()
+                    .checked_sub(amount)
+                    .ok_or(CryptoBetError::InsufficientFunds)?;
+
+                **ctx.accounts.depositor.to_account_info().try_borrow_mut_lamports()? = ctx
+                    .accounts
+                    .depositor
+                    .to_account_info()
+                    .lamports()
+                    .checked_add(amount)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+            }
+            MarketType::Stable => {
+                let depositor_token_account = ctx.accounts.depositor_token_account
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenAccount)?;
+                let lp_vault = ctx.accounts.lp_vault
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingVault)?;
+
+                require!(
+                    depositor_token_account.mint == lp_pool.accepted_mint,
+                    CryptoBetError::InvalidTokenMint
+                );
+
+                let cpi_accounts = Transfer {
+                    from: lp_vault.to_account_info(),
+                    to: depositor_token_account.to_account_info(),
+                    authority: lp_pool.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    cpi_accounts,
+                    signer
+                );
+                token::transfer(cpi_ctx, amount)?;
+            }
+        }
+
+        lp_pool.idle_liquidity = lp_pool.idle_liquidity
+            .checked_sub(amount)
+            .ok_or(CryptoBetError::InsufficientPoolLiquidity)?;
+
+        emit!(LpWithdrawn {
+            lp_pool: lp_pool.key(),
+            owner: withdrawal.owner,
+            lp_amount: withdrawal.lp_amount,
+            amount,
+            nav,
+        });
+
+        Ok(())
+    }
+
+    /// Seed a market's AMM with pool liquidity at a YES probability (dual-mode support)
+    /// Only the protocol admin can deploy pool liquidity. The seed is split into complete sets held by
+    /// the pool's own position; the AMM reserves are drawn from them, so the pool takes the other side
+    /// of every AMM trade and absorbs the market's P&L and trading fees. Reseeding adds liquidity at
+    /// the AMM's current price, and the probability is ignored
+    pub fn seed_market(
+        ctx: Context<SeedMarket>,
+        amount: u64,
+        yes_probability_bps: u16,
+    ) -> Result<()> {
+        let lp_pool = &mut ctx.accounts.lp_pool;
+        let market = &mut ctx.accounts.market;
+        require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+        require!(Clock::get()?.unix_timestamp < market.resolution_time, CryptoBetError::MarketExpired);
+        require!(market.accepted_mint == lp_pool.accepted_mint, CryptoBetError::InvalidTokenMint);
+        require!(
+            !market.is_tokenized() && !market.is_fixed_odds() && !market.is_scalar(),
+            CryptoBetError::InvalidMarketAccount
+        );
+        require!(
+            market.amm_pool == Pubkey::default() || market.amm_pool == lp_pool.key(),
+            CryptoBetError::InvalidMarketAccount
+        );
+        require!(amount <= lp_pool.idle_liquidity, CryptoBetError::InsufficientPoolLiquidity);
+
+        // Reserves are weighted against the price: the YES price is the NO reserve's share
+        let (yes_weight, no_weight) = if market.has_amm() {
+            (market.amm_yes_reserve, market.amm_no_reserve)
+        } else {
+            require!(
+                yes_probability_bps > 0 && (yes_probability_bps as u64) < BPS_DENOMINATOR,
+                CryptoBetError::InvalidProbability
+            );
+            (
+                BPS_DENOMINATOR - yes_probability_bps as u64,
+                yes_probability_bps as u64,
+            )
+        };
+        let scaled = |weight: u64, of: u64| -> Result<u64> {
+            Ok((amount as u128)
+                .checked_mul(weight as u128)
+                .ok_or(CryptoBetError::MathOverflow)?
+                .checked_div(of as u128)
+                .ok_or(CryptoBetError::DivisionByZero)? as u64)
+        };
+        let (yes_reserve, no_reserve) = if yes_weight >= no_weight {
+            (amount, scaled(no_weight, yes_weight)?)
+        } else {
+            (scaled(yes_weight, no_weight)?, amount)
+        };
+        require!(
+            yes_reserve.min(no_reserve) >= scale_limit(MIN_BET_AMOUNT, market.decimals),
+            CryptoBetError::BetTooSmall
+        );
+
+        match lp_pool.market_type {
+            MarketType::Degen => {
+                **lp_pool.to_account_info().try_borrow_mut_lamports()? = lp_pool
+                    .to_account_info()
+                    .lamports()
+                    .checked_sub(amount)
+                    .ok_or(CryptoBetError::InsufficientFunds)?;
+
+                **market.to_account_info().try_borrow_mut_lamports()? = market
+                    .to_account_info()
+                    .lamports()
+                    .checked_add(amount)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+            }
+            MarketType::Stable => {
+                let lp_vault = ctx.accounts.lp_vault
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingVault)?;
+                let market_vault = ctx.accounts.market_vault
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingVault)?;
+                let token_program = ctx.accounts.token_program
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenProgram)?;
+
+                let accepted_mint = lp_pool.accepted_mint;
+                let seeds = &[
+                    b"lp_pool".as_ref(),
+                    accepted_mint.as_ref(),
+                    &[lp_pool.bump]
+                ];
+                let signer = &[&seeds[..]];
+
+                let cpi_accounts = Transfer {
+                    from: lp_vault.to_account_info(),
+                    to: market_vault.to_account_info(),
+                    authority: lp_pool.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    cpi_accounts,
+                    signer
+                );
+                token::transfer(cpi_ctx, amount)?;
+            }
+        }
+
+        market.total_complete_sets = market.total_complete_sets
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        market.amm_pool = lp_pool.key();
+        market.amm_yes_reserve = market.amm_yes_reserve
+            .checked_add(yes_reserve)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        market.amm_no_reserve = market.amm_no_reserve
+            .checked_add(no_reserve)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        let position = &mut ctx.accounts.position;
+        if position.user == Pubkey::default() {
+            position.user = lp_pool.key();
+            position.market = market.key();
+            position.bump = ctx.bumps.position;
+
+            market.open_positions = market.open_positions
+                .checked_add(1)
+                .ok_or(CryptoBetError::MathOverflow)?;
+        }
+        position.credit_complete_sets(amount)?;
+
+        if !lp_pool.markets.contains(&market.key()) {
+            require!(lp_pool.markets.len() < MAX_LP_MARKETS, CryptoBetError::LpMarketLimit);
+            lp_pool.markets.push(market.key());
+        }
+        lp_pool.idle_liquidity = lp_pool.idle_liquidity
+            .checked_sub(amount)
+            .ok_or(CryptoBetError::InsufficientPoolLiquidity)?;
+
+        emit!(LpMarketSeeded {
+            lp_pool: lp_pool.key(),
+            market: market.key(),
+            amount,
+            yes_reserve: market.amm_yes_reserve,
+            no_reserve: market.amm_no_reserve,
+        });
+
+        Ok(())
+    }
+
+    /// Collect the pool's payout or refund from a settled market (permissionless)
+    /// Returns the pool's P&L on the market to idle liquidity. After the claim deadline the
+    /// market's funds belong to the treasury sweep, so it is only dropped from the pool
+    pub fn harvest_lp_market(ctx: Context<HarvestLpMarket>) -> Result<()> {
+        let lp_pool = &mut ctx.accounts.lp_pool;
+        let market = &mut ctx.accounts.market;
+        let position = &mut ctx.accounts.position;
+        let market_key = market.key();
+        require!(!position.claimed, CryptoBetError::AlreadyClaimed);
+        require!(market.state != MarketState::Active, CryptoBetError::MarketStillActive);
+        market.amm_yes_reserve = 0;
+        market.amm_no_reserve = 0;
+
+        if Clock::get()?.unix_timestamp >= market.claim_deadline {
+            position.mark_settled(None);
+            lp_pool.markets.retain(|key| *key != market_key);
+
+            emit!(LpMarketHarvested {
+                lp_pool: lp_pool.key(),
+                market: market_key,
+                amount: 0,
+            });
+            return Ok(());
+        }
+        require!(!market.awaiting_group_settlement(), CryptoBetError::GroupNotSettled);
+
+        let (amount, creator_fee, settled_shares, outcome) = match market.state {
+            MarketState::Resolved if market.is_scalar() => {
+                let (payout, creator_fee, settled_shares) = market.scalar_settlement(position)?;
+                (payout, creator_fee, settled_shares, None)
+            }
+            MarketState::Resolved => {
+                let outcome = market.resolved_outcome.ok_or(CryptoBetError::MarketNotResolved)?;
+                let (winning_shares, winning_set_shares) = position.winning_shares(outcome);
+                let (payout, creator_fee) = if winning_shares > 0 || winning_set_shares > 0 {
+                    market.winning_payout(winning_shares, winning_set_shares)?
+                } else {
+                    (0, 0)
+                };
+                let settled_shares = winning_shares
+                    .checked_add(winning_set_shares)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+                (payout, creator_fee, settled_shares, None)
+            }
+            MarketState::Cancelled => {
+                let (refund, settled_shares) = position.refund()?;
+                (refund, 0, settled_shares, None)
+            }
+            MarketState::Active => return err!(CryptoBetError::MarketStillActive),
+        };
+
+        if amount > 0 {
+            transfer_from_market(
+                market,
+                &lp_pool.to_account_info(),
+                ctx.accounts.lp_vault.as_ref(),
+                ctx.accounts.market_vault.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+                amount,
+            )?;
+        }
+
+        market.record_payout(amount, creator_fee, settled_shares)?;
+        position.mark_settled(outcome);
+
+        lp_pool.idle_liquidity = lp_pool.idle_liquidity
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        lp_pool.markets.retain(|key| *key != market_key);
+
+        emit!(LpMarketHarvested {
+            lp_pool: lp_pool.key(),
+            market: market_key,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Buy YES or NO shares from a market's AMM (dual-mode support)
+    /// The collateral mints complete sets; the AMM keeps the other side and pays out shares of
+    /// `side` so the product of its reserves holds. Bought shares redeem at par like split shares
+    pub fn amm_buy(ctx: Context<AmmTrade>, side: BetSide, amount: u64, min_shares_out: u64) -> Result<()> {
+        require!(amount > 0, CryptoBetError::InvalidAmount);
+        require!(!side.is_scalar(), CryptoBetError::InvalidBetSide);
+
+        let market = &mut ctx.accounts.market;
+        require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+        require!(Clock::get()?.unix_timestamp < market.resolution_time, CryptoBetError::MarketExpired);
+        require!(market.has_amm(), CryptoBetError::AmmNotSeeded);
+
+        // Collateral goes to the same place as bets for the market type
+        match market.market_type {
+            MarketType::Degen => {
+                let cpi_context = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.user.to_account_info(),
+                        to: market.to_account_info(),
+                    },
+                );
+                anchor_lang::system_program::transfer(cpi_context, amount)?;
+            }
+            MarketType::Stable => {
+                let user_token_account = ctx.accounts.user_token_account
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenAccount)?;
+                let market_vault = ctx.accounts.market_vault
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingVault)?;
+                let token_program = ctx.accounts.token_program
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenProgram)?;
+
+                require!(
+                    user_token_account.mint == market.accepted_mint,
+                    CryptoBetError::InvalidTokenMint
+                );
+
+                let cpi_accounts = Transfer {
+                    from: user_token_account.to_account_info(),
+                    to: market_vault.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+                token::transfer(cpi_ctx, amount)?;
+            }
+        }
+
+        // The fee stays with the pool as complete sets outside the reserves
+        let fee = amm_fee(amount)?;
+        let shares = market.amm_buy(side, amount - fee)?;
+        require!(shares > 0, CryptoBetError::InvalidAmount);
+        require!(shares >= min_shares_out, CryptoBetError::SlippageExceeded);
+        market.total_complete_sets = market.total_complete_sets
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        let pool_position = &mut ctx.accounts.pool_position;
+        pool_position.credit_complete_sets(amount)?;
+        pool_position.debit_set_shares(side, shares)?;
+
+        let position = &mut ctx.accounts.position;
+        if position.user == Pubkey::default() {
+            position.user = ctx.accounts.user.key();
+            position.market = market.key();
+            position.bump = ctx.bumps.position;
+
+            market.open_positions = market.open_positions
+                .checked_add(1)
+                .ok_or(CryptoBetError::MathOverflow)?;
+        }
+        position.credit_set_shares(side, shares)?;
+        market.check_exposure_limit(position.exposure()?)?;
+
+        emit!(AmmTraded {
+            market: market.key(),
+            user: ctx.accounts.user.key(),
+            side,
+            amount,
+            shares,
+            buy: true,
+            yes_price_bps: market.amm_yes_price_bps()?,
+        });
+
+        Ok(())
+    }
+
+    /// Sell YES or NO set shares back to a market's AMM for `amount` of collateral, less the fee (dual-mode support)
+    /// The AMM merges `amount` complete sets out of its reserves after taking the shares
+    pub fn amm_sell(ctx: Context<AmmTrade>, side: BetSide, amount: u64, max_shares_in: u64) -> Result<()> {
+        require!(amount > 0, CryptoBetError::InvalidAmount);
+        require!(!side.is_scalar(), CryptoBetError::InvalidBetSide);
+
+        let market = &mut ctx.accounts.market;
+        require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+        require!(Clock::get()?.unix_timestamp < market.resolution_time, CryptoBetError::MarketExpired);
+        require!(market.has_amm(), CryptoBetError::AmmNotSeeded);
+
+        let shares = market.amm_sell(side, amount)?;
+        require!(shares > 0 && amount > amm_fee(amount)?, CryptoBetError::InvalidAmount);
+        require!(shares <= max_shares_in, CryptoBetError::SlippageExceeded);
+
+        let position = &mut ctx.accounts.position;
+        require!(position.user == ctx.accounts.user.key(), CryptoBetError::InvalidPosition);
+        position.debit_set_shares(side, shares)?;
+
+        // Only the sets paid out are burned; the fee's worth stays with the pool
+        let fee = amm_fee(amount)?;
+        let payout = amount - fee;
+        let pool_position = &mut ctx.accounts.pool_position;
+        pool_position.credit_set_shares(side, shares)?;
+        pool_position.debit_set_shares(BetSide::Yes, payout)?;
+        pool_position.debit_set_shares(BetSide::No, payout)?;
+        market.total_complete_sets = market.total_complete_sets
+            .checked_sub(payout)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        transfer_from_market(
+            market,
+            &ctx.accounts.user.to_account_info(),
+            ctx.accounts.user_token_account.as_ref(),
+            ctx.accounts.market_vault.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+            payout,
+        )?;
+
+        emit!(AmmTraded {
+            market: market.key(),
+            user: ctx.accounts.user.key(),
+            side,
+            amount: payout,
+            shares,
+            buy: false,
+            yes_price_bps: market.amm_yes_price_bps()?,
+        });
+
+        Ok(())
+    }
+
+    /// Create the limit order book for a market (permissionless)
+    /// Orders trade complete-set shares, which redeem at par
+    pub fn initialize_order_book(ctx: Context<InitializeOrderBook>) -> Result<()> {
+        let market = &ctx.accounts.market;
+        require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+        require!(!market.is_scalar(), CryptoBetError::ScalarMarketUnsupported);
+        require!(!market.is_fixed_odds(), CryptoBetError::FixedOddsUnsupported);
+
+        let order_book = &mut ctx.accounts.order_book;
+        order_book.market = market.key();
+        order_book.next_order_id = 0;
+        order_book.orders = Vec::new();
+        order_book.bump = ctx.bumps.order_book;
+
+        Ok(())
+    }
+
+    /// Place a limit order to buy (bid) or sell (ask) YES or NO shares (dual-mode support)
+    /// Fills against resting orders at their price, best price first; a YES bid and a NO bid
+    /// that cross mint complete sets. Takes the Position of each filled order's owner, in fill
+    /// order, in remaining_accounts. Any unfilled size rests on the book with its funds escrowed
+    /// in the market (lamports or vault tokens)
+    pub fn place_order<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PlaceOrder<'info>>,
+        side: BetSide,
+        kind: OrderKind,
+        price_bps: u16,
+        size: u64,
+    ) -> Result<()> {
+        require!(size > 0 && size.checked_rem(ORDER_LOT_SIZE) == Some(0), CryptoBetError::InvalidOrderSize);
+        require!(
+            price_bps > 0 && (price_bps as u64) < BPS_DENOMINATOR,
+            CryptoBetError::InvalidOrderPrice
+        );
+        require!(matches!(side, BetSide::Yes | BetSide::No), CryptoBetError::InvalidBetSide);
+
+        let market = &mut ctx.accounts.market;
+        require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+        require!(Clock::get()?.unix_timestamp < market.resolution_time, CryptoBetError::MarketExpired);
+
+        let user = ctx.accounts.user.key();
+        let position = &mut ctx.accounts.position;
+        if position.user == Pubkey::default() {
+            position.user = user;
+            position.market = market.key();
+            position.bump = ctx.bumps.position;
+
+            market.open_positions = market.open_positions
+                .checked_add(1)
+                .ok_or(CryptoBetError::MathOverflow)?;
+        }
+
+        // Asks escrow their shares up front; fills hand them to the buyers
+        if kind == OrderKind::Ask {
+            position.debit_set_shares(side, size)?;
+        }
+
+        let order_book = &mut ctx.accounts.order_book;
+        let mut maker_positions = ctx.remaining_accounts.iter();
+        let mut remaining = size;
+        let mut taker_paid = 0u64;
+        let mut taker_received = 0u64;
+
+        while remaining > 0 {
+            let Some(index) = order_book.best_match(&user, side, kind, price_bps) else {
+                break;
+            };
+            let order = order_book.orders[index];
+
+            let maker_info = maker_positions.next().ok_or(CryptoBetError::MissingMakerPosition)?;
+            let mut maker_position: Account<'info, Position> = Account::try_from(maker_info)?;
+            let expected_position = Pubkey::create_program_address(
+                &[b"position", market.key().as_ref(), order.owner.as_ref(), &[maker_position.bump]],
+                ctx.program_id,
+            )
+            .map_err(|_| error!(CryptoBetError::InvalidPosition))?;
+            require_keys_eq!(maker_info.key(), expected_position, CryptoBetError::InvalidPosition);
+
+            let fill = remaining.min(order.remaining);
+            let maker_value = order.value(fill)?;
+
+            match (kind, order.kind) {
+                // Buy the maker's escrowed shares; the maker's proceeds wait in the market
+                (OrderKind::Bid, OrderKind::Ask) => {
+                    taker_paid = taker_paid
+                        .checked_add(maker_value)
+                        .ok_or(CryptoBetError::MathOverflow)?;
+                    position.credit_set_shares(side, fill)?;
+                    maker_position.order_proceeds = maker_position.order_proceeds
+                        .checked_add(maker_value)
+                        .ok_or(CryptoBetError::MathOverflow)?;
+                    market.order_escrow = market.order_escrow
+                        .checked_add(maker_value)
+                        .ok_or(CryptoBetError::MathOverflow)?;
+                }
+                // A YES bid and a NO bid together pay one unit per share: mint complete sets
+                (OrderKind::Bid, OrderKind::Bid) => {
+                    taker_paid = taker_paid
+                        .checked_add(fill - maker_value)
+                        .ok_or(CryptoBetError::MathOverflow)?;
+                    position.credit_set_shares(side, fill)?;
+                    maker_position.credit_set_shares(order.side, fill)?;
+                    market.check_exposure_limit(maker_position.exposure()?)?;
+                    market.order_escrow = market.order_escrow
+                        .checked_sub(maker_value)
+                        .ok_or(CryptoBetError::MathOverflow)?;
+                    market.total_complete_sets = market.total_complete_sets
+                        .checked_add(fill)
+                        .ok_or(CryptoBetError::MathOverflow)?;
+                }
+                // Sell escrowed shares into the maker's bid, paid from its escrow
+                (OrderKind::Ask, OrderKind::Bid) => {
+                    taker_received = taker_received
+                        .checked_add(maker_value)
+                        .ok_or(CryptoBetError::MathOverflow)?;
+                    maker_position.credit_set_shares(side, fill)?;
+                    market.check_exposure_limit(maker_position.exposure()?)?;
+                    market.order_escrow = market.order_escrow
+                        .checked_sub(maker_value)
+                        .ok_or(CryptoBetError::MathOverflow)?;
+                }
+                (OrderKind::Ask, OrderKind::Ask) => return err!(CryptoBetError::InvalidOrderPrice),
+            }
+
+            if order_book.fill(index, fill) {
+                maker_position.open_orders = maker_position.open_orders.saturating_sub(1);
+            }
+            maker_position.exit(ctx.program_id)?;
+            remaining -= fill;
+
+            emit!(OrderFilled {
+                market: market.key(),
+                order_id: order.order_id,
+                maker: order.owner,
+                taker: user,
+                side: order.side,
+                kind: order.kind,
+                price_bps: order.price_bps,
+                size: fill,
+            });
+        }
+
+        // Bids count their unfilled size too, so a resting bid can't fill past the limit later
+        let pending = if kind == OrderKind::Bid { remaining } else { 0 };
+        market.check_exposure_limit(
+            position.exposure()?
+                .checked_add(pending)
+                .ok_or(CryptoBetError::MathOverflow)?
+        )?;
+
+        let mut order_id = None;
+        if remaining > 0 {
+            require!(order_book.orders.len() < MAX_ORDERS, CryptoBetError::OrderBookFull);
+
+            let id = order_book.next_order_id;
+            order_book.next_order_id = id
+                .checked_add(1)
+                .ok_or(CryptoBetError::MathOverflow)?;
+            let order = Order {
+                order_id: id,
+                owner: user,
+                side,
+                kind,
+                price_bps,
+                remaining,
+            };
+            if kind == OrderKind::Bid {
+                let escrow = order.value(remaining)?;
+                taker_paid = taker_paid
+                    .checked_add(escrow)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+                market.order_escrow = market.order_escrow
+                    .checked_add(escrow)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+            }
+            order_book.orders.push(order);
+            position.open_orders = position.open_orders
+                .checked_add(1)
+                .ok_or(CryptoBetError::MathOverflow)?;
+            order_id = Some(id);
+        }
+
+        if taker_paid > 0 {
+            match market.market_type {
+                MarketType::Degen => {
+                    let cpi_context = CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        anchor_lang::system_program::Transfer {
+                            from: ctx.accounts.user.to_account_info(),
+                            to: market.to_account_info(),
+                        },
+                    );
+                    anchor_lang::system_program::transfer(cpi_context, taker_paid)?;
+                }
+                MarketType::Stable => {
+                    let user_token_account = ctx.accounts.user_token_account
+                        .as_ref()
+                        .ok_or(CryptoBetError::MissingTokenAccount)?;
+                    let market_vault = ctx.accounts.market_vault
+                        .as_ref()
+                        .ok_or(CryptoBetError::MissingVault)?;
+                    let token_program = ctx.accounts.token_program
+                        .as_ref()
+                        .ok_or(CryptoBetError::MissingTokenProgram)?;
+
+                    require!(
+                        user_token_account.mint == market.accepted_mint,
+                        CryptoBetError::InvalidTokenMint
+                    );
+
+                    let cpi_accounts = Transfer {
+                        from: user_token_account.to_account_info(),
+                        to: market_vault.to_account_info(),
+                        authority: ctx.accounts.user.to_account_info(),
+                    };
+                    let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+                    token::transfer(cpi_ctx, taker_paid)?;
+                }
+            }
+        }
+
+        if taker_received > 0 {
+            transfer_from_market(
+                market,
+                &ctx.accounts.user.to_account_info(),
+                ctx.accounts.user_token_account.as_ref(),
+                ctx.accounts.market_vault.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+                taker_received,
+            )?;
+        }
+
+        emit!(OrderPlaced {
+            market: market.key(),
+            order_id,
+            owner: user,
+            side,
+            kind,
+            price_bps,
+            size,
+            remaining,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a resting order and release its escrow (dual-mode support)
+    /// Bids get their collateral back, asks their shares. Allowed in any market state
+    pub fn cancel_order(ctx: Context<CancelOrder>, order_id: u64) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let order_book = &mut ctx.accounts.order_book;
+        let position = &mut ctx.accounts.position;
+
+        let index = order_book.orders
+            .iter()
+            .position(|order| order.order_id == order_id)
+            .ok_or(CryptoBetError::OrderNotFound)?;
+        let order = order_book.orders[index];
+        require_keys_eq!(order.owner, ctx.accounts.user.key(), CryptoBetError::UnauthorizedOrderOwner);
+
+        match order.kind {
+            OrderKind::Bid => {
+                let refund = order.value(order.remaining)?;
+                market.order_escrow = market.order_escrow
+                    .checked_sub(refund)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+                transfer_from_market(
+                    market,
+                    &ctx.accounts.user.to_account_info(),
+                    ctx.accounts.user_token_account.as_ref(),
+                    ctx.accounts.market_vault.as_ref(),
+                    ctx.accounts.token_program.as_ref(),
+                    refund,
+                )?;
+            }
+            OrderKind::Ask => {
+                // Returned shares must still be claimable
+                require!(!position.claimed, CryptoBetError::AlreadyClaimed);
+                position.credit_set_shares(order.side, order.remaining)?;
+            }
+        }
+
+        order_book.orders.remove(index);
+        position.open_orders = position.open_orders.saturating_sub(1);
+
+        emit!(OrderCancelled {
+            market: market.key(),
+            order_id,
+            owner: order.owner,
+            remaining: order.remaining,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw collateral earned by resting asks that were filled (dual-mode support)
+    pub fn withdraw_order_proceeds(ctx: Context<WithdrawOrderProceeds>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let position = &mut ctx.accounts.position;
+
+        let amount = position.order_proceeds;
+        require!(amount > 0, CryptoBetError::InvalidAmount);
+
+        market.order_escrow = market.order_escrow
+            .checked_sub(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        position.order_proceeds = 0;
+
+        transfer_from_market(
+            market,
+            &ctx.accounts.user.to_account_info(),
+            ctx.accounts.user_token_account.as_ref(),
+            ctx.accounts.market_vault.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+            amount,
+        )?;
+
+        emit!(OrderProceedsWithdrawn {
+            market: market.key(),
+            user: ctx.accounts.user.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Deposit collateral that pays for the maker's signed bid quotes (dual-mode support)
+    /// The deposit is held as order proceeds, so anything left unfilled is withdrawn
+    /// with `withdraw_order_proceeds`
+    pub fn deposit_quote_collateral(ctx: Context<DepositQuoteCollateral>, amount: u64) -> Result<()> {
+        require!(amount > 0, CryptoBetError::InvalidAmount);
+
+        let market = &mut ctx.accounts.market;
+        require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+
+        let position = &mut ctx.accounts.position;
+        if position.user == Pubkey::default() {
+            position.user = ctx.accounts.user.key();
+            position.market = market.key();
+            position.bump = ctx.bumps.position;
+
+            market.open_positions = market.open_positions
+                .checked_add(1)
+                .ok_or(CryptoBetError::MathOverflow)?;
+        }
+
+        match market.market_type {
+            MarketType::Degen => {
+                let cpi_context = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.user.to_account_info(),
+                        to: market.to_account_info(),
+                    },
+                );
+                anchor_lang::system_program::transfer(cpi_context, amount)?;
+            }
+            MarketType::Stable => {
+                let user_token_account = ctx.accounts.user_token_account
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenAccount)?;
+                let market_vault = ctx.accounts.market_vault
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingVault)?;
+                let token_program = ctx.accounts.token_program
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenProgram)?;
+
+                require!(
+                    user_token_account.mint == market.accepted_mint,
+                    CryptoBetError::InvalidTokenMint
+                );
+
+                let cpi_accounts = Transfer {
+                    from: user_token_account.to_account_info(),
+                    to: market_vault.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+                token::transfer(cpi_ctx, amount)?;
+            }
+        }
+
+        position.order_proceeds = position.order_proceeds
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        market.order_escrow = market.order_escrow
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        emit!(QuoteCollateralDeposited {
+            market: market.key(),
+            user: ctx.accounts.user.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Fill a maker-signed off-chain quote against the taker (dual-mode support)
+    /// The maker's Ed25519 signature over the serialized quote must be verified by an Ed25519
+    /// program instruction immediately before this one. Trades complete-set shares like the
+    /// order book: a maker ask is paid into the maker's order proceeds, a maker bid is paid
+    /// out of them, so makers fund bids with `deposit_quote_collateral`. Each (maker, nonce)
+    /// can be filled once
+    pub fn fill_quote(ctx: Context<FillQuote>, quote: Quote) -> Result<()> {
+        require!(quote.size > 0 && quote.size.checked_rem(ORDER_LOT_SIZE) == Some(0), CryptoBetError::InvalidOrderSize);
+        require!(
+            quote.price_bps > 0 && (quote.price_bps as u64) < BPS_DENOMINATOR,
+            CryptoBetError::InvalidOrderPrice
+        );
+        require!(matches!(quote.side, BetSide::Yes | BetSide::No), CryptoBetError::InvalidBetSide);
+        require!(Clock::get()?.unix_timestamp < quote.expires_at, CryptoBetError::QuoteExpired);
+        require!(quote.maker != ctx.accounts.taker.key(), CryptoBetError::InvalidQuote);
+
+        let market = &mut ctx.accounts.market;
+        require_keys_eq!(quote.market, market.key(), CryptoBetError::InvalidQuote);
+        require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+        require!(Clock::get()?.unix_timestamp < market.resolution_time, CryptoBetError::MarketExpired);
+        require!(!market.is_scalar(), CryptoBetError::ScalarMarketUnsupported);
+        require!(!market.is_fixed_odds(), CryptoBetError::FixedOddsUnsupported);
+
+        verify_ed25519_signature(
+            &ctx.accounts.instructions_sysvar,
+            &quote.maker,
+            &quote.try_to_vec()?,
+        )?;
+
+        let used_nonce = &mut ctx.accounts.used_nonce;
+        used_nonce.maker = quote.maker;
+        used_nonce.nonce = quote.nonce;
+        used_nonce.bump = ctx.bumps.used_nonce;
+
+        let taker_position = &mut ctx.accounts.taker_position;
+        if taker_position.user == Pubkey::default() {
+            taker_position.user = ctx.accounts.taker.key();
+            taker_position.market = market.key();
+            taker_position.bump = ctx.bumps.taker_position;
+
+            market.open_positions = market.open_positions
+                .checked_add(1)
+                .ok_or(CryptoBetError::MathOverflow)?;
+        }
+
+        let maker_position = &mut ctx.accounts.maker_position;
+        let value = quote.value()?;
+        match quote.kind {
+            // Maker sells: the taker pays into the market and the maker is credited proceeds
+            OrderKind::Ask => {
+                maker_position.debit_set_shares(quote.side, quote.size)?;
+                taker_position.credit_set_shares(quote.side, quote.size)?;
+                market.check_exposure_limit(taker_position.exposure()?)?;
+                maker_position.order_proceeds = maker_position.order_proceeds
+                    .checked_add(value)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+                market.order_escrow = market.order_escrow
+                    .checked_add(value)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+
+                match market.market_type {
+                    MarketType::Degen => {
+                        let cpi_context = CpiContext::new(
+                            ctx.accounts.system_program.to_account_info(),
+                            anchor_lang::system_program::Transfer {
+                                from: ctx.accounts.taker.to_account_info(),
+                                to: market.to_account_info(),
+                            },
+                        );
+                        anchor_lang::system_program::transfer(cpi_context, value)?;
+                    }
+                    MarketType::Stable => {
+                        let taker_token_account = ctx.accounts.taker_token_account
+                            .as_ref()
+                            .ok_or(CryptoBetError::MissingTokenAccount)?;
+                        let market_vault = ctx.accounts.market_vault
+                            .as_ref()
+                            .ok_or(CryptoBetError::MissingVault)?;
+                        let token_program = ctx.accounts.token_program
+                            .as_ref()
+                            .ok_or(CryptoBetError::MissingTokenProgram)?;
+
+                        require!(
+                            taker_token_account.mint == market.accepted_mint,
+                            CryptoBetError::InvalidTokenMint
+                        );
+
+                        let cpi_accounts = Transfer {
+                            from: taker_token_account.to_account_info(),
+                            to: market_vault.to_account_info(),
+                            authority: ctx.accounts.taker.to_account_info(),
+                        };
+                        let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+                        token::transfer(cpi_ctx, value)?;
+                    }
+                }
+            }
+            // Maker buys: paid from the maker's deposits and proceeds held in the market
+            OrderKind::Bid => {
+                taker_position.debit_set_shares(quote.side, quote.size)?;
+                maker_position.credit_set_shares(quote.side, quote.size)?;
+                market.check_exposure_limit(maker_position.exposure()?)?;
+                maker_position.order_proceeds = maker_position.order_proceeds
+                    .checked_sub(value)
+                    .ok_or(CryptoBetError::InsufficientFunds)?;
+                market.order_escrow = market.order_escrow
+                    .checked_sub(value)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+
+                transfer_from_market(
+                    market,
+                    &ctx.accounts.taker.to_account_info(),
+                    ctx.accounts.taker_token_account.as_ref(),
+                    ctx.accounts.market_vault.as_ref(),
+                    ctx.accounts.token_program.as_ref(),
+                    value,
+                )?;
+            }
+        }
+
+        emit!(QuoteFilled {
+            market: market.key(),
+            maker: quote.maker,
+            taker: ctx.accounts.taker.key(),
+            side: quote.side,
+            kind: quote.kind,
+            price_bps: quote.price_bps,
+            size: quote.size,
+            nonce: quote.nonce,
+        });
+
+        Ok(())
+    }
+
+    /// Open a 1v1 challenge on a market's outcome at custom odds (dual-mode support)
+    /// The creator escrows a stake on one side; the counterparty stakes enough to pay the creator
+    /// at `odds_bps` (decimal odds x 10_000). An optional counterparty restricts who can accept
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_challenge(
+        ctx: Context<CreateChallenge>,
+        challenge_id: u64,
+        side: BetSide,
+        stake: u64,
+        odds_bps: u64,
+        counterparty: Option<Pubkey>,
+        expires_at: i64,
+    ) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        market.check_bet_limits(stake)?;
+        require!(matches!(side, BetSide::Yes | BetSide::No), CryptoBetError::InvalidBetSide);
+        require!(
+            odds_bps > BPS_DENOMINATOR && odds_bps <= MAX_FIXED_ODDS_BPS,
+            CryptoBetError::InvalidFixedOdds
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+        require!(!market.is_scalar(), CryptoBetError::ScalarMarketUnsupported);
+        require!(
+            expires_at > now && expires_at <= market.resolution_time,
+            CryptoBetError::InvalidChallengeExpiry
+        );
+        require!(
+            counterparty != Some(ctx.accounts.creator.key()),
+            CryptoBetError::InvalidChallengeCounterparty
+        );
+
+        // The counterparty covers the creator's winnings beyond the stake
+        let counterparty_stake = u64::try_from(
+            (stake as u128)
+                .checked_mul(odds_bps as u128)
+                .ok_or(CryptoBetError::MathOverflow)?
+                .checked_div(BPS_DENOMINATOR as u128)
+                .ok_or(CryptoBetError::DivisionByZero)?,
+        )
+        .map_err(|_| error!(CryptoBetError::MathOverflow))?
+        .checked_sub(stake)
+        .ok_or(CryptoBetError::MathOverflow)?;
+        require!(counterparty_stake > 0, CryptoBetError::InvalidFixedOdds);
+
+        let challenge = &mut ctx.accounts.challenge;
+        match market.market_type {
+            MarketType::Degen => {
+                let cpi_context = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.creator.to_account_info(),
+                        to: challenge.to_account_info(),
+                    },
+                );
+                anchor_lang::system_program::transfer(cpi_context, stake)?;
+            }
+            MarketType::Stable => {
+                let creator_token_account = ctx.accounts.creator_token_account
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenAccount)?;
+                let challenge_vault = ctx.accounts.challenge_vault
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingVault)?;
+                let token_program = ctx.accounts.token_program
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenProgram)?;
+
+                require!(
+                    creator_token_account.mint == market.accepted_mint
+                        && challenge_vault.mint == market.accepted_mint,
+                    CryptoBetError::InvalidTokenMint
+                );
+
+                let cpi_accounts = Transfer {
+                    from: creator_token_account.to_account_info(),
+                    to: challenge_vault.to_account_info(),
+                    authority: ctx.accounts.creator.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+                token::transfer(cpi_ctx, stake)?;
+            }
+        }
+
+        challenge.creator = ctx.accounts.creator.key();
+        challenge.challenge_id = challenge_id;
+        challenge.market = market.key();
+        challenge.market_type = market.market_type;
+        challenge.creator_side = side;
+        challenge.creator_stake = stake;
+        challenge.counterparty_stake = counterparty_stake;
+        challenge.odds_bps = odds_bps;
+        challenge.counterparty = counterparty;
+        challenge.acceptor = Pubkey::default();
+        challenge.expires_at = expires_at;
+        challenge.state = ChallengeState::Open;
+        challenge.bump = ctx.bumps.challenge;
+
+        // The market can't close while the challenge still needs it to settle or refund
+        market.open_challenges = market.open_challenges
+            .checked_add(1)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        emit!(ChallengeCreated {
+            challenge: challenge.key(),
+            market: challenge.market,
+            creator: challenge.creator,
+            side,
+            stake,
+            counterparty_stake,
+            odds_bps,
+            counterparty,
+            expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// Take the other side of an open challenge (dual-mode support)
+    pub fn accept_challenge(ctx: Context<AcceptChallenge>) -> Result<()> {
+        let challenge = &mut ctx.accounts.challenge;
+        let market = &ctx.accounts.market;
+        let acceptor = ctx.accounts.acceptor.key();
+        let now = Clock::get()?.unix_timestamp;
+        require!(challenge.state == ChallengeState::Open, CryptoBetError::ChallengeNotOpen);
+        require!(now < challenge.expires_at, CryptoBetError::ChallengeExpired);
+        require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+        require!(now < market.resolution_time, CryptoBetError::MarketExpired);
+        require!(acceptor != challenge.creator, CryptoBetError::InvalidChallengeCounterparty);
+        if let Some(counterparty) = challenge.counterparty {
+            require_keys_eq!(acceptor, counterparty, CryptoBetError::InvalidChallengeCounterparty);
+        }
+
+        let amount = challenge.counterparty_stake;
+        match challenge.market_type {
+            MarketType::Degen => {
+                let cpi_context = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.acceptor.to_account_info(),
+                        to: challenge.to_account_info(),
+                    },
+                );
+                anchor_lang::system_program::transfer(cpi_context, amount)?;
+            }
+            MarketType::Stable => {
+                let acceptor_token_account = ctx.accounts.acceptor_token_account
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenAccount)?;
+                let challenge_vault = ctx.accounts.challenge_vault
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingVault)?;
+                let token_program = ctx.accounts.token_program
+                    .as_ref()
+                    .ok_or(CryptoBetError::MissingTokenProgram)?;
+
+                require!(
+                    acceptor_token_account.mint == market.accepted_mint,
+                    CryptoBetError::InvalidTokenMint
+                );
+
+                let cpi_accounts = Transfer {
+                    from: acceptor_token_account.to_account_info(),
+                    to: challenge_vault.to_account_info(),
+                    authority: ctx.accounts.acceptor.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+                token::transfer(cpi_ctx, amount)?;
+            }
+        }
+
+        challenge.acceptor = acceptor;
+        challenge.state = ChallengeState::Accepted;
+
+        emit!(ChallengeAccepted {
+            challenge: challenge.key(),
+            acceptor,
+            stake: amount,
+        });
+
+        Ok(())
+    }
+
+    /// Return the creator's stake from a challenge nobody accepted (dual-mode support)
+    /// The creator can withdraw at any time; anyone can once it expires or the market is cancelled
+    pub fn cancel_challenge(ctx: Context<CancelChallenge>) -> Result<()> {
+        let challenge = &mut ctx.accounts.challenge;
+        let market = &mut ctx.accounts.market;
+        require!(challenge.state == ChallengeState::Open, CryptoBetError::ChallengeNotOpen);
+        require!(
+            ctx.accounts.caller.key() == challenge.creator
+                || Clock::get()?.unix_timestamp >= challenge.expires_at
+                || market.state == MarketState::Cancelled,
+            CryptoBetError::ChallengeStillOpen
+        );
+
+        let stake = challenge.creator_stake;
+        transfer_from_challenge(
+            challenge,
+            &ctx.accounts.creator,
+            ctx.accounts.creator_token_account.as_ref(),
+            ctx.accounts.challenge_vault.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+            stake,
+        )?;
+        close_challenge_vault(
+            challenge,
+            &ctx.accounts.creator,
+            ctx.accounts.challenge_vault.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+        )?;
+        challenge.state = ChallengeState::Refunded;
+        market.open_challenges = market.open_challenges
+            .checked_sub(1)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        emit!(ChallengeSettled {
+            challenge: challenge.key(),
+            state: challenge.state,
+            winner: None,
+            creator_amount: stake,
+            acceptor_amount: 0,
+        });
+
+        Ok(())
+    }
+
+    /// Settle an accepted challenge from its market's result (permissionless)
+    /// The winner takes both stakes; if the market was cancelled each side is refunded
+    pub fn settle_challenge(ctx: Context<SettleChallenge>) -> Result<()> {
+        let challenge = &mut ctx.accounts.challenge;
+        let market = &mut ctx.accounts.market;
+        require!(challenge.state == ChallengeState::Accepted, CryptoBetError::ChallengeNotAccepted);
+
+        let pot = challenge.creator_stake
+            .checked_add(challenge.counterparty_stake)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        let (creator_amount, acceptor_amount, winner, state) = match market.state {
+            MarketState::Resolved => {
+                let outcome = market.resolved_outcome.ok_or(CryptoBetError::MarketNotResolved)?;
+                if outcome == (challenge.creator_side == BetSide::Yes) {
+                    (pot, 0, Some(challenge.creator), ChallengeState::Settled)
+                } else {
+                    (0, pot, Some(challenge.acceptor), ChallengeState::Settled)
+                }
+            }
+            MarketState::Cancelled => (
+                challenge.creator_stake,
+                challenge.counterparty_stake,
+                None,
+                ChallengeState::Refunded,
+            ),
+            MarketState::Active => return err!(CryptoBetError::MarketStillActive),
+        };
+
+        if creator_amount > 0 {
+            transfer_from_challenge(
+                challenge,
+                &ctx.accounts.creator,
+                ctx.accounts.creator_token_account.as_ref(),
+                ctx.accounts.challenge_vault.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+                creator_amount,
+            )?;
+        }
+        if acceptor_amount > 0 {
+            transfer_from_challenge(
+                challenge,
+                &ctx.accounts.acceptor,
+                ctx.accounts.acceptor_token_account.as_ref(),
+                ctx.accounts.challenge_vault.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+                acceptor_amount,
+            )?;
+        }
+        close_challenge_vault(
+            challenge,
+            &ctx.accounts.creator,
+            ctx.accounts.challenge_vault.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+        )?;
+        challenge.state = state;
+        market.open_challenges = market.open_challenges
+            .checked_sub(1)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        emit!(ChallengeSettled {
+            challenge: challenge.key(),
+            state,
+            winner,
+            creator_amount,
+            acceptor_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Update a market's bet, position and volume limits
+    /// Only the market authority can change limits; they apply to bets placed afterwards
+    pub fn set_market_limits(ctx: Context<SetMarketLimits>, limits: MarketLimits) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        require!(market.state == MarketState::Active, CryptoBetError::MarketNotActive);
+        limits.validate(market.decimals)?;
+
+        market.limits = limits;
+
+        emit!(MarketLimitsUpdated {
+            market: market.key(),
+            limits,
+        });
+
+        Ok(())
+    }
+}
+
+/// Convert a limit in thousandths of a token to base units of a mint with `decimals`
+pub fn scale_limit(amount: u64, decimals: u8) -> u64 {
+    if decimals >= LIMIT_DECIMALS {
+        let factor = 10u64.checked_pow((decimals - LIMIT_DECIMALS) as u32).unwrap_or(u64::MAX);
+        amount.saturating_mul(factor)
+    } else {
+        amount / 10u64.pow((LIMIT_DECIMALS - decimals) as u32)
+    }
+}
+
+/// Create a program-owned PDA like Anchor's `init`, including when the address was pre-funded
+fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+    owner: &Pubkey,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let signer = &[seeds];
+
+    if account.lamports() == 0 {
+        let cpi_context = CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            anchor_lang::system_program::CreateAccount {
+                from: payer.clone(),
+                to: account.clone(),
+            },
+            signer,
+        );
+        return anchor_lang::system_program::create_account(cpi_context, rent, space as u64, owner);
+    }
+
+    // Someone sent lamports to the address first, so top it up, then allocate and assign it
+    let top_up = rent.saturating_sub(account.lamports());
+    if top_up > 0 {
+        let cpi_context = CpiContext::new(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: payer.clone(),
+                to: account.clone(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, top_up)?;
+    }
+
+    let cpi_context = CpiContext::new_with_signer(
+        system_program.to_account_info(),
+        anchor_lang::system_program::Allocate { account_to_allocate: account.clone() },
+        signer,
+    );
+    anchor_lang::system_program::allocate(cpi_context, space as u64)?;
+
+    let cpi_context = CpiContext::new_with_signer(
+        system_program.to_account_info(),
+        anchor_lang::system_program::Assign { account_to_assign: account.clone() },
+        signer,
+    );
+    anchor_lang::system_program::assign(cpi_context, owner)
+}
+
+/// Check that the instruction before this one is an Ed25519 program instruction verifying
+/// `signer`'s signature over exactly `message`, with all data inline in that instruction
+fn verify_ed25519_signature(instructions: &AccountInfo, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    let ix = instructions_sysvar::get_instruction_relative(-1, instructions)
+        .map_err(|_| error!(CryptoBetError::MissingSignatureVerification))?;
+    require_keys_eq!(ix.program_id, ed25519_program::ID, CryptoBetError::MissingSignatureVerification);
+
+    // Header: signature count and padding, then one set of u16 offsets
+    let data = &ix.data;
+    require!(data.len() >= 16 && data[0] == 1, CryptoBetError::InvalidSignatureVerification);
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let public_key_offset = read_u16(6) as usize;
+    let message_offset = read_u16(10) as usize;
+    let message_size = read_u16(12) as usize;
+
+    // u16::MAX means the signature, key and message live in the Ed25519 instruction itself
+    require!(
+        read_u16(4) == u16::MAX && read_u16(8) == u16::MAX && read_u16(14) == u16::MAX,
+        CryptoBetError::InvalidSignatureVerification
+    );
+
+    let public_key = data
+        .get(public_key_offset..public_key_offset + 32)
+        .ok_or(CryptoBetError::InvalidSignatureVerification)?;
+    let signed_message = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(CryptoBetError::InvalidSignatureVerification)?;
+    require!(public_key == signer.as_ref(), CryptoBetError::InvalidSignatureVerification);
+    require!(signed_message == message, CryptoBetError::InvalidSignatureVerification);
+
+    Ok(())
+}
+
+/// Pay out of a challenge's escrow: lamports for Degen, challenge-signed vault transfer for Stable
+fn transfer_from_challenge<'info>(
+    challenge: &Account<'info, Challenge>,
+    recipient: &AccountInfo<'info>,
+    recipient_token_account: Option<&Account<'info, TokenAccount>>,
+    challenge_vault: Option<&Account<'info, TokenAccount>>,
+    token_program: Option<&Program<'info, Token>>,
+    amount: u64,
+) -> Result<()> {
+    match challenge.market_type {
+        MarketType::Degen => {
+            **challenge.to_account_info().try_borrow_mut_lamports()? = challenge
+                .to_account_info()
+                .lamports()
+                .checked_sub(amount)
+                .ok_or(CryptoBetError::InsufficientFunds)?;
+
+            **recipient.try_borrow_mut_lamports()? = recipient
+                .lamports()
+                .checked_add(amount)
+                .ok_or(CryptoBetError::MathOverflow)?;
+        }
+        MarketType::Stable => {
+            let recipient_token_account = recipient_token_account.ok_or(CryptoBetError::MissingTokenAccount)?;
+            let challenge_vault = challenge_vault.ok_or(CryptoBetError::MissingVault)?;
+            let token_program = token_program.ok_or(CryptoBetError::MissingTokenProgram)?;
+
+            require_keys_eq!(recipient_token_account.owner, recipient.key(), CryptoBetError::InvalidTokenAccountOwner);
+            require!(
+                recipient_token_account.mint == challenge_vault.mint,
+                CryptoBetError::InvalidTokenMint
+            );
+
+            // The vault is owned by the challenge PDA, so the challenge signs
+            let challenge_id_bytes = challenge.challenge_id.to_le_bytes();
+            let seeds = &[
+                b"challenge".as_ref(),
+                challenge.creator.as_ref(),
+                challenge_id_bytes.as_ref(),
+                &[challenge.bump]
+            ];
+            let signer = &[&seeds[..]];
+
+            let cpi_accounts = Transfer {
+                from: challenge_vault.to_account_info(),
+                to: recipient_token_account.to_account_info(),
+                authority: challenge.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                cpi_accounts,
+                signer
+            );
+            token::transfer(cpi_ctx, amount)?;
+        }
+    }
+    Ok(())
+}
+
+/// Close a settled Stable challenge's vault, returning its rent to the creator
+fn close_challenge_vault<'info>(
+    challenge: &Account<'info, Challenge>,
+    creator: &AccountInfo<'info>,
+    challenge_vault: Option<&Account<'info, TokenAccount>>,
+    token_program: Option<&Program<'info, Token>>,
+) -> Result<()> {
+    if challenge.market_type != MarketType::Stable {
+        return Ok(());
+    }
+    let challenge_vault = challenge_vault.ok_or(CryptoBetError::MissingVault)?;
+    let token_program = token_program.ok_or(CryptoBetError::MissingTokenProgram)?;
+
+    let challenge_id_bytes = challenge.challenge_id.to_le_bytes();
+    let seeds = &[
+        b"challenge".as_ref(),
+        challenge.creator.as_ref(),
+        challenge_id_bytes.as_ref(),
+        &[challenge.bump]
+    ];
+    let signer = &[&seeds[..]];
+
+    let cpi_accounts = CloseAccount {
+        account: challenge_vault.to_account_info(),
+        destination: creator.clone(),
+        authority: challenge.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        cpi_accounts,
+        signer
+    );
+    token::close_account(cpi_ctx)
+}
+
+/// AMM trading fee on `amount` of collateral
+fn amm_fee(amount: u64) -> Result<u64> {
+    Ok((amount as u128)
+        .checked_mul(AMM_FEE_BPS as u128)
+        .ok_or(CryptoBetError::MathOverflow)?
+        .checked_div(BPS_DENOMINATOR as u128)
+        .ok_or(CryptoBetError::DivisionByZero)? as u64)
+}
+
+/// Pay collateral out of a market: lamports for Degen, market-signed vault transfer for Stable
+fn transfer_from_market<'info>(
+    market: &Account<'info, Market>,
+    recipient: &AccountInfo<'info>,
+    recipient_token_account: Option<&Account<'info, TokenAccount>>,
+    market_vault: Option<&Account<'info, TokenAccount>>,
+    token_program: Option<&Program<'info, Token>>,
+    amount: u64,
+) -> Result<()> {
+    match market.market_type {
+        MarketType::Degen => {
+            **market.to_account_info().try_borrow_mut_lamports()? = market
+                .to_account_info()
+                .lamports()
+                .checked_sub(amount)
+                .ok_or(CryptoBetError::InsufficientFunds)?;
+
+            **recipient.try_borrow_mut_lamports()? = recipient
+                .lamports()
+                .checked_add(amount)
+                .ok_or(CryptoBetError::MathOverflow)?;
+        }
+        MarketType::Stable => {
+            let recipient_token_account = recipient_token_account.ok_or(CryptoBetError::MissingTokenAccount)?;
+            let market_vault = market_vault.ok_or(CryptoBetError::MissingVault)?;
+            let token_program = token_program.ok_or(CryptoBetError::MissingTokenProgram)?;
+
+            require!(
+                recipient_token_account.mint == market.accepted_mint,
+                CryptoBetError::InvalidTokenMint
+            );
+
+            // The vault is owned by the market PDA, so the market signs
+            let market_id_bytes = market.market_id.to_le_bytes();
+            let seeds = &[
+                b"market".as_ref(),
+                market_id_bytes.as_ref(),
+                &[market.bump]
+            ];
+            let signer = &[&seeds[..]];
+
+            let cpi_accounts = Transfer {
+                from: market_vault.to_account_info(),
+                to: recipient_token_account.to_account_info(),
+                authority: market.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                cpi_accounts,
+                signer
+            );
+            token::transfer(cpi_ctx, amount)?;
+        }
+    }
+    Ok(())
+}
+
+/// Pay collateral out of a round series: lamports for Degen, series-signed vault transfer for Stable
+fn transfer_from_series<'info>(
+    series: &Account<'info, RoundSeries>,
+    recipient: &AccountInfo<'info>,
+    recipient_token_account: Option<&Account<'info, TokenAccount>>,
+    series_vault: Option<&Account<'info, TokenAccount>>,
+    token_program: Option<&Program<'info, Token>>,
+    amount: u64,
+) -> Result<()> {
+    match series.market_type {
+        MarketType::Degen => {
+            **series.to_account_info().try_borrow_mut_lamports()? = series
+                .to_account_info()
+                .lamports()
+                .checked_sub(amount)
+                .ok_or(CryptoBetError::InsufficientFunds)?;
+
+            **recipient.try_borrow_mut_lamports()? = recipient
+                .lamports()
+                .checked_add(amount)
+                .ok_or(CryptoBetError::MathOverflow)?;
+        }
+        MarketType::Stable => {
+            let recipient_token_account = recipient_token_account.ok_or(CryptoBetError::MissingTokenAccount)?;
+            let series_vault = series_vault.ok_or(CryptoBetError::MissingVault)?;
+            let token_program = token_program.ok_or(CryptoBetError::MissingTokenProgram)?;
+
+            require!(
+                recipient_token_account.mint == series.accepted_mint,
+                CryptoBetError::InvalidTokenMint
+            );
+
+            // The series vault is owned by the series PDA, so the series signs
+            let series_id_bytes = series.series_id.to_le_bytes();
+            let seeds = &[
+                b"round_series".as_ref(),
+                series_id_bytes.as_ref(),
+                &[series.bump]
+            ];
+            let signer = &[&seeds[..]];
+
+            let cpi_accounts = Transfer {
+                from: series_vault.to_account_info(),
+                to: recipient_token_account.to_account_info(),
+                authority: series.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                cpi_accounts,
+                signer
+            );
+            token::transfer(cpi_ctx, amount)?;
+        }
+    }
+    Ok(())
+}
+
+// Context Structs
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + ProtocolConfig::LEN,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::CryptoBet>,
+    
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ CryptoBetError::UnauthorizedAdmin)]
+    pub program_data: Account<'info, ProgramData>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ CryptoBetError::UnauthorizedAdmin
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(market_id: u64)]
+pub struct InitializeMarket<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Market::LEN,
+        seeds = [b"market", market_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    // Optional vault for USDC markets
+    #[account(
+        init_if_needed,
+        payer = authority,
+        token::mint = usdc_mint,
+        token::authority = market,
+        seeds = [b"vault", market.key().as_ref()],
+        bump
+    )]
+    pub market_vault: Option<Account<'info, TokenAccount>>,
+    
+    // Collateral mint for USDC markets, read for its decimals
+    pub usdc_mint: Option<Account<'info, Mint>>,
+    
+    // Optional parent for conditional markets
+    #[account(
+        seeds = [b"market", &parent_market.market_id.to_le_bytes()],
+        bump = parent_market.bump
+    )]
+    pub parent_market: Option<Account<'info, Market>>,
+    
+    // Optional accounts for creator-seeded liquidity
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + Position::LEN,
+        seeds = [b"position", market.key().as_ref(), authority.key().as_ref()],
+        bump
+    )]
+    pub creator_position: Option<Account<'info, Position>>,
+    
+    #[account(mut)]
+    pub creator_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBet<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + Position::LEN,
+        seeds = [b"position", market.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump = market.vault_bump
+    )]
+    pub market_vault: Option<Account<'info, TokenAccount>>,
+    
+    // Optional accounts for tokenized markets
+    #[account(mut)]
+    pub outcome_mint: Option<Account<'info, Mint>>,
+    
+    #[account(mut)]
+    pub user_outcome_token_account: Option<Account<'info, TokenAccount>>,
+    
+    // Optional accounts for fixed-odds markets
+    #[account(
+        mut,
+        seeds = [b"house_pool", house_pool.accepted_mint.as_ref()],
+        bump = house_pool.bump
+    )]
+    pub house_pool: Option<Account<'info, HousePool>>,
+    
+    #[account(
+        mut,
+        seeds = [b"house_vault", house_pool.as_ref().map(|pool| pool.key()).unwrap_or_default().as_ref()],
+        bump
+    )]
+    pub house_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBets<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // Optional accounts for USDC markets, shared by every Stable market in the batch
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveMarket<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump,
+        has_one = authority
+    )]
+    pub market: Account<'info, Market>,
+    
+    // Required when resolving a conditional market
+    pub parent_market: Option<Account<'info, Market>>,
+    
+    // Required when resolving a grouped market
+    #[account(mut)]
+    pub market_group: Option<Account<'info, MarketGroup>>,
+    
+    // Required for fixed-odds markets
+    #[account(
+        mut,
+        seeds = [b"house_pool", house_pool.accepted_mint.as_ref()],
+        bump = house_pool.bump
+    )]
+    pub house_pool: Option<Account<'info, HousePool>>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VoidConditionalMarket<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    pub parent_market: Account<'info, Market>,
+    
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelMarket<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump,
+        has_one = authority
+    )]
+    pub market: Account<'info, Market>,
+    
+    // Required for fixed-odds markets
+    #[account(
+        mut,
+        seeds = [b"house_pool", house_pool.accepted_mint.as_ref()],
+        bump = house_pool.bump
+    )]
+    pub house_pool: Option<Account<'info, HousePool>>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWinnings<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    // Optional for tokenized markets, where outcome tokens may be held without a position
+    #[account(
+        mut,
+        seeds = [b"position", market.key().as_ref(), user.key().as_ref()],
+        bump = position.bump,
+        has_one = user
+    )]
+    pub position: Option<Account<'info, Position>>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump = market.vault_bump
+    )]
+    pub market_vault: Option<Account<'info, TokenAccount>>,
+    
+    // Optional accounts for tokenized markets
+    #[account(mut)]
+    pub winning_mint: Option<Account<'info, Mint>>,
+    
+    #[account(mut)]
+    pub user_outcome_token_account: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRefund<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    // Optional for tokenized markets, where outcome tokens may be held without a position
+    #[account(
+        mut,
+        seeds = [b"position", market.key().as_ref(), user.key().as_ref()],
+        bump = position.bump,
+        has_one = user
+    )]
+    pub position: Option<Account<'info, Position>>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump = market.vault_bump
+    )]
+    pub market_vault: Option<Account<'info, TokenAccount>>,
+    
+    // Optional accounts for tokenized markets
+    #[account(mut)]
+    pub yes_mint: Option<Account<'info, Mint>>,
+    
+    #[account(mut)]
+    pub no_mint: Option<Account<'info, Mint>>,
+    
+    #[account(mut)]
+    pub user_yes_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub user_no_token_account: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePosition<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"position", market.key().as_ref(), user.key().as_ref()],
+        bump = position.bump,
+        has_one = user
+    )]
+    pub position: Account<'info, Position>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseMarket<'info> {
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump,
+        has_one = authority
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump = market.vault_bump
+    )]
+    pub market_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct SweepUnclaimed<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ CryptoBetError::UnauthorizedAdmin
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    pub admin: Signer<'info>,
+    
+    /// CHECK: Must match config.treasury, receives lamports for SOL markets
+    #[account(mut)]
+    pub treasury: UncheckedAccount<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump = market.vault_bump
+    )]
+    pub market_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct SweepDust<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    pub caller: Signer<'info>,
+    
+    /// CHECK: Must match config.treasury, receives lamports for SOL markets
+    #[account(mut)]
+    pub treasury: UncheckedAccount<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump = market.vault_bump
+    )]
+    pub market_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawCreatorFees<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump,
+        has_one = authority
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub authority_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump = market.vault_bump
+    )]
+    pub market_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimMany<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // Optional accounts for USDC markets, shared by every Stable market in the batch
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct TransferPosition<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        mut,
+        seeds = [b"position", market.key().as_ref(), user.key().as_ref()],
+        bump = from_position.bump,
+        has_one = user
+    )]
+    pub from_position: Account<'info, Position>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + Position::LEN,
+        seeds = [b"position", market.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub to_position: Account<'info, Position>,
+    
+    /// CHECK: Recipient wallet, only used as the new position's owner and PDA seed
+    pub recipient: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateOutcomeMints<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump,
+        has_one = authority
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = market.decimals,
+        mint::authority = market,
+        seeds = [b"yes_mint", market.key().as_ref()],
+        bump
+    )]
+    pub yes_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = market.decimals,
+        mint::authority = market,
+        seeds = [b"no_mint", market.key().as_ref()],
+        bump
+    )]
+    pub no_mint: Account<'info, Mint>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct Split<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + Position::LEN,
+        seeds = [b"position", market.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump = market.vault_bump
+    )]
+    pub market_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct Merge<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        mut,
+        seeds = [b"position", market.key().as_ref(), user.key().as_ref()],
+        bump = position.bump,
+        has_one = user
+    )]
+    pub position: Account<'info, Position>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump = market.vault_bump
+    )]
+    pub market_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+#[instruction(market_type: MarketType)]
+pub struct InitializeParlayPool<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ CryptoBetError::UnauthorizedAdmin
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + ParlayPool::LEN,
+        seeds = [b"parlay_pool", config.accepted_mint(market_type).as_ref()],
+        bump
+    )]
+    pub parlay_pool: Account<'info, ParlayPool>,
+    
+    // Optional vault for USDC pools
+    #[account(
+        init_if_needed,
+        payer = admin,
+        token::mint = usdc_mint,
+        token::authority = parlay_pool,
+        seeds = [b"parlay_vault", parlay_pool.key().as_ref()],
+        bump
+    )]
+    pub pool_vault: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: USDC mint address
+    pub usdc_mint: Option<AccountInfo<'info>>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct FundParlayPool<'info> {
+    #[account(
+        mut,
+        seeds = [b"parlay_pool", parlay_pool.accepted_mint.as_ref()],
+        bump = parlay_pool.bump
+    )]
+    pub parlay_pool: Account<'info, ParlayPool>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ CryptoBetError::UnauthorizedAdmin
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    // Optional accounts for USDC pools
+    #[account(mut)]
+    pub admin_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"parlay_vault", parlay_pool.key().as_ref()],
+        bump
+    )]
+    pub pool_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawParlayPool<'info> {
+    #[account(
+        mut,
+        seeds = [b"parlay_pool", parlay_pool.accepted_mint.as_ref()],
+        bump = parlay_pool.bump
+    )]
+    pub parlay_pool: Account<'info, ParlayPool>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ CryptoBetError::UnauthorizedAdmin
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    // Optional accounts for USDC pools
+    #[account(mut)]
+    pub admin_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"parlay_vault", parlay_pool.key().as_ref()],
+        bump
+    )]
+    pub pool_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+#[instruction(parlay_id: u64)]
+pub struct PlaceParlay<'info> {
+    #[account(
+        mut,
+        seeds = [b"parlay_pool", parlay_pool.accepted_mint.as_ref()],
+        bump = parlay_pool.bump
+    )]
+    pub parlay_pool: Account<'info, ParlayPool>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Parlay::LEN,
+        seeds = [b"parlay", user.key().as_ref(), parlay_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub parlay: Account<'info, Parlay>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // Optional accounts for USDC pools
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"parlay_vault", parlay_pool.key().as_ref()],
+        bump
+    )]
+    pub pool_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct SettleParlay<'info> {
+    #[account(
+        mut,
+        seeds = [b"parlay_pool", parlay_pool.accepted_mint.as_ref()],
+        bump = parlay_pool.bump,
+        constraint = parlay_pool.accepted_mint == parlay.accepted_mint @ CryptoBetError::InvalidParlayLegs
+    )]
+    pub parlay_pool: Account<'info, ParlayPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"parlay", user.key().as_ref(), parlay.parlay_id.to_le_bytes().as_ref()],
+        bump = parlay.bump,
+        has_one = user
+    )]
+    pub parlay: Account<'info, Parlay>,
+    
+    /// CHECK: Parlay owner, checked by has_one; receives any payout
+    #[account(mut)]
+    pub user: UncheckedAccount<'info>,
+    
+    pub caller: Signer<'info>,
+    
+    // Optional accounts for USDC pools
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"parlay_vault", parlay_pool.key().as_ref()],
+        bump
+    )]
+    pub pool_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+#[instruction(group_id: u64)]
+pub struct InitializeMarketGroup<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + MarketGroup::LEN,
+        seeds = [b"market_group", group_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub market_group: Account<'info, MarketGroup>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddMarketToGroup<'info> {
+    #[account(
+        mut,
+        seeds = [b"market_group", &market_group.group_id.to_le_bytes()],
+        bump = market_group.bump,
+        has_one = authority
+    )]
+    pub market_group: Account<'info, MarketGroup>,
+    
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump,
+        has_one = authority
+    )]
+    pub market: Account<'info, Market>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ConvertNoBasket<'info> {
+    #[account(
+        mut,
+        seeds = [b"market_group", &market_group.group_id.to_le_bytes()],
+        bump = market_group.bump
+    )]
+    pub market_group: Account<'info, MarketGroup>,
+    
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleGroup<'info> {
+    #[account(
+        mut,
+        seeds = [b"market_group", &market_group.group_id.to_le_bytes()],
+        bump = market_group.bump
+    )]
+    pub market_group: Account<'info, MarketGroup>,
+    
+    pub caller: Signer<'info>,
+    
+    // Optional accounts for USDC groups
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+#[instruction(series_id: u64)]
+pub struct InitializeRoundSeries<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + RoundSeries::LEN,
+        seeds = [b"round_series", series_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub round_series: Account<'info, RoundSeries>,
+    
+    // Optional vault for USDC series
+    #[account(
+        init_if_needed,
+        payer = authority,
+        token::mint = usdc_mint,
+        token::authority = round_series,
+        seeds = [b"series_vault", round_series.key().as_ref()],
+        bump
+    )]
+    pub series_vault: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: USDC mint address
+    pub usdc_mint: Option<AccountInfo<'info>>,
+    
+    /// CHECK: Pyth price update account, owner checked in the handler
+    pub price_feed: AccountInfo<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CrankRound<'info> {
+    #[account(
+        mut,
+        seeds = [b"round_series", &round_series.series_id.to_le_bytes()],
+        bump = round_series.bump,
+        has_one = price_feed @ CryptoBetError::InvalidPriceFeed
+    )]
+    pub round_series: Account<'info, RoundSeries>,
+    
+    /// CHECK: Pyth price update account, parsed in `read_oracle_price`
+    pub price_feed: AccountInfo<'info>,
+    
+    // Required once the first round has been opened
+    #[account(
+        mut,
+        seeds = [b"round", round_series.key().as_ref(), &round_series.current_epoch.to_le_bytes()],
+        bump = current_round.bump
+    )]
+    pub current_round: Option<Account<'info, Round>>,
+    
+    // Required from the second round on
+    #[account(
+        mut,
+        seeds = [b"round", round_series.key().as_ref(), &round_series.current_epoch.saturating_sub(1).to_le_bytes()],
+        bump = previous_round.bump
+    )]
+    pub previous_round: Option<Account<'info, Round>>,
+    
+    #[account(
+        init,
+        payer = cranker,
+        space = 8 + Round::LEN,
+        seeds = [b"round", round_series.key().as_ref(), &(round_series.current_epoch + 1).to_le_bytes()],
+        bump
+    )]
+    pub next_round: Account<'info, Round>,
+    
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceRoundBet<'info> {
+    #[account(
+        mut,
+        seeds = [b"round_series", &round_series.series_id.to_le_bytes()],
+        bump = round_series.bump
+    )]
+    pub round_series: Account<'info, RoundSeries>,
+    
+    #[account(
+        mut,
+        seeds = [b"round", round_series.key().as_ref(), &round.epoch.to_le_bytes()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + Position::LEN,
+        seeds = [b"position", round.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // Optional accounts for USDC series
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"series_vault", round_series.key().as_ref()],
+        bump
+    )]
+    pub series_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRound<'info> {
+    #[account(
+        mut,
+        seeds = [b"round_series", &round_series.series_id.to_le_bytes()],
+        bump = round_series.bump
+    )]
+    pub round_series: Account<'info, RoundSeries>,
+    
+    #[account(
+        mut,
+        seeds = [b"round", round_series.key().as_ref(), &round.epoch.to_le_bytes()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+    
+    #[account(
+        mut,
+        seeds = [b"position", round.key().as_ref(), user.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // Optional accounts for USDC series
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"series_vault", round_series.key().as_ref()],
+        bump
+    )]
+    pub series_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct SweepRound<'info> {
+    #[account(
+        mut,
+        seeds = [b"round_series", &round_series.series_id.to_le_bytes()],
+        bump = round_series.bump
+    )]
+    pub round_series: Account<'info, RoundSeries>,
+    
+    #[account(
+        mut,
+        seeds = [b"round", round_series.key().as_ref(), &round.epoch.to_le_bytes()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ CryptoBetError::UnauthorizedAdmin
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    pub admin: Signer<'info>,
+    
+    /// CHECK: Must match config.treasury, receives lamports for SOL series
+    #[account(mut)]
+    pub treasury: UncheckedAccount<'info>,
+    
+    // Optional accounts for USDC series
+    #[account(mut)]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"series_vault", round_series.key().as_ref()],
+        bump
+    )]
+    pub series_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRoundPosition<'info> {
+    #[account(
+        seeds = [b"round", round.series.as_ref(), &round.epoch.to_le_bytes()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+    
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"position", round.key().as_ref(), user.key().as_ref()],
+        bump = position.bump,
+        has_one = user
+    )]
+    pub position: Account<'info, Position>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(market_type: MarketType)]
+pub struct InitializeHousePool<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ CryptoBetError::UnauthorizedAdmin
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + HousePool::LEN,
+        seeds = [b"house_pool", config.accepted_mint(market_type).as_ref()],
+        bump
+    )]
+    pub house_pool: Account<'info, HousePool>,
+    
+    // Optional vault for USDC pools
+    #[account(
+        init_if_needed,
+        payer = admin,
+        token::mint = usdc_mint,
+        token::authority = house_pool,
+        seeds = [b"house_vault", house_pool.key().as_ref()],
+        bump
+    )]
+    pub house_vault: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: USDC mint address
+    pub usdc_mint: Option<AccountInfo<'info>>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct DepositHouseLiquidity<'info> {
+    #[account(
+        mut,
+        seeds = [b"house_pool", house_pool.accepted_mint.as_ref()],
+        bump = house_pool.bump
+    )]
+    pub house_pool: Account<'info, HousePool>,
+    
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        space = 8 + HouseDeposit::LEN,
+        seeds = [b"house_deposit", house_pool.key().as_ref(), depositor.key().as_ref()],
+        bump
+    )]
+    pub house_deposit: Account<'info, HouseDeposit>,
+    
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    
+    // Optional accounts for USDC pools
+    #[account(mut)]
+    pub depositor_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"house_vault", house_pool.key().as_ref()],
+        bump
+    )]
+    pub house_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawHouseLiquidity<'info> {
+    #[account(
+        mut,
+        seeds = [b"house_pool", house_pool.accepted_mint.as_ref()],
+        bump = house_pool.bump
+    )]
+    pub house_pool: Account<'info, HousePool>,
+    
+    #[account(
+        mut,
+        seeds = [b"house_deposit", house_pool.key().as_ref(), depositor.key().as_ref()],
+        bump = house_deposit.bump
+    )]
+    pub house_deposit: Account<'info, HouseDeposit>,
+    
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    
+    // Optional accounts for USDC pools
+    #[account(mut)]
+    pub depositor_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"house_vault", house_pool.key().as_ref()],
+        bump
+    )]
+    pub house_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct SetFixedOdds<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump,
+        has_one = authority
+    )]
+    pub market: Account<'info, Market>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimFixedOdds<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        mut,
+        seeds = [b"house_pool", house_pool.accepted_mint.as_ref()],
+        bump = house_pool.bump
+    )]
+    pub house_pool: Account<'info, HousePool>,
+    
+    #[account(
+        mut,
+        seeds = [b"position", market.key().as_ref(), user.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"house_vault", house_pool.key().as_ref()],
+        bump
+    )]
+    pub house_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseHouseReservation<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        mut,
+        seeds = [b"house_pool", house_pool.accepted_mint.as_ref()],
+        bump = house_pool.bump
+    )]
+    pub house_pool: Account<'info, HousePool>,
+    
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(market_type: MarketType)]
+pub struct InitializeLpPool<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ CryptoBetError::UnauthorizedAdmin
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + LpPool::LEN,
+        seeds = [b"lp_pool", config.accepted_mint(market_type).as_ref()],
+        bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    
+    #[account(
+        init,
+        payer = admin,
+        mint::decimals = config.decimals(market_type),
+        mint::authority = lp_pool,
+        seeds = [b"lp_mint", lp_pool.key().as_ref()],
+        bump
+    )]
+    pub lp_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = admin,
+        token::mint = lp_mint,
+        token::authority = lp_pool,
+        seeds = [b"lp_escrow", lp_pool.key().as_ref()],
+        bump
+    )]
+    pub lp_escrow: Account<'info, TokenAccount>,
+    
+    // Optional vault for USDC pools
+    #[account(
+        init_if_needed,
+        payer = admin,
+        token::mint = usdc_mint,
+        token::authority = lp_pool,
+        seeds = [b"lp_vault", lp_pool.key().as_ref()],
+        bump
+    )]
+    pub lp_vault: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: USDC mint address
+    pub usdc_mint: Option<AccountInfo<'info>>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct DepositLp<'info> {
+    #[account(
+        mut,
+        seeds = [b"lp_pool", lp_pool.accepted_mint.as_ref()],
+        bump = lp_pool.bump,
+        has_one = lp_mint
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    
+    #[account(mut)]
+    pub lp_mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        token::mint = lp_mint,
+        token::authority = depositor
+    )]
+    pub depositor_lp_token_account: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    
+    // Optional accounts for USDC pools
+    #[account(mut)]
+    pub depositor_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"lp_vault", lp_pool.key().as_ref()],
+        bump
+    )]
+    pub lp_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RequestLpWithdrawal<'info> {
+    #[account(
+        seeds = [b"lp_pool", lp_pool.accepted_mint.as_ref()],
+        bump = lp_pool.bump,
+        has_one = lp_mint
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    
+    pub lp_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = depositor,
+        space = 8 + LpWithdrawal::LEN,
+        seeds = [b"lp_withdrawal", lp_pool.key().as_ref(), depositor.key().as_ref()],
+        bump
+    )]
+    pub lp_withdrawal: Account<'info, LpWithdrawal>,
+    
+    #[account(
+        mut,
+        seeds = [b"lp_escrow", lp_pool.key().as_ref()],
+        bump
+    )]
+    pub lp_escrow: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = lp_mint,
+        token::authority = depositor
+    )]
+    pub depositor_lp_token_account: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CompleteLpWithdrawal<'info> {
+    #[account(
+        mut,
+        seeds = [b"lp_pool", lp_pool.accepted_mint.as_ref()],
+        bump = lp_pool.bump,
+        has_one = lp_mint
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    
+    #[account(mut)]
+    pub lp_mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        close = depositor,
+        seeds = [b"lp_withdrawal", lp_pool.key().as_ref(), depositor.key().as_ref()],
+        bump = lp_withdrawal.bump
+    )]
+    pub lp_withdrawal: Account<'info, LpWithdrawal>,
+    
+    #[account(
+        mut,
+        seeds = [b"lp_escrow", lp_pool.key().as_ref()],
+        bump
+    )]
+    pub lp_escrow: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    
+    // Optional accounts for USDC pools
+    #[account(mut)]
+    pub depositor_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"lp_vault", lp_pool.key().as_ref()],
+        bump
+    )]
+    pub lp_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SeedMarket<'info> {
+    #[account(
+        mut,
+        seeds = [b"lp_pool", lp_pool.accepted_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + Position::LEN,
+        seeds = [b"position", market.key().as_ref(), lp_pool.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ CryptoBetError::UnauthorizedAdmin
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    // Optional accounts for USDC pools
+    #[account(
+        mut,
+        seeds = [b"lp_vault", lp_pool.key().as_ref()],
+        bump
+    )]
+    pub lp_vault: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump = market.vault_bump
+    )]
+    pub market_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct HarvestLpMarket<'info> {
+    #[account(
+        mut,
+        seeds = [b"lp_pool", lp_pool.accepted_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        mut,
+        seeds = [b"position", market.key().as_ref(), lp_pool.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+    
+    pub caller: Signer<'info>,
+    
+    // Optional accounts for USDC pools
+    #[account(
+        mut,
+        seeds = [b"lp_vault", lp_pool.key().as_ref()],
+        bump
+    )]
+    pub lp_vault: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump = market.vault_bump
+    )]
+    pub market_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct AmmTrade<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    // The LP pool's position holds the AMM reserves
+    #[account(
+        mut,
+        seeds = [b"position", market.key().as_ref(), market.amm_pool.as_ref()],
+        bump = pool_position.bump
+    )]
+    pub pool_position: Account<'info, Position>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + Position::LEN,
+        seeds = [b"position", market.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump = market.vault_bump
+    )]
+    pub market_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeOrderBook<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + OrderBook::LEN,
+        seeds = [b"order_book", market.key().as_ref()],
+        bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceOrder<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        mut,
+        seeds = [b"order_book", market.key().as_ref()],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + Position::LEN,
+        seeds = [b"position", market.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump = market.vault_bump
+    )]
+    pub market_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelOrder<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        mut,
+        seeds = [b"order_book", market.key().as_ref()],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+    
+    #[account(
+        mut,
+        seeds = [b"position", market.key().as_ref(), user.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump = market.vault_bump
+    )]
+    pub market_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawOrderProceeds<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        mut,
+        seeds = [b"position", market.key().as_ref(), user.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump = market.vault_bump
+    )]
+    pub market_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct DepositQuoteCollateral<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + Position::LEN,
+        seeds = [b"position", market.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump = market.vault_bump
+    )]
+    pub market_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+#[instruction(quote: Quote)]
+pub struct FillQuote<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        mut,
+        seeds = [b"position", market.key().as_ref(), quote.maker.as_ref()],
+        bump = maker_position.bump
+    )]
+    pub maker_position: Account<'info, Position>,
+    
+    #[account(
+        init_if_needed,
+        payer = taker,
+        space = 8 + Position::LEN,
+        seeds = [b"position", market.key().as_ref(), taker.key().as_ref()],
+        bump
+    )]
+    pub taker_position: Account<'info, Position>,
+    
+    // Fails to initialize if the quote was already filled
+    #[account(
+        init,
+        payer = taker,
+        space = 8 + UsedNonce::LEN,
+        seeds = [b"quote_nonce", quote.maker.as_ref(), quote.nonce.to_le_bytes().as_ref()],
         bump
     )]
+    pub used_nonce: Account<'info, UsedNonce>,
+    
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub taker_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump = market.vault_bump
+    )]
     pub market_vault: Option<Account<'info, TokenAccount>>,
     
+    /// CHECK: Instructions sysvar, checked by address
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+#[instruction(challenge_id: u64)]
+pub struct CreateChallenge<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Challenge::LEN,
+        seeds = [b"challenge", creator.key().as_ref(), challenge_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub challenge: Account<'info, Challenge>,
+    
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub creator_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        init_if_needed,
+        payer = creator,
+        token::mint = usdc_mint,
+        token::authority = challenge,
+        seeds = [b"challenge_vault", challenge.key().as_ref()],
+        bump
+    )]
+    pub challenge_vault: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: USDC mint address
+    pub usdc_mint: Option<AccountInfo<'info>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptChallenge<'info> {
+    #[account(
+        mut,
+        seeds = [b"challenge", challenge.creator.as_ref(), challenge.challenge_id.to_le_bytes().as_ref()],
+        bump = challenge.bump,
+        has_one = market
+    )]
+    pub challenge: Account<'info, Challenge>,
+    
+    pub market: Account<'info, Market>,
+    
+    #[account(mut)]
+    pub acceptor: Signer<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub acceptor_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"challenge_vault", challenge.key().as_ref()],
+        bump
+    )]
+    pub challenge_vault: Option<Account<'info, TokenAccount>>,
+    
     pub system_program: Program<'info, System>,
     pub token_program: Option<Program<'info, Token>>,
 }
 
-#[derive(Accounts)]
-pub struct ResolveMarket<'info> {
-    #[account(
-        mut,
-        seeds = [b"market", &market.market_id.to_le_bytes()],
-        bump = market.bump,
-        has_one = authority
-    )]
-    pub market: Account<'info, Market>,
-    
-    pub authority: Signer<'info>,
+#[derive(Accounts)]
+pub struct CancelChallenge<'info> {
+    #[account(
+        mut,
+        close = creator,
+        seeds = [b"challenge", challenge.creator.as_ref(), challenge.challenge_id.to_le_bytes().as_ref()],
+        bump = challenge.bump,
+        has_one = market,
+        has_one = creator
+    )]
+    pub challenge: Account<'info, Challenge>,
+    
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    
+    /// CHECK: Challenge creator, checked by has_one
+    #[account(mut)]
+    pub creator: AccountInfo<'info>,
+    
+    pub caller: Signer<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub creator_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"challenge_vault", challenge.key().as_ref()],
+        bump
+    )]
+    pub challenge_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct SettleChallenge<'info> {
+    #[account(
+        mut,
+        close = creator,
+        seeds = [b"challenge", challenge.creator.as_ref(), challenge.challenge_id.to_le_bytes().as_ref()],
+        bump = challenge.bump,
+        has_one = market,
+        has_one = creator,
+        has_one = acceptor
+    )]
+    pub challenge: Account<'info, Challenge>,
+    
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    
+    /// CHECK: Challenge creator, checked by has_one
+    #[account(mut)]
+    pub creator: AccountInfo<'info>,
+    
+    /// CHECK: Challenge acceptor, checked by has_one
+    #[account(mut)]
+    pub acceptor: AccountInfo<'info>,
+    
+    pub caller: Signer<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub creator_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub acceptor_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"challenge_vault", challenge.key().as_ref()],
+        bump
+    )]
+    pub challenge_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct SetMarketLimits<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump,
+        has_one = authority
+    )]
+    pub market: Account<'info, Market>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetStableMint<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ CryptoBetError::UnauthorizedAdmin
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    pub stable_mint: Account<'info, Mint>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimBasketCollateral<'info> {
+    #[account(
+        seeds = [b"market_group", &market_group.group_id.to_le_bytes()],
+        bump = market_group.bump
+    )]
+    pub market_group: Account<'info, MarketGroup>,
+    
+    #[account(
+        mut,
+        seeds = [b"market", &market.market_id.to_le_bytes()],
+        bump = market.bump,
+        constraint = market.group == market_group.key() @ CryptoBetError::InvalidMarketGroup
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        mut,
+        seeds = [b"position", market.key().as_ref(), user.key().as_ref()],
+        bump = position.bump,
+        has_one = user
+    )]
+    pub position: Account<'info, Position>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    // Optional accounts for USDC markets
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump = market.vault_bump
+    )]
+    pub market_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+// Account Structs
+
+#[account]
+pub struct Market {
+    pub authority: Pubkey,          // 32
+    pub market_id: u64,             // 8
+    pub title: String,              // 4 + 100
+    pub description: String,        // 4 + 500
+    pub category: String,           // 4 + 50
+    pub resolution_time: i64,       // 8
+    pub state: MarketState,         // 1
+    pub market_type: MarketType,    // 1 - NEW: Degen vs Stable
+    pub accepted_mint: Pubkey,      // 32 - NEW: SOL or USDC mint
+    pub total_yes_amount: u64,      // 8 - Token amounts
+    pub total_no_amount: u64,       // 8 - Token amounts
+    pub total_yes_shares: u64,      // 8 - Share counts
+    pub total_no_shares: u64,       // 8 - Share counts
+    pub total_volume: u64,          // 8
+    pub resolved_outcome: Option<bool>, // 1 + 1
+    pub created_at: i64,            // 8
+    pub bump: u8,                   // 1
+    pub creator_fee_bps: u16,       // 2 - Creator cut of winning payouts
+    pub creator_fees_accrued: u64,  // 8 - Unwithdrawn creator fees
+    pub total_complete_sets: u64,   // 8 - Outstanding YES+NO sets, backed 1:1 by collateral
+    pub yes_mint: Pubkey,           // 32 - YES outcome token (default if not tokenized)
+    pub no_mint: Pubkey,            // 32 - NO outcome token (default if not tokenized)
+    pub open_positions: u64,        // 8 - Position accounts not yet closed
+    pub claim_deadline: i64,        // 8 - Claims close and unclaimed funds can be swept
+    pub total_owed: u64,            // 8 - Collateral owed to claimants, fixed at settlement
+    pub total_claimed: u64,         // 8 - Paid out, assigned as creator fees, or swept
+    pub total_paid_out: u64,        // 8 - Winnings and refunds actually sent to users
+    pub shares_settled: u64,        // 8 - Shares redeemed through claims or refunds
+    pub scalar_range: Option<ScalarRange>, // 1 + 16 - Set for scalar (range) markets
+    pub resolved_value: Option<i64>,       // 1 + 8 - Resolution value of a scalar market
+    pub parent: Option<ParentCondition>,   // 1 + 33 - Set for conditional markets
+    pub group: Pubkey,              // 32 - Mutually exclusive market group, default if none
+    pub basket_yes_shares: u64,     // 8 - YES set shares from NO-basket conversions, backed at group settlement
+    pub basket_no_shares: u64,      // 8 - NO set shares given up in NO-basket conversions
+    pub group_settled: bool,        // 1
+    pub fixed_odds: Option<FixedOddsConfig>, // 1 + 24 - Set for fixed-odds (house-priced) markets
+    pub yes_liability: u64,         // 8 - House payout owed if YES wins
+    pub no_liability: u64,          // 8 - House payout owed if NO wins
+    pub house_stakes: u64,          // 8 - Stakes taken by the house on this market
+    pub house_reserved: u64,        // 8 - House pool liquidity reserved for this market
+    pub order_escrow: u64,          // 8 - Collateral held for open bids and unwithdrawn order proceeds
+    pub limits: MarketLimits,       // 34 - Bet, position and volume limits enforced on bets
+    pub decimals: u8,               // 1 - Decimals of the accepted mint, read at creation
+    pub vault_bump: u8,             // 1 - Bump of the Stable collateral vault, owned by this market
+    pub basket_collateral: u64,     // 8 - Collateral owed to NO-basket converters on this market, funded at group settlement
+    pub house_min_reserved: u64,    // 8 - Best-case share of the house pool's reservation for this market
+    pub amm_pool: Pubkey,           // 32 - LP pool whose position backs the AMM, default if unseeded
+    pub amm_yes_reserve: u64,       // 8 - YES set shares the AMM trades from, held by the pool's position
+    pub amm_no_reserve: u64,        // 8 - NO set shares the AMM trades from, held by the pool's position
+    pub open_parlays: u64,          // 8 - Unsettled parlays with a leg on this market
+    pub open_challenges: u64,       // 8 - Challenges on this market not yet settled or refunded
+}
+
+impl Market {
+    pub const LEN: usize = 32 + 8 + (4 + 100) + (4 + 500) + (4 + 50) + 8 + 1 + 1 + 32 + 8 + 8 + 8 + 8 + 8 + 2 + 8 + 1 + 2 + 8 + 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + (1 + 16) + (1 + 8) + (1 + 33) + 32 + 8 + 8 + 1 + (1 + 24) + 8 + 8 + 8 + 8 + 8 + MarketLimits::LEN + 1 + 1 + 8 + 8 + 32 + 8 + 8 + 8 + 8;
+
+    /// Address of the Stable collateral vault, derived from the stored bump
+    pub fn vault_address(&self, market: &Pubkey) -> Result<Pubkey> {
+        Pubkey::create_program_address(&[b"vault", market.as_ref(), &[self.vault_bump]], &crate::ID)
+            .map_err(|_| error!(CryptoBetError::MissingVault))
+    }
+
+    pub fn is_scalar(&self) -> bool {
+        self.scalar_range.is_some()
+    }
+
+    pub fn is_fixed_odds(&self) -> bool {
+        self.fixed_odds.is_some()
+    }
+
+    /// Book a fixed-odds bet against the house, returning the payout locked in for it.
+    /// The house pool reserves the worst-case payout and the market's exposure limit caps its net loss
+    pub fn record_fixed_odds_bet(&mut self, house_pool: &mut HousePool, side: BetSide, amount: u64) -> Result<u64> {
+        let config = self.fixed_odds.ok_or(CryptoBetError::NotFixedOddsMarket)?;
+        let (odds_bps, liability) = match side {
+            BetSide::Yes | BetSide::Long => (config.yes_odds_bps, &mut self.yes_liability),
+            BetSide::No | BetSide::Short => (config.no_odds_bps, &mut self.no_liability),
+        };
+        let payout = (amount as u128)
+            .checked_mul(odds_bps as u128)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_div(BPS_DENOMINATOR as u128)
+            .ok_or(CryptoBetError::DivisionByZero)? as u64;
+        *liability = liability
+            .checked_add(payout)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        self.house_stakes = self.house_stakes
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        let worst_case = self.yes_liability.max(self.no_liability);
+        let best_case = self.yes_liability.min(self.no_liability);
+        require!(
+            worst_case.saturating_sub(self.house_stakes) <= config.max_exposure,
+            CryptoBetError::ExposureLimitExceeded
+        );
+
+        house_pool.total_liquidity = house_pool.total_liquidity
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        self.set_house_reserved_range(house_pool, worst_case, best_case)?;
+        require!(
+            house_pool.reserved <= house_pool.total_liquidity,
+            CryptoBetError::InsufficientPoolLiquidity
+        );
+
+        Ok(payout)
+    }
+
+    /// Move this market's reservation in the house pool to `reserved`, once its payouts are known
+    pub fn set_house_reserved(&mut self, house_pool: &mut HousePool, reserved: u64) -> Result<()> {
+        self.set_house_reserved_range(house_pool, reserved, reserved)
+    }
+
+    /// Move this market's reservation in the house pool to its worst- and best-case payouts
+    pub fn set_house_reserved_range(
+        &mut self,
+        house_pool: &mut HousePool,
+        reserved: u64,
+        min_reserved: u64,
+    ) -> Result<()> {
+        house_pool.reserved = house_pool.reserved
+            .checked_sub(self.house_reserved)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_add(reserved)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        house_pool.min_reserved = house_pool.min_reserved
+            .checked_sub(self.house_min_reserved)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_add(min_reserved)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        self.house_reserved = reserved;
+        self.house_min_reserved = min_reserved;
+        Ok(())
+    }
+
+    /// Whether a conditional market's parent condition held: Some(true) met, Some(false) failed,
+    /// None while the parent is undecided. Markets without a parent always report met
+    pub fn parent_condition(&self, parent_market: Option<&Account<Market>>) -> Result<Option<bool>> {
+        let Some(condition) = self.parent else {
+            return Ok(Some(true));
+        };
+        let parent_market = parent_market.ok_or(CryptoBetError::MissingParentMarket)?;
+        require_keys_eq!(parent_market.key(), condition.market, CryptoBetError::InvalidParentMarket);
+
+        Ok(match parent_market.state {
+            MarketState::Active => None,
+            MarketState::Resolved => Some(parent_market.resolved_outcome == Some(condition.outcome)),
+            MarketState::Cancelled => Some(false),
+        })
+    }
+
+    pub fn require_parent_condition(&self, parent_market: Option<&Account<Market>>) -> Result<()> {
+        match self.parent_condition(parent_market)? {
+            Some(true) => Ok(()),
+            Some(false) => err!(CryptoBetError::ParentConditionFailed),
+            None => err!(CryptoBetError::ParentNotResolved),
+        }
+    }
+
+    /// Grouped markets can only pay out once the group has moved basket collateral
+    pub fn awaiting_group_settlement(&self) -> bool {
+        self.group != Pubkey::default() && !self.group_settled
+    }
+
+    pub fn has_basket_activity(&self) -> bool {
+        self.basket_yes_shares > 0 || self.basket_no_shares > 0
+    }
+
+    pub fn is_tokenized(&self) -> bool {
+        self.yes_mint != Pubkey::default()
+    }
+
+    pub fn outcome_mint(&self, yes: bool) -> Pubkey {
+        if yes { self.yes_mint } else { self.no_mint }
+    }
+
+    /// Give a market that settles late at least MIN_CLAIM_PERIOD to claim in,
+    /// so its funds can't be swept before anyone had a chance to claim them
+    pub fn extend_claim_deadline(&mut self) -> Result<()> {
+        let earliest = Clock::get()?.unix_timestamp
+            .checked_add(MIN_CLAIM_PERIOD)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        self.claim_deadline = self.claim_deadline.max(earliest);
+        Ok(())
+    }
+
+    /// Collateral held for bettors and complete sets (excludes rent and unrelated lamports)
+    pub fn total_collateral(&self) -> Result<u64> {
+        // Fixed-odds stakes are held by the house pool, not the market
+        if self.is_fixed_odds() {
+            return Ok(0);
+        }
+        Ok(self.total_yes_amount
+            .checked_add(self.total_no_amount)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_add(self.total_complete_sets)
+            .ok_or(CryptoBetError::MathOverflow)?)
+    }
+
+    /// Add a bet to the side totals and market volume
+    pub fn record_bet(&mut self, side: BetSide, amount: u64, shares: u64) -> Result<()> {
+        match side {
+            BetSide::Yes | BetSide::Long => {
+                self.total_yes_amount = self.total_yes_amount
+                    .checked_add(amount)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+                self.total_yes_shares = self.total_yes_shares
+                    .checked_add(shares)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+            }
+            BetSide::No | BetSide::Short => {
+                self.total_no_amount = self.total_no_amount
+                    .checked_add(amount)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+                self.total_no_shares = self.total_no_shares
+                    .checked_add(shares)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+            }
+        }
+        self.total_volume = self.total_volume
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        require!(
+            self.total_volume <= scale_limit(MAX_MARKET_VOLUME, self.decimals),
+            CryptoBetError::MarketVolumeTooHigh
+        );
+        Ok(())
+    }
+
+    /// Payout for winning bet shares and complete-set shares, returned as (payout, creator_fee)
+    pub fn winning_payout(&self, winning_shares: u64, winning_set_shares: u64) -> Result<(u64, u64)> {
+        let mut winnings = 0u64;
+        if winning_shares > 0 {
+            // Use total pool for winnings calculation
+            let total_winning_pool = self.total_yes_amount
+                .checked_add(self.total_no_amount)
+                .ok_or(CryptoBetError::MathOverflow)?;
+
+            // Use correct share totals for calculation
+            let total_winning_shares = match self.resolved_outcome {
+                Some(true) => self.total_yes_shares,
+                Some(false) => self.total_no_shares,
+                None => return err!(CryptoBetError::MarketNotResolved),
+            };
+
+            require!(total_winning_shares > 0, CryptoBetError::NoWinningShares);
+
+            // Calculate proportional winnings: (user_winning_shares / total_winning_shares) * total_pool
+            winnings = (winning_shares as u128)
+                .checked_mul(total_winning_pool as u128)
+                .ok_or(CryptoBetError::MathOverflow)?
+                .checked_div(total_winning_shares as u128)
+                .ok_or(CryptoBetError::DivisionByZero)? as u64;
+        }
+
+        // Creator fee is taken out of the gross payout and left in the market
+        // (lamports or vault tokens) until the creator withdraws it
+        let creator_fee = (winnings as u128)
+            .checked_mul(self.creator_fee_bps as u128)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_div(BPS_DENOMINATOR as u128)
+            .ok_or(CryptoBetError::DivisionByZero)? as u64;
+
+        // Complete-set shares sit outside the parimutuel pool and redeem at par, fee-free
+        let payout = winnings
+            .checked_sub(creator_fee)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_add(winning_set_shares)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        Ok((payout, creator_fee))
+    }
+
+    /// Book a claim or refund against the settlement totals
+    pub fn record_payout(&mut self, payout: u64, creator_fee: u64, settled_shares: u64) -> Result<()> {
+        self.creator_fees_accrued = self.creator_fees_accrued
+            .checked_add(creator_fee)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        // The creator fee is no longer unclaimed; it is owed to the creator instead
+        self.total_claimed = self.total_claimed
+            .checked_add(payout)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_add(creator_fee)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        self.total_paid_out = self.total_paid_out
+            .checked_add(payout)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        self.shares_settled = self.shares_settled
+            .checked_add(settled_shares)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Shares that can still be redeemed once the market is settled
+    pub fn total_settleable_shares(&self) -> Result<u64> {
+        if self.state == MarketState::Resolved && self.is_scalar() {
+            let (long_pot, short_pot) = self.scalar_pots()?;
+            let long_shares = if long_pot > 0 { self.total_yes_shares } else { 0 };
+            let short_shares = if short_pot > 0 { self.total_no_shares } else { 0 };
+            return Ok(long_shares.checked_add(short_shares).ok_or(CryptoBetError::MathOverflow)?);
+        }
+
+        let shares = match (self.state, self.resolved_outcome) {
+            (MarketState::Resolved, Some(true)) => self.total_yes_shares
+                .checked_add(self.total_complete_sets)
+                .and_then(|shares| shares.checked_add(self.basket_yes_shares)),
+            (MarketState::Resolved, Some(false)) => self.total_no_shares
+                .checked_add(self.total_complete_sets)
+                .and_then(|shares| shares.checked_sub(self.basket_no_shares)),
+            (MarketState::Cancelled, _) => self.total_yes_shares
+                .checked_add(self.total_no_shares)
+                .and_then(|shares| shares.checked_add(self.total_complete_sets))
+                .and_then(|shares| shares.checked_add(self.total_complete_sets)),
+            _ => return Ok(u64::MAX),
+        };
+        Ok(shares.ok_or(CryptoBetError::MathOverflow)?)
+    }
+
+    pub fn is_fully_settled(&self) -> Result<bool> {
+        Ok(self.basket_collateral == 0 && self.shares_settled >= self.total_settleable_shares()?)
+    }
+
+    /// Split the pool between the LONG (YES) and SHORT (NO) sides by where the value landed.
+    /// A side nobody bet on forfeits its share to the other side.
+    pub fn scalar_pots(&self) -> Result<(u64, u64)> {
+        let range = self.scalar_range.ok_or(CryptoBetError::NotScalarMarket)?;
+        let value = self.resolved_value.ok_or(CryptoBetError::MarketNotResolved)?;
+        let pool = self.total_yes_amount
+            .checked_add(self.total_no_amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        if self.total_yes_shares == 0 {
+            return Ok((0, pool));
+        }
+        if self.total_no_shares == 0 {
+            return Ok((pool, 0));
+        }
+
+        let clamped = value.clamp(range.lower_bound, range.upper_bound);
+        let span = (range.upper_bound as i128) - (range.lower_bound as i128);
+        let offset = (clamped as i128) - (range.lower_bound as i128);
+        let long_pot = (pool as i128)
+            .checked_mul(offset)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_div(span)
+            .ok_or(CryptoBetError::DivisionByZero)? as u64;
+        let short_pot = pool
+            .checked_sub(long_pot)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        Ok((long_pot, short_pot))
+    }
+
+    /// Payout for a position on a resolved scalar market, returned as (payout, creator_fee, settled_shares)
+    pub fn scalar_settlement(&self, position: &Position) -> Result<(u64, u64, u64)> {
+        let (long_pot, short_pot) = self.scalar_pots()?;
+
+        let mut winnings = 0u64;
+        let mut settled_shares = 0u64;
+        for (shares, pot, total_shares) in [
+            (position.yes_shares, long_pot, self.total_yes_shares),
+            (position.no_shares, short_pot, self.total_no_shares),
+        ] {
+            // Shares on a side with an empty pot are not counted as settleable
+            if shares == 0 || pot == 0 {
+                continue;
+            }
+            let side_winnings = (shares as u128)
+                .checked_mul(pot as u128)
+                .ok_or(CryptoBetError::MathOverflow)?
+                .checked_div(total_shares as u128)
+                .ok_or(CryptoBetError::DivisionByZero)? as u64;
+            winnings = winnings
+                .checked_add(side_winnings)
+                .ok_or(CryptoBetError::MathOverflow)?;
+            settled_shares = settled_shares
+                .checked_add(shares)
+                .ok_or(CryptoBetError::MathOverflow)?;
+        }
+
+        let creator_fee = (winnings as u128)
+            .checked_mul(self.creator_fee_bps as u128)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_div(BPS_DENOMINATOR as u128)
+            .ok_or(CryptoBetError::DivisionByZero)? as u64;
+        let payout = winnings
+            .checked_sub(creator_fee)
+            .ok_or(CryptoBetError::MathOverflow)?;
+
+        Ok((payout, creator_fee, settled_shares))
+    }
+
+    pub fn has_amm(&self) -> bool {
+        self.amm_yes_reserve > 0 && self.amm_no_reserve > 0
+    }
+
+    /// YES price of the AMM in basis points: the NO reserve's share of both reserves
+    pub fn amm_yes_price_bps(&self) -> Result<u64> {
+        let reserves = self.amm_yes_reserve
+            .checked_add(self.amm_no_reserve)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        Ok((self.amm_no_reserve as u128)
+            .checked_mul(BPS_DENOMINATOR as u128)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_div(reserves as u128)
+            .ok_or(CryptoBetError::DivisionByZero)? as u64)
+    }
+
+    /// Trade `amount` new complete sets into the AMM and take out shares of `side`,
+    /// keeping the product of the reserves. Returns the shares taken out
+    pub fn amm_buy(&mut self, side: BetSide, amount: u64) -> Result<u64> {
+        let (reserve, other) = match side {
+            BetSide::Yes | BetSide::Long => (&mut self.amm_yes_reserve, &mut self.amm_no_reserve),
+            BetSide::No | BetSide::Short => (&mut self.amm_no_reserve, &mut self.amm_yes_reserve),
+        };
+        let product = (*reserve as u128)
+            .checked_mul(*other as u128)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        let other_after = other
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        // Round the remaining reserve up so the product never shrinks
+        let reserve_after = product
+            .checked_add(other_after as u128 - 1)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_div(other_after as u128)
+            .ok_or(CryptoBetError::DivisionByZero)? as u64;
+        let shares = reserve
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_sub(reserve_after)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        *reserve = reserve_after;
+        *other = other_after;
+        Ok(shares)
+    }
+
+    /// Take shares of `side` into the AMM and merge `amount` complete sets out of it,
+    /// keeping the product of the reserves. Returns the shares taken in
+    pub fn amm_sell(&mut self, side: BetSide, amount: u64) -> Result<u64> {
+        let (reserve, other) = match side {
+            BetSide::Yes | BetSide::Long => (&mut self.amm_yes_reserve, &mut self.amm_no_reserve),
+            BetSide::No | BetSide::Short => (&mut self.amm_no_reserve, &mut self.amm_yes_reserve),
+        };
+        require!(amount < *other, CryptoBetError::InsufficientPoolLiquidity);
+        let product = (*reserve as u128)
+            .checked_mul(*other as u128)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        let other_after = *other - amount;
+        // Round the required reserve up so the product never shrinks
+        let reserve_after = product
+            .checked_add(other_after as u128 - 1)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_div(other_after as u128)
+            .ok_or(CryptoBetError::DivisionByZero)?;
+        let reserve_after = u64::try_from(reserve_after).map_err(|_| error!(CryptoBetError::MathOverflow))?;
+        let shares = reserve_after
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_sub(*reserve)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        *reserve = reserve_after;
+        *other = other_after;
+        Ok(shares)
+    }
+
+    /// Price used to mark YES set shares: the AMM's when seeded, otherwise the pools' implied
+    /// probability, and even odds on a market nobody has bet on
+    pub fn set_share_yes_price_bps(&self) -> Result<u64> {
+        if self.has_amm() {
+            return self.amm_yes_price_bps();
+        }
+        if self.total_yes_amount == 0 && self.total_no_amount == 0 {
+            return Ok(BPS_DENOMINATOR / 2);
+        }
+        self.implied_probability_bps(BetSide::Yes)
+    }
+
+    /// Marked value of a position: the payout or refund once settled, otherwise its exposure.
+    /// Parimutuel pools price each side at its share of the pool, so an open bet's expected
+    /// payout at the market's implied probability is what was staked. Set shares, including
+    /// AMM reserves, are marked at the YES price
+    pub fn position_value(&self, position: &Position) -> Result<u64> {
+        if position.claimed {
+            return Ok(0);
+        }
+        match self.state {
+            MarketState::Active => {
+                let yes_price_bps = self.set_share_yes_price_bps()?;
+                let set_value = (position.set_yes_shares as u128)
+                    .checked_mul(yes_price_bps as u128)
+                    .and_then(|value| value.checked_add(
+                        (position.set_no_shares as u128).checked_mul((BPS_DENOMINATOR - yes_price_bps) as u128)?
+                    ))
+                    .ok_or(CryptoBetError::MathOverflow)?
+                    / BPS_DENOMINATOR as u128;
+                Ok(position.yes_shares
+                    .checked_add(position.no_shares)
+                    .and_then(|stake| stake.checked_add(set_value as u64))
+                    .ok_or(CryptoBetError::MathOverflow)?)
+            }
+            MarketState::Resolved if self.is_scalar() => Ok(self.scalar_settlement(position)?.0),
+            MarketState::Resolved => {
+                let outcome = self.resolved_outcome.ok_or(CryptoBetError::MarketNotResolved)?;
+                let (winning_shares, winning_set_shares) = position.winning_shares(outcome);
+                if winning_shares == 0 && winning_set_shares == 0 {
+                    return Ok(0);
+                }
+                Ok(self.winning_payout(winning_shares, winning_set_shares)?.0)
+            }
+            MarketState::Cancelled => Ok(position.refund()?.0),
+        }
+    }
+
+    /// Check a single bet against the market's bet size and volume limits
+    pub fn check_bet_limits(&self, amount: u64) -> Result<()> {
+        require!(amount >= self.limits.min_bet, CryptoBetError::BetTooSmall);
+        require!(amount <= self.limits.max_bet, CryptoBetError::BetTooLarge);
+        let new_total_volume = self.total_volume
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        require!(new_total_volume <= self.limits.max_volume, CryptoBetError::MarketVolumeTooHigh);
+        Ok(())
+    }
+
+    /// Check a position's cumulative totals, after a bet on `side` was recorded, against
+    /// the per-wallet limits. The side-share cap only applies once another wallet holds that side
+    pub fn check_position_limits(&self, position: &Position, side: BetSide) -> Result<()> {
+        self.check_exposure_limit(position.exposure()?)?;
+
+        let position_shares = match side {
+            BetSide::Yes | BetSide::Long => position.yes_shares,
+            BetSide::No | BetSide::Short => position.no_shares,
+        };
+        self.check_side_share_limit(side, position_shares)
+    }
+
+    /// Cap what one holder may have at stake, complete-set shares counted at par.
+    /// AMM pool positions are the market's liquidity rather than a holder and are never checked
+    pub fn check_exposure_limit(&self, exposure: u64) -> Result<()> {
+        require!(exposure <= self.limits.max_position, CryptoBetError::PositionLimitExceeded);
+        Ok(())
+    }
+
+    /// Cap the share of one side's pool a holder of `position_shares` may own
+    pub fn check_side_share_limit(&self, side: BetSide, position_shares: u64) -> Result<()> {
+        let side_shares = match side {
+            BetSide::Yes | BetSide::Long => self.total_yes_shares,
+            BetSide::No | BetSide::Short => self.total_no_shares,
+        };
+        if side_shares > position_shares {
+            let max_shares = (side_shares as u128)
+                .checked_mul(self.limits.max_side_share_bps as u128)
+                .ok_or(CryptoBetError::MathOverflow)?
+                .checked_div(BPS_DENOMINATOR as u128)
+                .ok_or(CryptoBetError::DivisionByZero)?;
+            require!(position_shares as u128 <= max_shares, CryptoBetError::SideShareLimitExceeded);
+        }
+        Ok(())
+    }
+
+    /// Probability in bps the market currently prices a side at: the side's share of the
+    /// pools, or the inverse of the house odds for fixed-odds markets
+    pub fn implied_probability_bps(&self, side: BetSide) -> Result<u64> {
+        if let Some(config) = self.fixed_odds {
+            let odds_bps = match side {
+                BetSide::Yes | BetSide::Long => config.yes_odds_bps,
+                BetSide::No | BetSide::Short => config.no_odds_bps,
+            };
+            return Ok((BPS_DENOMINATOR as u128 * BPS_DENOMINATOR as u128)
+                .checked_div(odds_bps as u128)
+                .ok_or(CryptoBetError::DivisionByZero)? as u64);
+        }
+
+        let side_amount = match side {
+            BetSide::Yes | BetSide::Long => self.total_yes_amount,
+            BetSide::No | BetSide::Short => self.total_no_amount,
+        };
+        let total_pool = self.total_yes_amount
+            .checked_add(self.total_no_amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        Ok((side_amount as u128)
+            .checked_mul(BPS_DENOMINATOR as u128)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_div(total_pool as u128)
+            .ok_or(CryptoBetError::DivisionByZero)? as u64)
+    }
+
+    /// Decimal odds x 10_000 implied by the parimutuel pools for a side
+    pub fn implied_odds_bps(&self, side: BetSide) -> Result<u64> {
+        let side_amount = match side {
+            BetSide::Yes | BetSide::Long => self.total_yes_amount,
+            BetSide::No | BetSide::Short => self.total_no_amount,
+        };
+        require!(side_amount > 0, CryptoBetError::NoOddsAvailable);
+
+        let total_pool = self.total_yes_amount
+            .checked_add(self.total_no_amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        let odds_bps = (total_pool as u128)
+            .checked_mul(BPS_DENOMINATOR as u128)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_div(side_amount as u128)
+            .ok_or(CryptoBetError::DivisionByZero)?;
+        Ok(odds_bps as u64)
+    }
+
+}
+
+#[account]
+pub struct Position {
+    pub user: Pubkey,           // 32
+    pub market: Pubkey,         // 32
+    pub yes_shares: u64,        // 8
+    pub no_shares: u64,         // 8
+    pub total_invested: u64,    // 8
+    pub claimed: bool,          // 1 - 🔒 SECURITY: Track claim status to prevent double claiming
+    pub bump: u8,               // 1
+    pub set_yes_shares: u64,    // 8 - YES shares from complete sets (redeem at par)
+    pub set_no_shares: u64,     // 8 - NO shares from complete sets (redeem at par)
+    pub fixed_yes_payout: u64,  // 8 - Fixed-odds markets: sum of payouts locked in by YES bets
+    pub fixed_no_payout: u64,   // 8 - Fixed-odds markets: sum of payouts locked in by NO bets
+    pub order_proceeds: u64,    // 8 - Collateral from filled asks, held in the market until withdrawn
+    pub open_orders: u16,       // 2 - Resting orders on the market's order book
+    pub basket_shares: u64,     // 8 - YES set shares from NO-basket conversions, locked until the group settles
+    pub basket_collateral: u64, // 8 - Collateral owed from NO-basket conversions, claimable once the group settles
+}
+
+impl Position {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 1 + 1 + 8 + 8 + 8 + 8 + 8 + 2 + 8 + 8; // Updated size
+
+    /// Add complete-set shares on one side
+    pub fn credit_set_shares(&mut self, side: BetSide, shares: u64) -> Result<()> {
+        let total = match side {
+            BetSide::Yes | BetSide::Long => &mut self.set_yes_shares,
+            BetSide::No | BetSide::Short => &mut self.set_no_shares,
+        };
+        *total = total
+            .checked_add(shares)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Add `amount` complete sets: one YES and one NO set share each
+    pub fn credit_complete_sets(&mut self, amount: u64) -> Result<()> {
+        self.credit_set_shares(BetSide::Yes, amount)?;
+        self.credit_set_shares(BetSide::No, amount)
+    }
+
+    /// Remove complete-set shares on one side
+    pub fn debit_set_shares(&mut self, side: BetSide, shares: u64) -> Result<()> {
+        // Basket YES shares are not backed by collateral until the group settles, so they stay put
+        let locked = self.basket_shares;
+        let (total, locked) = match side {
+            BetSide::Yes | BetSide::Long => (&mut self.set_yes_shares, locked),
+            BetSide::No | BetSide::Short => (&mut self.set_no_shares, 0),
+        };
+        require!(
+            total.checked_sub(locked).ok_or(CryptoBetError::MathOverflow)? >= shares,
+            CryptoBetError::InsufficientShares
+        );
+        *total = total
+            .checked_sub(shares)
+            .ok_or(CryptoBetError::InsufficientShares)?;
+        Ok(())
+    }
+
+    /// Add the payout locked in by a fixed-odds bet
+    pub fn record_fixed_payout(&mut self, side: BetSide, payout: u64) -> Result<()> {
+        let total = match side {
+            BetSide::Yes | BetSide::Long => &mut self.fixed_yes_payout,
+            BetSide::No | BetSide::Short => &mut self.fixed_no_payout,
+        };
+        *total = total
+            .checked_add(payout)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Stake plus the larger side of complete-set shares, which redeem at par
+    pub fn exposure(&self) -> Result<u64> {
+        self.total_invested
+            .checked_add(self.set_yes_shares.max(self.set_no_shares))
+            .ok_or(error!(CryptoBetError::MathOverflow))
+    }
+
+    /// Add a bet's shares and stake to the position
+    pub fn record_bet(&mut self, side: BetSide, amount: u64, shares: u64) -> Result<()> {
+        match side {
+            BetSide::Yes | BetSide::Long => {
+                self.yes_shares = self.yes_shares
+                    .checked_add(shares)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+            }
+            BetSide::No | BetSide::Short => {
+                self.no_shares = self.no_shares
+                    .checked_add(shares)
+                    .ok_or(CryptoBetError::MathOverflow)?;
+            }
+        }
+        self.total_invested = self.total_invested
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Winning (bet shares, complete-set shares) for the given outcome
+    pub fn winning_shares(&self, outcome: bool) -> (u64, u64) {
+        if outcome {
+            (self.yes_shares, self.set_yes_shares)
+        } else {
+            (self.no_shares, self.set_no_shares)
+        }
+    }
+
+    /// Refund owed on a cancelled market, returned as (amount, shares settled)
+    pub fn refund(&self) -> Result<(u64, u64)> {
+        // Each complete set was backed by one unit, so every set share refunds half a unit
+        let set_shares = self.set_yes_shares
+            .checked_add(self.set_no_shares)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        let amount = self.total_invested
+            .checked_add(set_shares / 2)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        let shares = self.yes_shares
+            .checked_add(self.no_shares)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_add(set_shares)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        Ok((amount, shares))
+    }
+
+    /// Clear everything redeemed by a claim or refund and mark the position claimed
+    pub fn mark_settled(&mut self, outcome: Option<bool>) {
+        match outcome {
+            Some(true) => {
+                self.yes_shares = 0;
+                self.set_yes_shares = 0;
+                self.basket_shares = 0;
+                self.fixed_yes_payout = 0;
+            }
+            Some(false) => {
+                self.no_shares = 0;
+                self.set_no_shares = 0;
+                self.fixed_no_payout = 0;
+            }
+            None => {
+                self.yes_shares = 0;
+                self.no_shares = 0;
+                self.set_yes_shares = 0;
+                self.set_no_shares = 0;
+                self.basket_shares = 0;
+                self.total_invested = 0;
+                self.fixed_yes_payout = 0;
+                self.fixed_no_payout = 0;
+            }
+        }
+        self.claimed = true;
+    }
+}
+
+#[account]
+pub struct ProtocolConfig {
+    pub admin: Pubkey,              // 32
+    pub treasury: Pubkey,           // 32
+    pub bump: u8,                   // 1
+    pub min_initial_liquidity: u64, // 8 - Smallest creator seed a new market must carry, in LIMIT_DECIMALS units (0 = optional)
+    pub stable_mint: Pubkey,        // 32 - Mint accepted by new Stable markets and pools on this cluster
+    pub stable_decimals: u8,        // 1
+}
+
+impl ProtocolConfig {
+    pub const LEN: usize = 32 + 32 + 1 + 8 + 32 + 1;
+
+    pub fn accepted_mint(&self, market_type: MarketType) -> Pubkey {
+        match market_type {
+            MarketType::Degen => SOL_MINT,
+            MarketType::Stable => self.stable_mint,
+        }
+    }
+
+    pub fn decimals(&self, market_type: MarketType) -> u8 {
+        match market_type {
+            MarketType::Degen => SOL_DECIMALS,
+            MarketType::Stable => self.stable_decimals,
+        }
+    }
+
+    /// Stable products must create their vaults for the configured stable mint
+    pub fn check_collateral_mint(&self, market_type: MarketType, usdc_mint: Option<&AccountInfo>) -> Result<()> {
+        if market_type == MarketType::Stable {
+            let usdc_mint = usdc_mint.ok_or(CryptoBetError::MissingCollateralMint)?;
+            require_keys_eq!(usdc_mint.key(), self.stable_mint, CryptoBetError::InvalidTokenMint);
+        }
+        Ok(())
+    }
+}
+
+#[account]
+pub struct ParlayPool {
+    pub market_type: MarketType,    // 1
+    pub accepted_mint: Pubkey,      // 32
+    pub total_liquidity: u64,       // 8 - Funding plus stakes, less payouts and withdrawals
+    pub reserved: u64,              // 8 - Potential payouts of open parlays
+    pub open_parlays: u64,          // 8
+    pub bump: u8,                   // 1
+    pub decimals: u8,               // 1 - Decimals of the accepted mint
+}
+
+impl ParlayPool {
+    pub const LEN: usize = 1 + 32 + 8 + 8 + 8 + 1 + 1;
+
+    /// Liquidity not already promised to open parlays
+    pub fn free_liquidity(&self) -> Result<u64> {
+        Ok(self.total_liquidity.saturating_sub(self.reserved))
+    }
+}
+
+#[account]
+pub struct Parlay {
+    pub user: Pubkey,               // 32
+    pub parlay_id: u64,             // 8
+    pub market_type: MarketType,    // 1
+    pub accepted_mint: Pubkey,      // 32 - Mint of the parlay pool that took the stake
+    pub legs: Vec<ParlayLeg>,       // 4 + 41 * MAX_PARLAY_LEGS
+    pub stake: u64,                 // 8
+    pub potential_payout: u64,      // 8 - Locked in at placement, after house edge
+    pub state: ParlayState,         // 1
+    pub created_at: i64,            // 8
+    pub bump: u8,                   // 1
+}
+
+impl Parlay {
+    pub const LEN: usize = 32 + 8 + 1 + 32 + (4 + ParlayLeg::LEN * MAX_PARLAY_LEGS) + 8 + 8 + 1 + 8 + 1;
+}
+
+#[account]
+pub struct MarketGroup {
+    pub authority: Pubkey,          // 32
+    pub group_id: u64,              // 8
+    pub market_type: MarketType,    // 1
+    pub markets: Vec<Pubkey>,       // 4 + 32 * MAX_GROUP_MARKETS
+    pub winner: Option<Pubkey>,     // 1 + 32 - The one market that resolved YES, if any
+    pub settled: bool,              // 1
+    pub bump: u8,                   // 1
+    pub basket_converted: bool,     // 1 - Set by the first NO-basket conversion, which fixes the group size
+}
+
+impl MarketGroup {
+    pub const LEN: usize = 32 + 8 + 1 + (4 + 32 * MAX_GROUP_MARKETS) + (1 + 32) + 1 + 1 + 1;
 }
 
-#[derive(Accounts)]
-pub struct CancelMarket<'info> {
-    #[account(
-        mut,
-        seeds = [b"market", &market.market_id.to_le_bytes()],
-        bump = market.bump,
-        has_one = authority
-    )]
-    pub market: Account<'info, Market>,
-    
-    pub authority: Signer<'info>,
+#[account]
+pub struct RoundSeries {
+    pub authority: Pubkey,          // 32
+    pub series_id: u64,             // 8
+    pub market_type: MarketType,    // 1
+    pub accepted_mint: Pubkey,      // 32
+    pub price_feed: Pubkey,         // 32 - Pyth price update account
+    pub round_length: i64,          // 8 - Seconds a round is open, and then locked
+    pub buffer_seconds: i64,        // 8 - Grace period for cranking before a round is cancelled
+    pub current_epoch: u64,         // 8 - Most recently opened round, 0 before the first crank
+    pub bump: u8,                   // 1
+    pub decimals: u8,               // 1 - Decimals of the accepted mint
 }
 
-#[derive(Accounts)]
-pub struct ClaimWinnings<'info> {
-    #[account(
-        seeds = [b"market", &market.market_id.to_le_bytes()],
-        bump = market.bump
-    )]
-    pub market: Account<'info, Market>,
-    
-    #[account(
-        mut,
-        seeds = [b"position", market.key().as_ref(), user.key().as_ref()],
-        bump = position.bump,
-        has_one = user
-    )]
-    pub position: Account<'info, Position>,
-    
-    #[account(mut)]
-    pub user: Signer<'info>,
-    
-    // Optional accounts for USDC markets
-    #[account(mut)]
-    pub user_token_account: Option<Account<'info, TokenAccount>>,
-    
-    #[account(
-        mut,
-        seeds = [b"vault", market.key().as_ref()],
-        bump
-    )]
-    pub market_vault: Option<Account<'info, TokenAccount>>,
-    
-    pub token_program: Option<Program<'info, Token>>,
+impl RoundSeries {
+    pub const LEN: usize = 32 + 8 + 1 + 32 + 32 + 8 + 8 + 8 + 1 + 1;
 }
 
-#[derive(Accounts)]
-pub struct ClaimRefund<'info> {
-    #[account(
-        seeds = [b"market", &market.market_id.to_le_bytes()],
-        bump = market.bump
-    )]
-    pub market: Account<'info, Market>,
-    
-    #[account(
-        mut,
-        seeds = [b"position", market.key().as_ref(), user.key().as_ref()],
-        bump = position.bump,
-        has_one = user
-    )]
-    pub position: Account<'info, Position>,
-    
-    #[account(mut)]
-    pub user: Signer<'info>,
-    
-    // Optional accounts for USDC markets
-    #[account(mut)]
-    pub user_token_account: Option<Account<'info, TokenAccount>>,
-    
-    #[account(
-        mut,
-        seeds = [b"vault", market.key().as_ref()],
-        bump
-    )]
-    pub market_vault: Option<Account<'info, TokenAccount>>,
-    
-    pub token_program: Option<Program<'info, Token>>,
+#[account]
+pub struct Round {
+    pub series: Pubkey,             // 32
+    pub epoch: u64,                 // 8
+    pub start_time: i64,            // 8
+    pub lock_time: i64,             // 8
+    pub close_time: i64,            // 8
+    pub lock_price: i64,            // 8 - Raw oracle price, in the feed's exponent
+    pub close_price: i64,           // 8
+    pub state: RoundState,          // 1
+    pub outcome: Option<bool>,      // 2 - Some(true) when the price went up
+    pub total_up_amount: u64,       // 8
+    pub total_down_amount: u64,     // 8
+    pub bump: u8,                   // 1
+    pub claim_deadline: i64,        // 8 - After this, unclaimed funds can be swept to the treasury
+    pub total_claimed: u64,         // 8 - Paid out to bettors or swept
 }
 
-// Account Structs
+impl Round {
+    pub const LEN: usize = 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 2 + 8 + 8 + 1 + 8 + 8;
+
+    /// Add a bet to the round's side totals
+    pub fn record_bet(&mut self, side: BetSide, amount: u64) -> Result<()> {
+        let total = match side {
+            BetSide::Yes | BetSide::Long => &mut self.total_up_amount,
+            BetSide::No | BetSide::Short => &mut self.total_down_amount,
+        };
+        *total = total
+            .checked_add(amount)
+            .ok_or(CryptoBetError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Settle a locked round from its lock and close prices.
+    /// Ties and rounds with nobody on the winning side are cancelled and refunded
+    pub fn resolve(&mut self) {
+        let up = match self.close_price.cmp(&self.lock_price) {
+            std::cmp::Ordering::Greater => true,
+            std::cmp::Ordering::Less => false,
+            std::cmp::Ordering::Equal => {
+                self.state = RoundState::Cancelled;
+                return;
+            }
+        };
+        let winning_total = if up { self.total_up_amount } else { self.total_down_amount };
+        if winning_total == 0 {
+            self.state = RoundState::Cancelled;
+            return;
+        }
+        self.state = RoundState::Resolved;
+        self.outcome = Some(up);
+    }
+}
+
+/// Read the price from a fully verified Pyth `PriceUpdateV2` account, rejecting stale updates
+pub fn read_oracle_price(price_feed: &AccountInfo, now: i64) -> Result<i64> {
+    require!(*price_feed.owner == PYTH_RECEIVER_PROGRAM_ID, CryptoBetError::InvalidPriceFeed);
+
+    // Layout: discriminator (8), write authority (32), verification level (1, Full),
+    // then the price message: feed id (32), price (8), conf (8), exponent (4), publish time (8)
+    let data = price_feed.try_borrow_data()?;
+    require!(data.len() >= 101, CryptoBetError::InvalidPriceFeed);
+    let discriminator = anchor_lang::solana_program::hash::hash(b"account:PriceUpdateV2");
+    require!(data[..8] == discriminator.to_bytes()[..8], CryptoBetError::InvalidPriceFeed);
+    require!(data[40] == 1, CryptoBetError::InvalidPriceFeed);
+
+    let price = i64::from_le_bytes(data[73..81].try_into().unwrap());
+    let publish_time = i64::from_le_bytes(data[93..101].try_into().unwrap());
+    let age = now
+        .checked_sub(publish_time)
+        .ok_or(CryptoBetError::MathOverflow)?;
+    require!(age <= MAX_PRICE_AGE, CryptoBetError::StalePrice);
+    require!(price > 0, CryptoBetError::InvalidPriceFeed);
+
+    Ok(price)
+}
+
+fn emit_round_update(round: &Round) {
+    emit!(RoundUpdated {
+        series: round.series,
+        epoch: round.epoch,
+        state: round.state,
+        lock_price: round.lock_price,
+        close_price: round.close_price,
+        outcome: round.outcome,
+    });
+}
 
 #[account]
-pub struct Market {
-    pub authority: Pubkey,          // 32
-    pub market_id: u64,             // 8
-    pub title: String,              // 4 + 100
-    pub description: String,        // 4 + 500
-    pub category: String,           // 4 + 50
-    pub resolution_time: i64,       // 8
-    pub state: MarketState,         // 1
-    pub market_type: MarketType,    // 1 - NEW: Degen vs Stable
-    pub accepted_mint: Pubkey,      // 32 - NEW: SOL or USDC mint
-    pub total_yes_amount: u64,      // 8 - Token amounts
-    pub total_no_amount: u64,       // 8 - Token amounts
-    pub total_yes_shares: u64,      // 8 - Share counts
-    pub total_no_shares: u64,       // 8 - Share counts
-    pub total_volume: u64,          // 8
-    pub resolved_outcome: Option<bool>, // 1 + 1
-    pub created_at: i64,            // 8
+pub struct HousePool {
+    pub market_type: MarketType,    // 1
+    pub accepted_mint: Pubkey,      // 32
+    pub total_liquidity: u64,       // 8 - Deposits plus stakes, less payouts and withdrawals
+    pub reserved: u64,              // 8 - Worst-case payouts owed across fixed-odds markets
+    pub total_shares: u64,          // 8
+    pub bump: u8,                   // 1
+    pub min_reserved: u64,          // 8 - Best-case payouts owed across fixed-odds markets
+}
+
+impl HousePool {
+    pub const LEN: usize = 1 + 32 + 8 + 8 + 8 + 1 + 8;
+
+    /// Liquidity not reserved for fixed-odds payouts
+    pub fn free_liquidity(&self) -> u64 {
+        self.total_liquidity.saturating_sub(self.reserved)
+    }
+
+    /// Liquidity left if every open fixed-odds market resolves in the house's favour
+    pub fn best_case_liquidity(&self) -> u64 {
+        self.total_liquidity.saturating_sub(self.min_reserved)
+    }
+
+    /// Deposits are priced at the best case and withdrawals at the worst case, so the
+    /// resolution of an open market can never move share value in a depositor's favour
+    pub fn shares_for_deposit(&self, amount: u64) -> Result<u64> {
+        let best_case_liquidity = self.best_case_liquidity();
+        if self.total_shares == 0 || best_case_liquidity == 0 {
+            return Ok(amount);
+        }
+        Ok((amount as u128)
+            .checked_mul(self.total_shares as u128)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_div(best_case_liquidity as u128)
+            .ok_or(CryptoBetError::DivisionByZero)? as u64)
+    }
+
+    pub fn amount_for_shares(&self, shares: u64) -> Result<u64> {
+        Ok((shares as u128)
+            .checked_mul(self.free_liquidity() as u128)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_div(self.total_shares as u128)
+            .ok_or(CryptoBetError::DivisionByZero)? as u64)
+    }
+}
+
+#[account]
+pub struct HouseDeposit {
+    pub owner: Pubkey,              // 32
+    pub shares: u64,                // 8
+    pub bump: u8,                   // 1
+}
+
+impl HouseDeposit {
+    pub const LEN: usize = 32 + 8 + 1;
+}
+
+#[account]
+pub struct LpPool {
+    pub market_type: MarketType,    // 1
+    pub accepted_mint: Pubkey,      // 32
+    pub lp_mint: Pubkey,            // 32
+    pub idle_liquidity: u64,        // 8 - Collateral held by the pool and not deployed to markets
+    pub markets: Vec<Pubkey>,       // 4 + 32 * MAX_LP_MARKETS - Seeded markets not yet harvested
+    pub bump: u8,                   // 1
+}
+
+impl LpPool {
+    pub const LEN: usize = 1 + 32 + 32 + 8 + (4 + 32 * MAX_LP_MARKETS) + 1;
+
+    /// Idle liquidity plus the marked value of the pool's position in every seeded market.
+    /// Expects (market, position) pairs for every seeded market, in `markets` order
+    pub fn nav<'info>(&self, accounts: &'info [AccountInfo<'info>], program_id: &Pubkey) -> Result<u64> {
+        require!(
+            accounts.len() == self.markets.len() * 2,
+            CryptoBetError::InvalidRemainingAccounts
+        );
+
+        let pool_key = Pubkey::create_program_address(
+            &[b"lp_pool", self.accepted_mint.as_ref(), &[self.bump]],
+            program_id,
+        )
+        .map_err(|_| error!(CryptoBetError::InvalidMarketAccount))?;
+
+        let mut nav = self.idle_liquidity;
+        for (index, pair) in accounts.chunks(2).enumerate() {
+            require_keys_eq!(pair[0].key(), self.markets[index], CryptoBetError::InvalidMarketAccount);
+            let market: Account<'info, Market> = Account::try_from(&pair[0])?;
+            let position: Account<'info, Position> = Account::try_from(&pair[1])?;
+            let expected_position = Pubkey::create_program_address(
+                &[b"position", pair[0].key.as_ref(), pool_key.as_ref(), &[position.bump]],
+                program_id,
+            )
+            .map_err(|_| error!(CryptoBetError::InvalidPosition))?;
+            require_keys_eq!(pair[1].key(), expected_position, CryptoBetError::InvalidPosition);
+
+            nav = nav
+                .checked_add(market.position_value(&position)?)
+                .ok_or(CryptoBetError::MathOverflow)?;
+        }
+        Ok(nav)
+    }
+}
+
+#[account]
+pub struct LpWithdrawal {
+    pub owner: Pubkey,              // 32
+    pub lp_pool: Pubkey,            // 32
+    pub lp_amount: u64,             // 8 - LP tokens escrowed for redemption
+    pub unlock_at: i64,             // 8
+    pub bump: u8,                   // 1
+}
+
+impl LpWithdrawal {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 1;
+}
+
+#[account]
+pub struct OrderBook {
+    pub market: Pubkey,             // 32
+    pub next_order_id: u64,         // 8
+    pub orders: Vec<Order>,         // 4 + Order::LEN * MAX_ORDERS - Resting orders in arrival order
+    pub bump: u8,                   // 1
+}
+
+impl OrderBook {
+    pub const LEN: usize = 32 + 8 + (4 + Order::LEN * MAX_ORDERS) + 1;
+
+    /// Best resting order a taker can fill, skipping the taker's own orders.
+    /// Price priority by the taker's effective price, then time priority
+    pub fn best_match(&self, taker: &Pubkey, side: BetSide, kind: OrderKind, price_bps: u16) -> Option<usize> {
+        let mut best: Option<(usize, u16)> = None;
+        for (index, order) in self.orders.iter().enumerate() {
+            if order.owner == *taker {
+                continue;
+            }
+            let Some(taker_price) = order.taker_price(side, kind) else {
+                continue;
+            };
+            let (crosses, better) = match (kind, best) {
+                (OrderKind::Bid, Some((_, best_price))) => (taker_price <= price_bps, taker_price < best_price),
+                (OrderKind::Bid, None) => (taker_price <= price_bps, true),
+                (OrderKind::Ask, Some((_, best_price))) => (taker_price >= price_bps, taker_price > best_price),
+                (OrderKind::Ask, None) => (taker_price >= price_bps, true),
+            };
+            if crosses && better {
+                best = Some((index, taker_price));
+            }
+        }
+        best.map(|(index, _)| index)
+    }
+
+    /// Reduce an order by a fill, removing it when exhausted. Returns whether it was removed
+    pub fn fill(&mut self, index: usize, size: u64) -> bool {
+        self.orders[index].remaining -= size;
+        if self.orders[index].remaining == 0 {
+            self.orders.remove(index);
+            return true;
+        }
+        false
+    }
+}
+
+#[account]
+pub struct UsedNonce {
+    pub maker: Pubkey,              // 32
+    pub nonce: u64,                 // 8
+    pub bump: u8,                   // 1
+}
+
+impl UsedNonce {
+    pub const LEN: usize = 32 + 8 + 1;
+}
+
+#[account]
+pub struct Challenge {
+    pub creator: Pubkey,            // 32
+    pub challenge_id: u64,          // 8
+    pub market: Pubkey,             // 32
+    pub market_type: MarketType,    // 1
+    pub creator_side: BetSide,      // 1 - YES or NO
+    pub creator_stake: u64,         // 8
+    pub counterparty_stake: u64,    // 8 - Stake the acceptor puts up
+    pub odds_bps: u64,              // 8 - Decimal odds x 10_000 the creator is paid at
+    pub counterparty: Option<Pubkey>, // 1 + 32 - Only this wallet may accept, if set
+    pub acceptor: Pubkey,           // 32 - Default until accepted
+    pub expires_at: i64,            // 8 - Last moment the challenge can be accepted
+    pub state: ChallengeState,      // 1
     pub bump: u8,                   // 1
 }
 
-impl Market {
-    pub const LEN: usize = 32 + 8 + (4 + 100) + (4 + 500) + (4 + 50) + 8 + 1 + 1 + 32 + 8 + 8 + 8 + 8 + 8 + 2 + 8 + 1;
+impl Challenge {
+    pub const LEN: usize = 32 + 8 + 32 + 1 + 1 + 8 + 8 + 8 + (1 + 32) + 32 + 8 + 1 + 1;
+}
+
+// Enums and Types
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum MarketState {
+    Active,
+    Resolved,
+    Cancelled,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum MarketType {
+    Degen,      // SOL betting
+    Stable,     // USDC betting
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum BetSide {
+    Yes,
+    No,
+    Long,       // Scalar markets: paid more the higher the value lands (YES pool)
+    Short,      // Scalar markets: paid more the lower the value lands (NO pool)
+}
+
+impl BetSide {
+    pub fn is_scalar(&self) -> bool {
+        matches!(self, BetSide::Long | BetSide::Short)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct ScalarRange {
+    pub lower_bound: i64,
+    pub upper_bound: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct BetOrder {
+    pub market_index: u8,   // Index of the market triple in remaining_accounts
+    pub side: BetSide,
+    pub amount: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct ParlayLeg {
+    pub market: Pubkey,     // 32
+    pub side: BetSide,      // 1
+    pub odds_bps: u64,      // 8 - Decimal odds x 10_000 at placement
+}
+
+impl ParlayLeg {
+    pub const LEN: usize = 32 + 1 + 8;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ParlayState {
+    Open,
+    Won,
+    Lost,
+    Refunded,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct ParentCondition {
+    pub market: Pubkey,     // 32
+    pub outcome: bool,      // 1 - Parent outcome this market is conditional on
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RoundState {
+    Open,       // Taking bets until lock_time
+    Locked,     // Start price recorded, waiting for close_time
+    Resolved,
+    Cancelled,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct FixedOddsConfig {
+    pub yes_odds_bps: u64,  // 8 - Decimal odds x 10_000 offered on YES
+    pub no_odds_bps: u64,   // 8 - Decimal odds x 10_000 offered on NO
+    pub max_exposure: u64,  // 8 - Largest net loss the house accepts on this market
+}
+
+impl FixedOddsConfig {
+    pub fn validate(&self) -> Result<()> {
+        for odds_bps in [self.yes_odds_bps, self.no_odds_bps] {
+            require!(
+                odds_bps > BPS_DENOMINATOR && odds_bps <= MAX_FIXED_ODDS_BPS,
+                CryptoBetError::InvalidFixedOdds
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct InitialLiquidity {
+    pub amount: u64,                // 8 - Collateral split across both sides
+    pub yes_probability_bps: u16,   // 2 - Opening YES (or LONG) probability
+}
+
+impl InitialLiquidity {
+    /// Split the amount into (YES, NO) stakes so the pools open at the given probability.
+    /// Each side must be at least the minimum bet for a mint with `decimals`
+    pub fn split(&self, decimals: u8) -> Result<(u64, u64)> {
+        require!(
+            self.yes_probability_bps > 0 && (self.yes_probability_bps as u64) < BPS_DENOMINATOR,
+            CryptoBetError::InvalidProbability
+        );
+
+        let yes_amount = (self.amount as u128)
+            .checked_mul(self.yes_probability_bps as u128)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_div(BPS_DENOMINATOR as u128)
+            .ok_or(CryptoBetError::DivisionByZero)? as u64;
+        let no_amount = self.amount - yes_amount;
+        require!(
+            yes_amount >= scale_limit(MIN_BET_AMOUNT, decimals)
+                && no_amount >= scale_limit(MIN_BET_AMOUNT, decimals),
+            CryptoBetError::BetTooSmall
+        );
+        Ok((yes_amount, no_amount))
+    }
 }
 
-#[account]
-pub struct Position {
-    pub user: Pubkey,           // 32
-    pub market: Pubkey,         // 32
-    pub yes_shares: u64,        // 8
-    pub no_shares: u64,         // 8
-    pub total_invested: u64,    // 8
-    pub claimed: bool,          // 1 - 🔒 SECURITY: Track claim status to prevent double claiming
-    pub bump: u8,               // 1
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum OrderKind {
+    Bid,        // Buy shares, escrowing collateral
+    Ask,        // Sell shares, escrowing them
 }
 
-impl Position {
-    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 1 + 1; // Updated size
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Order {
+    pub order_id: u64,      // 8
+    pub owner: Pubkey,      // 32
+    pub side: BetSide,      // 1 - YES or NO
+    pub kind: OrderKind,    // 1
+    pub price_bps: u16,     // 2 - Price per share as a fraction of one unit of collateral
+    pub remaining: u64,     // 8 - Unfilled size in shares
 }
 
-// Enums and Types
+impl Order {
+    pub const LEN: usize = 8 + 32 + 1 + 1 + 2 + 8;
+
+    /// Collateral value of `size` shares at this order's price. Sizes are whole lots, so it is exact
+    pub fn value(&self, size: u64) -> Result<u64> {
+        Ok((size as u128)
+            .checked_mul(self.price_bps as u128)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_div(BPS_DENOMINATOR as u128)
+            .ok_or(CryptoBetError::DivisionByZero)? as u64)
+    }
+
+    /// Price per share a taker trading `side` with `kind` pays (bid) or receives (ask) against
+    /// this order, or None if the two can't trade
+    pub fn taker_price(&self, side: BetSide, kind: OrderKind) -> Option<u16> {
+        match (kind, self.kind) {
+            (OrderKind::Bid, OrderKind::Ask) if self.side == side => Some(self.price_bps),
+            // The taker pays the rest of the unit the maker's opposite-side bid doesn't cover
+            (OrderKind::Bid, OrderKind::Bid) if self.side != side => {
+                Some(BPS_DENOMINATOR as u16 - self.price_bps)
+            }
+            (OrderKind::Ask, OrderKind::Bid) if self.side == side => Some(self.price_bps),
+            _ => None,
+        }
+    }
+}
 
+/// Off-chain quote signed by a market maker; the signed message is its Borsh serialization
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
-pub enum MarketState {
-    Active,
-    Resolved,
-    Cancelled,
+pub struct Quote {
+    pub maker: Pubkey,
+    pub market: Pubkey,
+    pub side: BetSide,      // YES or NO shares
+    pub kind: OrderKind,    // The maker's side of the trade
+    pub price_bps: u16,     // Price per share as a fraction of one unit of collateral
+    pub size: u64,          // Shares, a multiple of the lot size
+    pub expires_at: i64,
+    pub nonce: u64,
+}
+
+impl Quote {
+    /// Collateral value of the quote. Sizes are whole lots, so it is exact
+    pub fn value(&self) -> Result<u64> {
+        Ok((self.size as u128)
+            .checked_mul(self.price_bps as u128)
+            .ok_or(CryptoBetError::MathOverflow)?
+            .checked_div(BPS_DENOMINATOR as u128)
+            .ok_or(CryptoBetError::DivisionByZero)? as u64)
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
-pub enum MarketType {
-    Degen,      // SOL betting
-    Stable,     // USDC betting
+pub enum ChallengeState {
+    Open,
+    Accepted,
+    Settled,
+    Refunded,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
-pub enum BetSide {
-    Yes,
-    No,
+pub struct MarketLimits {
+    pub min_bet: u64,               // 8 - Smallest single bet
+    pub max_bet: u64,               // 8 - Largest single bet
+    pub max_position: u64,          // 8 - Most a wallet may have staked in total
+    pub max_side_share_bps: u16,    // 2 - Largest share of one side's pool a wallet may hold
+    pub max_volume: u64,            // 8 - Total volume the market accepts
+}
+
+impl MarketLimits {
+    pub const LEN: usize = 8 + 8 + 8 + 2 + 8;
+
+    /// The protocol-wide limits in base units of a mint with `decimals`,
+    /// used when a market doesn't set its own
+    pub fn protocol_default(decimals: u8) -> Self {
+        MarketLimits {
+            min_bet: scale_limit(MIN_BET_AMOUNT, decimals),
+            max_bet: scale_limit(MAX_BET_AMOUNT, decimals),
+            max_position: u64::MAX,
+            max_side_share_bps: BPS_DENOMINATOR as u16,
+            max_volume: scale_limit(MAX_MARKET_VOLUME, decimals),
+        }
+    }
+
+    pub fn validate(&self, decimals: u8) -> Result<()> {
+        require!(
+            self.min_bet > 0 && self.min_bet <= self.max_bet && self.max_bet <= self.max_volume,
+            CryptoBetError::InvalidMarketLimits
+        );
+        require!(self.max_position >= self.min_bet, CryptoBetError::InvalidMarketLimits);
+        require!(
+            self.max_side_share_bps > 0 && self.max_side_share_bps as u64 <= BPS_DENOMINATOR,
+            CryptoBetError::InvalidMarketLimits
+        );
+        require!(
+            self.max_volume <= scale_limit(MAX_MARKET_VOLUME, decimals),
+            CryptoBetError::InvalidMarketLimits
+        );
+        Ok(())
+    }
 }
 
 // Events
@@ -711,6 +8489,12 @@ pub struct MarketCreated {
     pub category: String,
     pub resolution_time: i64,
     pub market_type: MarketType,
+    pub creator_fee_bps: u16,
+    pub claim_deadline: i64,
+    pub scalar_range: Option<ScalarRange>,
+    pub parent: Option<ParentCondition>,
+    pub fixed_odds: Option<FixedOddsConfig>,
+    pub limits: MarketLimits,
 }
 
 #[event]
@@ -745,6 +8529,7 @@ pub struct WinningsClaimed {
     pub market: Pubkey,
     pub user: Pubkey,
     pub amount: u64,
+    pub creator_fee: u64,
     pub market_type: MarketType,
 }
 
@@ -756,6 +8541,364 @@ pub struct RefundClaimed {
     pub market_type: MarketType,
 }
 
+#[event]
+pub struct PositionClosed {
+    pub market: Pubkey,
+    pub user: Pubkey,
+}
+
+#[event]
+pub struct MarketClosed {
+    pub market: Pubkey,
+    pub authority: Pubkey,
+    pub open_positions: u64,
+    pub market_type: MarketType,
+}
+
+#[event]
+pub struct UnclaimedSwept {
+    pub market: Pubkey,
+    pub treasury: Pubkey,
+    pub amount: u64,
+    pub market_type: MarketType,
+}
+
+#[event]
+pub struct DustSwept {
+    pub market: Pubkey,
+    pub treasury: Pubkey,
+    pub amount: u64,
+    pub total_paid_out: u64,
+    pub market_type: MarketType,
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+}
+
+#[event]
+pub struct CreatorFeesWithdrawn {
+    pub market: Pubkey,
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub market_type: MarketType,
+}
+
+#[event]
+pub struct PositionTransferred {
+    pub market: Pubkey,
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub yes_shares: u64,
+    pub no_shares: u64,
+    pub invested_moved: u64,
+}
+
+#[event]
+pub struct OutcomeMintsCreated {
+    pub market: Pubkey,
+    pub yes_mint: Pubkey,
+    pub no_mint: Pubkey,
+    pub market_type: MarketType,
+}
+
+#[event]
+pub struct SharesSplit {
+    pub market: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub total_complete_sets: u64,
+    pub market_type: MarketType,
+}
+
+#[event]
+pub struct SharesMerged {
+    pub market: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub total_complete_sets: u64,
+    pub market_type: MarketType,
+}
+
+#[event]
+pub struct ParlayPoolUpdated {
+    pub parlay_pool: Pubkey,
+    pub total_liquidity: u64,
+    pub reserved: u64,
+    pub market_type: MarketType,
+}
+
+#[event]
+pub struct ParlayPlaced {
+    pub parlay: Pubkey,
+    pub user: Pubkey,
+    pub legs: u8,
+    pub stake: u64,
+    pub potential_payout: u64,
+    pub market_type: MarketType,
+}
+
+#[event]
+pub struct ParlaySettled {
+    pub parlay: Pubkey,
+    pub user: Pubkey,
+    pub state: ParlayState,
+    pub amount: u64,
+    pub market_type: MarketType,
+}
+
+#[event]
+pub struct ScalarMarketResolved {
+    pub market: Pubkey,
+    pub value: i64,
+    pub long_pot: u64,
+    pub short_pot: u64,
+    pub market_type: MarketType,
+}
+
+#[event]
+pub struct MarketGroupCreated {
+    pub market_group: Pubkey,
+    pub group_id: u64,
+    pub authority: Pubkey,
+    pub market_type: MarketType,
+}
+
+#[event]
+pub struct MarketAddedToGroup {
+    pub market_group: Pubkey,
+    pub market: Pubkey,
+    pub index: u8,
+}
+
+#[event]
+pub struct NoBasketConverted {
+    pub market_group: Pubkey,
+    pub user: Pubkey,
+    pub target_market: Pubkey,
+    pub amount: u64,
+    pub collateral: u64,
+}
+
+#[event]
+pub struct MarketGroupSettled {
+    pub market_group: Pubkey,
+    pub winner: Option<Pubkey>,
+    pub collateral_moved: u64,
+}
+
+#[event]
+pub struct RoundSeriesCreated {
+    pub round_series: Pubkey,
+    pub series_id: u64,
+    pub price_feed: Pubkey,
+    pub round_length: i64,
+    pub market_type: MarketType,
+}
+
+#[event]
+pub struct RoundUpdated {
+    pub series: Pubkey,
+    pub epoch: u64,
+    pub state: RoundState,
+    pub lock_price: i64,
+    pub close_price: i64,
+    pub outcome: Option<bool>,
+}
+
+#[event]
+pub struct HouseLiquidityChanged {
+    pub house_pool: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+    pub deposit: bool,
+    pub market_type: MarketType,
+}
+
+#[event]
+pub struct FixedOddsUpdated {
+    pub market: Pubkey,
+    pub yes_odds_bps: u64,
+    pub no_odds_bps: u64,
+    pub max_exposure: u64,
+}
+
+#[event]
+pub struct LpDeposited {
+    pub lp_pool: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub lp_amount: u64,
+    pub nav: u64,
+}
+
+#[event]
+pub struct LpWithdrawalRequested {
+    pub lp_pool: Pubkey,
+    pub owner: Pubkey,
+    pub lp_amount: u64,
+    pub unlock_at: i64,
+}
+
+#[event]
+pub struct LpWithdrawn {
+    pub lp_pool: Pubkey,
+    pub owner: Pubkey,
+    pub lp_amount: u64,
+    pub amount: u64,
+    pub nav: u64,
+}
+
+#[event]
+pub struct LpMarketSeeded {
+    pub lp_pool: Pubkey,
+    pub market: Pubkey,
+    pub amount: u64,
+    pub yes_reserve: u64,
+    pub no_reserve: u64,
+}
+
+#[event]
+pub struct LpMarketHarvested {
+    pub lp_pool: Pubkey,
+    pub market: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct MinInitialLiquidityUpdated {
+    pub min_initial_liquidity: u64,
+}
+
+#[event]
+pub struct InitialLiquidityAdded {
+    pub market: Pubkey,
+    pub creator: Pubkey,
+    pub yes_amount: u64,
+    pub no_amount: u64,
+}
+
+#[event]
+pub struct OrderPlaced {
+    pub market: Pubkey,
+    pub order_id: Option<u64>, // None if the order filled completely
+    pub owner: Pubkey,
+    pub side: BetSide,
+    pub kind: OrderKind,
+    pub price_bps: u16,
+    pub size: u64,
+    pub remaining: u64,
+}
+
+#[event]
+pub struct OrderFilled {
+    pub market: Pubkey,
+    pub order_id: u64,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub side: BetSide,
+    pub kind: OrderKind,
+    pub price_bps: u16,
+    pub size: u64,
+}
+
+#[event]
+pub struct OrderCancelled {
+    pub market: Pubkey,
+    pub order_id: u64,
+    pub owner: Pubkey,
+    pub remaining: u64,
+}
+
+#[event]
+pub struct OrderProceedsWithdrawn {
+    pub market: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct QuoteCollateralDeposited {
+    pub market: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct QuoteFilled {
+    pub market: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub side: BetSide,
+    pub kind: OrderKind,
+    pub price_bps: u16,
+    pub size: u64,
+    pub nonce: u64,
+}
+
+#[event]
+pub struct ChallengeCreated {
+    pub challenge: Pubkey,
+    pub market: Pubkey,
+    pub creator: Pubkey,
+    pub side: BetSide,
+    pub stake: u64,
+    pub counterparty_stake: u64,
+    pub odds_bps: u64,
+    pub counterparty: Option<Pubkey>,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct ChallengeAccepted {
+    pub challenge: Pubkey,
+    pub acceptor: Pubkey,
+    pub stake: u64,
+}
+
+#[event]
+pub struct ChallengeSettled {
+    pub challenge: Pubkey,
+    pub state: ChallengeState,
+    pub winner: Option<Pubkey>,
+    pub creator_amount: u64,
+    pub acceptor_amount: u64,
+}
+
+#[event]
+pub struct MarketLimitsUpdated {
+    pub market: Pubkey,
+    pub limits: MarketLimits,
+}
+
+#[event]
+pub struct StableMintUpdated {
+    pub stable_mint: Pubkey,
+    pub decimals: u8,
+}
+
+#[event]
+pub struct BasketCollateralClaimed {
+    pub market: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub market_type: MarketType,
+}
+
+#[event]
+pub struct AmmTraded {
+    pub market: Pubkey,
+    pub user: Pubkey,
+    pub side: BetSide,
+    pub amount: u64,
+    pub shares: u64,
+    pub buy: bool,
+    pub yes_price_bps: u64,
+}
+
 // Error Codes
 
 #[error_code]
@@ -814,10 +8957,221 @@ pub enum CryptoBetError {
     MissingTokenProgram,
     #[msg("Invalid token mint")]
     InvalidTokenMint,
+    #[msg("Creator fee exceeds protocol maximum")]
+    CreatorFeeTooHigh,
+    #[msg("No creator fees to withdraw")]
+    NoCreatorFees,
+    #[msg("Insufficient shares")]
+    InsufficientShares,
+    #[msg("Market outcome shares are already tokenized")]
+    AlreadyTokenized,
+    #[msg("Market already has bets or complete sets")]
+    MarketHasActivity,
+    #[msg("Missing outcome mint")]
+    MissingOutcomeMint,
+    #[msg("Invalid outcome mint")]
+    InvalidOutcomeMint,
+    #[msg("Missing position")]
+    MissingPosition,
+    #[msg("Invalid recipient")]
+    InvalidRecipient,
+    #[msg("Position still has funds to claim")]
+    PositionNotSettled,
+    #[msg("Market is still active")]
+    MarketStillActive,
+    #[msg("Market still has open positions")]
+    PositionsOutstanding,
+    #[msg("Unauthorized admin")]
+    UnauthorizedAdmin,
+    #[msg("Claim deadline must be at least 30 days after resolution time")]
+    InvalidClaimDeadline,
+    #[msg("Claim period has ended")]
+    ClaimPeriodEnded,
+    #[msg("Claim period has not ended yet")]
+    ClaimPeriodNotEnded,
+    #[msg("Nothing to sweep")]
+    NothingToSweep,
+    #[msg("Invalid treasury account")]
+    InvalidTreasury,
+    #[msg("Market still has unsettled shares")]
+    SharesOutstanding,
+    #[msg("Remaining accounts do not match the expected layout")]
+    InvalidRemainingAccounts,
+    #[msg("Too many markets in one batch")]
+    BatchTooLarge,
+    #[msg("Invalid market account")]
+    InvalidMarketAccount,
+    #[msg("Tokenized markets are not supported here")]
+    TokenizedMarketUnsupported,
+    #[msg("Parlay legs must be 2 to 5 distinct markets of the pool's type")]
+    InvalidParlayLegs,
+    #[msg("Market has no liquidity on that side to price against")]
+    NoOddsAvailable,
+    #[msg("Insufficient pool liquidity")]
+    InsufficientPoolLiquidity,
+    #[msg("Parlay already settled")]
+    ParlayAlreadySettled,
+    #[msg("Parlay legs are not all decided yet")]
+    ParlayNotSettled,
+    #[msg("Scalar lower bound must be below upper bound")]
+    InvalidScalarRange,
+    #[msg("Use YES/NO on binary markets and LONG/SHORT on scalar markets")]
+    InvalidBetSide,
+    #[msg("Not supported for scalar markets")]
+    ScalarMarketUnsupported,
+    #[msg("Market is not a scalar market")]
+    NotScalarMarket,
+    #[msg("Parent market account is required")]
+    MissingParentMarket,
+    #[msg("Parent market does not match")]
+    InvalidParentMarket,
+    #[msg("Parent market must be resolved first")]
+    ParentNotResolved,
+    #[msg("Parent market resolved the other way; void this market instead")]
+    ParentConditionFailed,
+    #[msg("Parent condition has not failed")]
+    ParentConditionNotFailed,
+    #[msg("Market already belongs to a group")]
+    MarketAlreadyGrouped,
+    #[msg("Market group does not match")]
+    InvalidMarketGroup,
+    #[msg("Market group is full")]
+    MarketGroupFull,
+    #[msg("Market group account is required")]
+    MissingMarketGroup,
+    #[msg("Another market in the group already resolved YES")]
+    GroupAlreadyHasWinner,
+    #[msg("Market group already settled")]
+    GroupAlreadySettled,
+    #[msg("Market group must be settled before claims")]
+    GroupNotSettled,
+    #[msg("Market has NO-basket conversions outstanding")]
+    GroupBasketOutstanding,
+    #[msg("Round length or buffer out of range")]
+    InvalidRoundLength,
+    #[msg("Invalid price feed account")]
+    InvalidPriceFeed,
+    #[msg("Oracle price is too old")]
+    StalePrice,
+    #[msg("Round account is required")]
+    MissingRound,
+    #[msg("Round is not open")]
+    RoundNotOpen,
+    #[msg("Round cannot be locked yet")]
+    RoundNotLockable,
+    #[msg("Round has not finished")]
+    RoundNotFinished,
+    #[msg("Fixed odds must be above 1.0 and at most 100.0")]
+    InvalidFixedOdds,
+    #[msg("Not supported for fixed-odds markets")]
+    FixedOddsUnsupported,
+    #[msg("Market is not a fixed-odds market")]
+    NotFixedOddsMarket,
+    #[msg("House pool account is required")]
+    MissingHousePool,
+    #[msg("House pool does not match the market type")]
+    InvalidHousePool,
+    #[msg("Bet would exceed the market's house exposure limit")]
+    ExposureLimitExceeded,
+    #[msg("Probability must be between 0 and 100% exclusive")]
+    InvalidProbability,
+    #[msg("LP pool has seeded too many open markets")]
+    LpMarketLimit,
+    #[msg("Withdrawal is still in its cooldown")]
+    WithdrawalCooldown,
+    #[msg("Initial liquidity is below the protocol minimum")]
+    InitialLiquidityTooLow,
+    #[msg("Order size must be a positive multiple of the lot size")]
+    InvalidOrderSize,
+    #[msg("Order price must be between 0 and 1 exclusive")]
+    InvalidOrderPrice,
+    #[msg("Order book is full")]
+    OrderBookFull,
+    #[msg("Order not found")]
+    OrderNotFound,
+    #[msg("Only the order owner can cancel it")]
+    UnauthorizedOrderOwner,
+    #[msg("Missing position account for a matched order")]
+    MissingMakerPosition,
+    #[msg("Open orders or order proceeds are outstanding")]
+    OpenOrdersOutstanding,
+    #[msg("Quote has expired")]
+    QuoteExpired,
+    #[msg("Quote does not match this fill")]
+    InvalidQuote,
+    #[msg("Expected an Ed25519 signature verification instruction before this one")]
+    MissingSignatureVerification,
+    #[msg("Ed25519 instruction does not verify the maker's signature over the quote")]
+    InvalidSignatureVerification,
+    #[msg("Challenge must expire in the future and before market resolution")]
+    InvalidChallengeExpiry,
+    #[msg("This wallet cannot accept the challenge")]
+    InvalidChallengeCounterparty,
+    #[msg("Challenge is not open")]
+    ChallengeNotOpen,
+    #[msg("Challenge has not been accepted")]
+    ChallengeNotAccepted,
+    #[msg("Challenge has expired")]
+    ChallengeExpired,
+    #[msg("Only the creator can cancel before expiry")]
+    ChallengeStillOpen,
+    #[msg("Token account is not owned by the recipient")]
+    InvalidTokenAccountOwner,
+    #[msg("Bet would receive fewer shares than the minimum")]
+    SlippageExceeded,
+    #[msg("Market price moved above the maximum implied probability")]
+    ImpliedProbabilityTooHigh,
+    #[msg("Bet request has expired")]
+    BetRequestExpired,
+    #[msg("Invalid market limits")]
+    InvalidMarketLimits,
+    #[msg("Bet would exceed the maximum position per wallet")]
+    PositionLimitExceeded,
+    #[msg("Bet would exceed the maximum share of one side's pool per wallet")]
+    SideShareLimitExceeded,
+    #[msg("Missing collateral mint account")]
+    MissingCollateralMint,
+    #[msg("Creator fees must be withdrawn before closing the market")]
+    CreatorFeesOutstanding,
+    #[msg("Parlay leg market does not have enough liquidity on both sides")]
+    ParlayMarketTooThin,
+    #[msg("Markets cannot join a group after NO-basket conversions")]
+    MarketGroupConverted,
+    #[msg("Position has no NO-basket collateral to claim")]
+    NoBasketCollateral,
+    #[msg("Market has no AMM liquidity")]
+    AmmNotSeeded,
+    #[msg("Parlays with a leg on this market are still unsettled")]
+    ParlaysOutstanding,
+    #[msg("Challenges on this market are still unsettled")]
+    ChallengesOutstanding,
+    #[msg("A batch can only include Stable markets of one mint")]
+    MixedMintBatch,
 }
 
 // Constants
 
-pub const MIN_BET_AMOUNT: u64 = 1_000_000; // 0.001 SOL or 0.001 USDC
-pub const MAX_BET_AMOUNT: u64 = 1_000_000_000_000; // 1,000 SOL or 1,000 USDC
-pub const MAX_MARKET_VOLUME: u64 = 10_000_000_000_000; // 10,000 SOL or 10,000 USDC 
\ No newline at end of file
+// Bet limits are in thousandths of a whole token and scaled to the accepted mint's decimals
+pub const LIMIT_DECIMALS: u8 = 3;
+pub const MIN_BET_AMOUNT: u64 = 1; // 0.001 SOL or 0.001 USDC
+pub const MAX_BET_AMOUNT: u64 = 1_000_000; // 1,000 SOL or 1,000 USDC
+pub const MAX_MARKET_VOLUME: u64 = 10_000_000; // 10,000 SOL or 10,000 USDC
+pub const MAX_CREATOR_FEE_BPS: u16 = 500; // 5% of winning payouts
+pub const BPS_DENOMINATOR: u64 = 10_000;
+pub const MIN_CLAIM_PERIOD: i64 = 30 * 24 * 60 * 60; // 30 days after resolution time
+pub const MAX_BATCH_SIZE: usize = 8; // Markets per batch instruction, bounded by compute and account limits
+pub const MAX_PARLAY_LEGS: usize = 5;
+pub const PARLAY_HOUSE_EDGE_BPS: u64 = 500; // 5% off the combined odds
+pub const MIN_PARLAY_SIDE_DEPTH: u64 = 1_000; // 1 SOL or 1 USDC on each side of a leg's market, in LIMIT_DECIMALS units
+pub const MAX_PARLAY_LEG_ODDS_BPS: u64 = 100_000; // 10.0 decimal odds per leg
+pub const MAX_PARLAY_ODDS_BPS: u64 = 1_000_000; // 100.0 combined decimal odds
+pub const MAX_GROUP_MARKETS: usize = 8;
+pub const MIN_ROUND_LENGTH: i64 = 60; // 1 minute
+pub const MAX_ROUND_LENGTH: i64 = 24 * 60 * 60; // 1 day
+pub const MAX_PRICE_AGE: i64 = 60; // Seconds an oracle price stays usable
+pub const MAX_FIXED_ODDS_BPS: u64 = 1_000_000; // 100.0 decimal odds
+pub const MAX_LP_MARKETS: usize = 10; // Seeded markets per pool, bounded by NAV accounts per transaction
+pub const LP_WITHDRAWAL_COOLDOWN: i64 = 24 * 60 * 60; // 1 day between request and redemption
+pub const AMM_FEE_BPS: u64 = 100; // 1% of each AMM trade, kept by the LP pool
+pub const MAX_ORDERS: usize = 32; // Resting orders per order book
+pub const ORDER_LOT_SIZE: u64 = BPS_DENOMINATOR; // Order sizes are multiples of this so bps prices divide exactly
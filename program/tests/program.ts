@@ -1,16 +1,2509 @@
 import * as anchor from "@coral-xyz/anchor";
-import { Program } from "@coral-xyz/anchor";
-import { Program } from "../target/types/program";
+import { Program, BN } from "@coral-xyz/anchor";
+import {
+  Ed25519Program,
+  Keypair,
+  LAMPORTS_PER_SOL,
+  PublicKey,
+  SystemProgram,
+  Transaction,
+  TransactionInstruction,
+} from "@solana/web3.js";
+import { assert } from "chai";
+import { CryptoBet } from "../target/types/crypto_bet";
 
-describe("program", () => {
-  // Configure the client to use the local cluster.
-  anchor.setProvider(anchor.AnchorProvider.env());
+const BPF_LOADER_UPGRADEABLE = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+const TOKEN_PROGRAM_ID = new PublicKey("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+const MINT_SIZE = 82;
+const TOKEN_ACCOUNT_SIZE = 165;
+const MIN_CLAIM_PERIOD = 30 * 24 * 60 * 60;
+// Loaded from tests/fixtures/price_feed.json; the price never changes and never goes stale
+const PRICE_FEED = new PublicKey("2BmY64vgR7M3ywGPcxETWXMSHt2V2dqysHSARMi4nbxs");
 
-  const program = anchor.workspace.program as Program<Program>;
+const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
 
-  it("Is initialized!", async () => {
-    // Add your test here.
-    const tx = await program.methods.initialize().rpc();
-    console.log("Your transaction signature", tx);
+describe("crypto-bet", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.CryptoBet as Program<CryptoBet>;
+  const connection = provider.connection;
+  const admin = provider.wallet;
+  const treasury = Keypair.generate();
+
+  const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId);
+
+  const marketPda = (marketId: BN) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("market"), marketId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+
+  const fundedUser = async () => {
+    const user = Keypair.generate();
+    const sig = await connection.requestAirdrop(user.publicKey, 10 * LAMPORTS_PER_SOL);
+    await connection.confirmTransaction(sig);
+    return user;
+  };
+
+  // Minimal SPL Token instructions, enough to stand up a local stable mint
+  const createTokenAccount = async (size: number, data: Buffer, keys: PublicKey[]) => {
+    const account = Keypair.generate();
+    const lamports = await connection.getMinimumBalanceForRentExemption(size);
+    const tx = new Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: admin.publicKey,
+        newAccountPubkey: account.publicKey,
+        lamports,
+        space: size,
+        programId: TOKEN_PROGRAM_ID,
+      }),
+      new TransactionInstruction({
+        programId: TOKEN_PROGRAM_ID,
+        keys: [account.publicKey, ...keys].map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })),
+        data,
+      })
+    );
+    await provider.sendAndConfirm(tx, [account]);
+    return account.publicKey;
+  };
+
+  // InitializeMint2 with the admin as mint authority and no freeze authority
+  const createMint = (decimals: number) =>
+    createTokenAccount(
+      MINT_SIZE,
+      Buffer.concat([Buffer.from([20, decimals]), admin.publicKey.toBuffer(), Buffer.from([0])]),
+      []
+    );
+
+  // InitializeAccount3
+  const createTokenAccountFor = (mint: PublicKey, owner: PublicKey) =>
+    createTokenAccount(TOKEN_ACCOUNT_SIZE, Buffer.concat([Buffer.from([18]), owner.toBuffer()]), [mint]);
+
+  // MintTo, signed by the admin
+  const mintTo = async (mint: PublicKey, destination: PublicKey, amount: BN) => {
+    const tx = new Transaction().add(
+      new TransactionInstruction({
+        programId: TOKEN_PROGRAM_ID,
+        keys: [
+          { pubkey: mint, isSigner: false, isWritable: true },
+          { pubkey: destination, isSigner: false, isWritable: true },
+          { pubkey: admin.publicKey, isSigner: true, isWritable: false },
+        ],
+        data: Buffer.concat([Buffer.from([7]), amount.toArrayLike(Buffer, "le", 8)]),
+      })
+    );
+    await provider.sendAndConfirm(tx);
+  };
+
+  const tokenBalance = async (account: PublicKey) =>
+    new BN((await connection.getTokenAccountBalance(account)).value.amount);
+
+  const createDegenMarket = async (title: string, creatorFeeBps = 0, resolvesIn = 3) => {
+    const marketId = new BN(Date.now());
+    const market = marketPda(marketId);
+    const resolutionTime = Math.floor(Date.now() / 1000) + resolvesIn;
+
+    await program.methods
+      .initializeMarket(
+        marketId,
+        title,
+        `${title} test`,
+        "Test",
+        new BN(resolutionTime),
+        { degen: {} },
+        creatorFeeBps,
+        new BN(resolutionTime + MIN_CLAIM_PERIOD),
+        null,
+        null,
+        null,
+        null,
+        null
+      )
+      .accountsPartial({
+        market,
+        marketVault: null,
+        usdcMint: null,
+        parentMarket: null,
+        creatorPosition: null,
+        creatorTokenAccount: null,
+        authority: admin.publicKey,
+        tokenProgram: null,
+      })
+      .rpc();
+
+    return market;
+  };
+
+  const placeDegenBet = (market: PublicKey, user: Keypair, side: object, amount: number) =>
+    program.methods
+      .placeBet(side as any, new BN(amount), null, null, null)
+      .accountsPartial({
+        market,
+        user: user.publicKey,
+        userTokenAccount: null,
+        marketVault: null,
+        outcomeMint: null,
+        userOutcomeTokenAccount: null,
+        housePool: null,
+        houseVault: null,
+        tokenProgram: null,
+      })
+      .signers([user])
+      .rpc();
+
+  const resolveDegenMarket = (market: PublicKey, outcome: boolean) =>
+    program.methods.resolveMarket(outcome).accountsPartial({ market, parentMarket: null, marketGroup: null, housePool: null }).rpc();
+
+  const claimDegenWinnings = (market: PublicKey, user: Keypair) =>
+    program.methods
+      .claimWinnings()
+      .accountsPartial({
+        market,
+        user: user.publicKey,
+        userTokenAccount: null,
+        marketVault: null,
+        winningMint: null,
+        userOutcomeTokenAccount: null,
+        tokenProgram: null,
+      })
+      .signers([user])
+      .rpc();
+
+  const positionPda = (market: PublicKey, user: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), market.toBuffer(), user.toBuffer()],
+      program.programId
+    )[0];
+
+  before(async () => {
+    const [programData] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      BPF_LOADER_UPGRADEABLE
+    );
+
+    await program.methods
+      .initializeConfig(treasury.publicKey)
+      .accountsPartial({
+        config: configPda,
+        program: program.programId,
+        programData,
+        admin: admin.publicKey,
+      })
+      .rpc();
+
+    // Keep the treasury rent-exempt so it can receive small sweeps
+    const sig = await connection.requestAirdrop(treasury.publicKey, LAMPORTS_PER_SOL);
+    await connection.confirmTransaction(sig);
+  });
+
+  describe("rounding dust", () => {
+    it("sweeps the exact residual so a Degen market holds only rent", async () => {
+      const marketId = new BN(Date.now());
+      const market = marketPda(marketId);
+      const resolutionTime = Math.floor(Date.now() / 1000) + 3;
+
+      await program.methods
+        .initializeMarket(
+          marketId,
+          "Dust test",
+          "Three bettors with amounts that do not divide evenly",
+          "Test",
+          new BN(resolutionTime),
+          { degen: {} },
+          0,
+          new BN(resolutionTime + MIN_CLAIM_PERIOD),
+          null,
+          null,
+          null,
+          null,
+          null
+        )
+        .accountsPartial({
+          market,
+          marketVault: null,
+          usdcMint: null,
+          parentMarket: null,
+          creatorPosition: null,
+          creatorTokenAccount: null,
+          authority: admin.publicKey,
+          tokenProgram: null,
+        })
+        .rpc();
+
+      const alice = await fundedUser();
+      const bob = await fundedUser();
+      const carol = await fundedUser();
+      const bets: [Keypair, object, number][] = [
+        [alice, { yes: {} }, 1_000_001],
+        [bob, { yes: {} }, 2_000_003],
+        [carol, { no: {} }, 3_333_337],
+      ];
+
+      for (const [user, side, amount] of bets) {
+        await program.methods
+          .placeBet(side as any, new BN(amount), null, null, null)
+          .accountsPartial({
+            market,
+            user: user.publicKey,
+            userTokenAccount: null,
+            marketVault: null,
+            outcomeMint: null,
+            userOutcomeTokenAccount: null,
+            housePool: null,
+            houseVault: null,
+            tokenProgram: null,
+          })
+          .signers([user])
+          .rpc();
+      }
+
+      await sleep(4_000);
+      await program.methods.resolveMarket(true).accountsPartial({ market, parentMarket: null, marketGroup: null, housePool: null }).rpc();
+
+      for (const user of [alice, bob]) {
+        await program.methods
+          .claimWinnings()
+          .accountsPartial({
+            market,
+            user: user.publicKey,
+            userTokenAccount: null,
+            marketVault: null,
+            winningMint: null,
+            userOutcomeTokenAccount: null,
+            tokenProgram: null,
+          })
+          .signers([user])
+          .rpc();
+      }
+
+      const settled = await program.account.market.fetch(market);
+      const dust = settled.totalOwed.sub(settled.totalClaimed).toNumber();
+      assert.isAbove(dust, 0, "floor division should leave a residual");
+      assert.equal(settled.totalPaidOut.toNumber() + dust, settled.totalOwed.toNumber());
+
+      const treasuryBefore = await connection.getBalance(treasury.publicKey);
+      await program.methods
+        .sweepDust()
+        .accountsPartial({
+          market,
+          config: configPda,
+          caller: bob.publicKey,
+          treasury: treasury.publicKey,
+          treasuryTokenAccount: null,
+          marketVault: null,
+          tokenProgram: null,
+        })
+        .signers([bob])
+        .rpc();
+
+      const marketInfo = await connection.getAccountInfo(market);
+      const rent = await connection.getMinimumBalanceForRentExemption(marketInfo!.data.length);
+      assert.equal(marketInfo!.lamports, rent, "pool balance should reach zero");
+      assert.equal(await connection.getBalance(treasury.publicKey), treasuryBefore + dust);
+
+      const swept = await program.account.market.fetch(market);
+      assert.isTrue(swept.totalClaimed.eq(swept.totalOwed));
+    });
+
+    it("rejects sweeping dust while winners are still unpaid", async () => {
+      const marketId = new BN(Date.now());
+      const market = marketPda(marketId);
+      const resolutionTime = Math.floor(Date.now() / 1000) + 3;
+
+      await program.methods
+        .initializeMarket(
+          marketId,
+          "Early sweep",
+          "Dust cannot be swept before every winning share is settled",
+          "Test",
+          new BN(resolutionTime),
+          { degen: {} },
+          0,
+          new BN(resolutionTime + MIN_CLAIM_PERIOD),
+          null,
+          null,
+          null,
+          null,
+          null
+        )
+        .accountsPartial({
+          market,
+          marketVault: null,
+          usdcMint: null,
+          parentMarket: null,
+          creatorPosition: null,
+          creatorTokenAccount: null,
+          authority: admin.publicKey,
+          tokenProgram: null,
+        })
+        .rpc();
+
+      const alice = await fundedUser();
+      await program.methods
+        .placeBet({ yes: {} }, new BN(1_000_001), null, null, null)
+        .accountsPartial({
+          market,
+          user: alice.publicKey,
+          userTokenAccount: null,
+          marketVault: null,
+          outcomeMint: null,
+          userOutcomeTokenAccount: null,
+          housePool: null,
+          houseVault: null,
+          tokenProgram: null,
+        })
+        .signers([alice])
+        .rpc();
+
+      await sleep(4_000);
+      await program.methods.resolveMarket(true).accountsPartial({ market, parentMarket: null, marketGroup: null, housePool: null }).rpc();
+
+      try {
+        await program.methods
+          .sweepDust()
+          .accountsPartial({
+            market,
+            config: configPda,
+            caller: admin.publicKey,
+            treasury: treasury.publicKey,
+            treasuryTokenAccount: null,
+            marketVault: null,
+            tokenProgram: null,
+          })
+          .rpc();
+        assert.fail("sweep_dust should fail while shares are outstanding");
+      } catch (err) {
+        assert.include(String(err), "SharesOutstanding");
+      }
+    });
+  });
+
+  describe("scalar markets", () => {
+    it("splits the pool linearly by where the value lands in the range", async () => {
+      const marketId = new BN(Date.now());
+      const market = marketPda(marketId);
+      const resolutionTime = Math.floor(Date.now() / 1000) + 3;
+
+      await program.methods
+        .initializeMarket(
+          marketId,
+          "BTC close",
+          "Where will BTC close between 0 and 100?",
+          "Test",
+          new BN(resolutionTime),
+          { degen: {} },
+          0,
+          new BN(resolutionTime + MIN_CLAIM_PERIOD),
+          { lowerBound: new BN(0), upperBound: new BN(100) },
+          null,
+          null,
+          null,
+          null
+        )
+        .accountsPartial({
+          market,
+          marketVault: null,
+          usdcMint: null,
+          parentMarket: null,
+          creatorPosition: null,
+          creatorTokenAccount: null,
+          authority: admin.publicKey,
+          tokenProgram: null,
+        })
+        .rpc();
+
+      const long = await fundedUser();
+      const short = await fundedUser();
+      for (const [user, side] of [
+        [long, { long: {} }],
+        [short, { short: {} }],
+      ] as [Keypair, object][]) {
+        await program.methods
+          .placeBet(side as any, new BN(LAMPORTS_PER_SOL), null, null, null)
+          .accountsPartial({
+            market,
+            user: user.publicKey,
+            userTokenAccount: null,
+            marketVault: null,
+            outcomeMint: null,
+            userOutcomeTokenAccount: null,
+            housePool: null,
+            houseVault: null,
+            tokenProgram: null,
+          })
+          .signers([user])
+          .rpc();
+      }
+
+      await sleep(4_000);
+      await program.methods.resolveScalarMarket(new BN(75)).accountsPartial({ market, parentMarket: null, marketGroup: null, housePool: null }).rpc();
+
+      const received: number[] = [];
+      for (const user of [long, short]) {
+        const before = await connection.getBalance(user.publicKey);
+        await program.methods
+          .claimWinnings()
+          .accountsPartial({
+            market,
+            user: user.publicKey,
+            userTokenAccount: null,
+            marketVault: null,
+            winningMint: null,
+            userOutcomeTokenAccount: null,
+            tokenProgram: null,
+          })
+          .signers([user])
+          .rpc();
+        received.push((await connection.getBalance(user.publicKey)) - before);
+      }
+
+      // Fees are paid by the provider wallet, so each bettor receives its pot exactly
+      assert.equal(received[0], 1.5 * LAMPORTS_PER_SOL);
+      assert.equal(received[1], 0.5 * LAMPORTS_PER_SOL);
+    });
+
+    it("lets the empty side close when the value lands on a bound", async () => {
+      const marketId = new BN(Date.now());
+      const market = marketPda(marketId);
+      const resolutionTime = Math.floor(Date.now() / 1000) + 3;
+
+      await program.methods
+        .initializeMarket(
+          marketId,
+          "BTC close at bound",
+          "Where will BTC close between 0 and 100?",
+          "Test",
+          new BN(resolutionTime),
+          { degen: {} },
+          0,
+          new BN(resolutionTime + MIN_CLAIM_PERIOD),
+          { lowerBound: new BN(0), upperBound: new BN(100) },
+          null,
+          null,
+          null,
+          null
+        )
+        .accountsPartial({
+          market,
+          marketVault: null,
+          usdcMint: null,
+          parentMarket: null,
+          creatorPosition: null,
+          creatorTokenAccount: null,
+          authority: admin.publicKey,
+          tokenProgram: null,
+        })
+        .rpc();
+
+      const long = await fundedUser();
+      const short = await fundedUser();
+      await placeDegenBet(market, long, { long: {} }, LAMPORTS_PER_SOL);
+      await placeDegenBet(market, short, { short: {} }, LAMPORTS_PER_SOL);
+
+      await sleep(4_000);
+      await program.methods.resolveScalarMarket(new BN(150)).accountsPartial({ market, parentMarket: null, marketGroup: null, housePool: null }).rpc();
+
+      try {
+        await claimDegenWinnings(market, short);
+        assert.fail("the SHORT pot is empty");
+      } catch (err) {
+        assert.include(String(err), "NoWinningsAvailable");
+      }
+
+      const before = await connection.getBalance(long.publicKey);
+      await claimDegenWinnings(market, long);
+      assert.equal((await connection.getBalance(long.publicKey)) - before, 2 * LAMPORTS_PER_SOL);
+
+      for (const user of [short, long]) {
+        await program.methods
+          .closePosition()
+          .accountsPartial({ market, position: positionPda(market, user.publicKey), user: user.publicKey })
+          .signers([user])
+          .rpc();
+      }
+
+      await program.methods
+        .closeMarket()
+        .accountsPartial({ market, authority: admin.publicKey, config: configPda, treasuryTokenAccount: null, marketVault: null, tokenProgram: null })
+        .rpc();
+      assert.isNull(await connection.getAccountInfo(market));
+    });
+  });
+
+  describe("conditional markets", () => {
+    const createMarket = async (marketId: BN, resolutionTime: number, parent: PublicKey | null) =>
+      program.methods
+        .initializeMarket(
+          marketId,
+          parent ? "Child" : "Parent",
+          "Conditional market test",
+          "Test",
+          new BN(resolutionTime),
+          { degen: {} },
+          0,
+          new BN(resolutionTime + MIN_CLAIM_PERIOD),
+          null,
+          parent ? true : null,
+          null,
+          null,
+          null
+        )
+        .accountsPartial({
+          market: marketPda(marketId),
+          marketVault: null,
+          usdcMint: null,
+          parentMarket: parent,
+          creatorPosition: null,
+          creatorTokenAccount: null,
+          authority: admin.publicKey,
+          tokenProgram: null,
+        })
+        .rpc();
+
+    it("voids the child when the parent resolves the other way", async () => {
+      const parentId = new BN(Date.now());
+      const childId = parentId.addn(1);
+      const parent = marketPda(parentId);
+      const child = marketPda(childId);
+      const resolutionTime = Math.floor(Date.now() / 1000) + 3;
+
+      await createMarket(parentId, resolutionTime, null);
+      await createMarket(childId, resolutionTime, parent);
+      await sleep(4_000);
+
+      try {
+        await program.methods.resolveMarket(true).accountsPartial({ market: child, parentMarket: parent, marketGroup: null, housePool: null }).rpc();
+        assert.fail("child should not resolve before its parent");
+      } catch (err) {
+        assert.include(String(err), "ParentNotResolved");
+      }
+
+      await program.methods.resolveMarket(false).accountsPartial({ market: parent, parentMarket: null, marketGroup: null, housePool: null }).rpc();
+
+      const caller = await fundedUser();
+      await program.methods
+        .voidConditionalMarket()
+        .accountsPartial({ market: child, parentMarket: parent, caller: caller.publicKey })
+        .signers([caller])
+        .rpc();
+
+      const voided = await program.account.market.fetch(child);
+      assert.deepEqual(voided.state, { cancelled: {} });
+    });
+  });
+
+  describe("market groups", () => {
+    it("lets at most one market in a group resolve YES", async () => {
+      const groupId = new BN(Date.now());
+      const [marketGroup] = PublicKey.findProgramAddressSync(
+        [Buffer.from("market_group"), groupId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      const resolutionTime = Math.floor(Date.now() / 1000) + 3;
+
+      await program.methods
+        .initializeMarketGroup(groupId, { degen: {} })
+        .accountsPartial({ marketGroup, authority: admin.publicKey })
+        .rpc();
+
+      const markets: PublicKey[] = [];
+      for (const offset of [1, 2]) {
+        const marketId = groupId.addn(offset);
+        const market = marketPda(marketId);
+        await program.methods
+          .initializeMarket(
+            marketId,
+            `Candidate ${offset}`,
+            "Mutually exclusive group test",
+            "Test",
+            new BN(resolutionTime),
+            { degen: {} },
+            0,
+            new BN(resolutionTime + MIN_CLAIM_PERIOD),
+            null,
+            null,
+            null,
+            null,
+            null
+          )
+          .accountsPartial({
+            market,
+            marketVault: null,
+            usdcMint: null,
+            parentMarket: null,
+            creatorPosition: null,
+            creatorTokenAccount: null,
+            authority: admin.publicKey,
+            tokenProgram: null,
+          })
+          .rpc();
+        await program.methods
+          .addMarketToGroup()
+          .accountsPartial({ marketGroup, market, authority: admin.publicKey })
+          .rpc();
+        markets.push(market);
+      }
+
+      await sleep(4_000);
+      await program.methods
+        .resolveMarket(true)
+        .accountsPartial({ market: markets[0], parentMarket: null, marketGroup, housePool: null })
+        .rpc();
+
+      try {
+        await program.methods
+          .resolveMarket(true)
+          .accountsPartial({ market: markets[1], parentMarket: null, marketGroup, housePool: null })
+          .rpc();
+        assert.fail("a second market should not resolve YES");
+      } catch (err) {
+        assert.include(String(err), "GroupAlreadyHasWinner");
+      }
+
+      const group = await program.account.marketGroup.fetch(marketGroup);
+      assert.isTrue(group.winner!.equals(markets[0]));
+    });
+
+    it("pays NO-basket converters their freed collateral once the group settles", async () => {
+      const groupId = new BN(Date.now());
+      const [marketGroup] = PublicKey.findProgramAddressSync(
+        [Buffer.from("market_group"), groupId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .initializeMarketGroup(groupId, { degen: {} })
+        .accountsPartial({ marketGroup, authority: admin.publicKey })
+        .rpc();
+
+      const user = await fundedUser();
+      const markets: PublicKey[] = [];
+      for (const title of ["Basket A", "Basket B", "Basket C"]) {
+        const market = await createDegenMarket(title);
+        await program.methods
+          .addMarketToGroup()
+          .accountsPartial({ marketGroup, market, authority: admin.publicKey })
+          .rpc();
+        await program.methods
+          .split(new BN(LAMPORTS_PER_SOL))
+          .accountsPartial({
+            market,
+            position: positionPda(market, user.publicKey),
+            user: user.publicKey,
+            userTokenAccount: null,
+            marketVault: null,
+            tokenProgram: null,
+          })
+          .signers([user])
+          .rpc();
+        markets.push(market);
+      }
+
+      // NO on B and C becomes YES on A plus one unit of collateral, since only one of B and C can win
+      await program.methods
+        .convertNoBasket(0, new BN(LAMPORTS_PER_SOL))
+        .accountsPartial({ marketGroup, user: user.publicKey })
+        .remainingAccounts(
+          markets.flatMap((market) => [
+            { pubkey: market, isSigner: false, isWritable: true },
+            { pubkey: positionPda(market, user.publicKey), isSigner: false, isWritable: true },
+          ])
+        )
+        .signers([user])
+        .rpc();
+
+      const target = positionPda(markets[0], user.publicKey);
+      let held = await program.account.position.fetch(target);
+      assert.equal(held.setYesShares.toNumber(), 2 * LAMPORTS_PER_SOL);
+      assert.equal(held.basketShares.toNumber(), LAMPORTS_PER_SOL);
+      assert.equal(held.basketCollateral.toNumber(), LAMPORTS_PER_SOL);
+
+      try {
+        await program.methods
+          .addMarketToGroup()
+          .accountsPartial({ marketGroup, market: await createDegenMarket("Basket late"), authority: admin.publicKey })
+          .rpc();
+        assert.fail("a converted group should not grow");
+      } catch (err) {
+        assert.include(String(err), "MarketGroupConverted");
+      }
+
+      await sleep(4_000);
+      for (const [index, market] of markets.entries()) {
+        await program.methods
+          .resolveMarket(index === 0)
+          .accountsPartial({ market, parentMarket: null, marketGroup, housePool: null })
+          .rpc();
+      }
+      await program.methods
+        .settleGroup()
+        .accountsPartial({ marketGroup, caller: admin.publicKey, tokenProgram: null })
+        .remainingAccounts(
+          markets.flatMap((market) => [
+            { pubkey: market, isSigner: false, isWritable: true },
+            { pubkey: market, isSigner: false, isWritable: false },
+          ])
+        )
+        .rpc();
+
+      const before = await connection.getBalance(user.publicKey);
+      await program.methods
+        .claimBasketCollateral()
+        .accountsPartial({
+          marketGroup,
+          market: markets[0],
+          position: target,
+          user: user.publicKey,
+          userTokenAccount: null,
+          marketVault: null,
+          tokenProgram: null,
+        })
+        .signers([user])
+        .rpc();
+      assert.equal(await connection.getBalance(user.publicKey), before + LAMPORTS_PER_SOL);
+
+      held = await program.account.position.fetch(target);
+      assert.equal(held.basketShares.toNumber(), 0);
+      assert.equal(held.basketCollateral.toNumber(), 0);
+      const settled = await program.account.market.fetch(markets[0]);
+      assert.equal(settled.basketCollateral.toNumber(), 0);
+    });
+  });
+
+  describe("initial liquidity", () => {
+    it("records the creator seed as a position split at the opening probability", async () => {
+      const marketId = new BN(Date.now());
+      const market = marketPda(marketId);
+      const [creatorPosition] = PublicKey.findProgramAddressSync(
+        [Buffer.from("position"), market.toBuffer(), admin.publicKey.toBuffer()],
+        program.programId
+      );
+      const resolutionTime = Math.floor(Date.now() / 1000) + 60;
+
+      await program.methods
+        .initializeMarket(
+          marketId,
+          "Seeded",
+          "Creator-seeded liquidity test",
+          "Test",
+          new BN(resolutionTime),
+          { degen: {} },
+          0,
+          new BN(resolutionTime + MIN_CLAIM_PERIOD),
+          null,
+          null,
+          null,
+          { amount: new BN(LAMPORTS_PER_SOL), yesProbabilityBps: 2_500 },
+          null
+        )
+        .accountsPartial({
+          market,
+          marketVault: null,
+          usdcMint: null,
+          parentMarket: null,
+          creatorPosition,
+          creatorTokenAccount: null,
+          authority: admin.publicKey,
+          tokenProgram: null,
+        })
+        .rpc();
+
+      const seeded = await program.account.market.fetch(market);
+      assert.equal(seeded.totalYesAmount.toNumber(), 0.25 * LAMPORTS_PER_SOL);
+      assert.equal(seeded.totalNoAmount.toNumber(), 0.75 * LAMPORTS_PER_SOL);
+      assert.equal(seeded.openPositions.toNumber(), 1);
+
+      // Default limits are 0.001 to 1,000 whole tokens, scaled by the mint's decimals
+      assert.equal(seeded.decimals, 9);
+      assert.equal(seeded.limits.minBet.toNumber(), 0.001 * LAMPORTS_PER_SOL);
+      assert.equal(seeded.limits.maxBet.toNumber(), 1_000 * LAMPORTS_PER_SOL);
+
+      const position = await program.account.position.fetch(creatorPosition);
+      assert.isTrue(position.user.equals(admin.publicKey));
+      assert.equal(position.yesShares.toNumber(), 0.25 * LAMPORTS_PER_SOL);
+      assert.equal(position.noShares.toNumber(), 0.75 * LAMPORTS_PER_SOL);
+      assert.equal(position.totalInvested.toNumber(), LAMPORTS_PER_SOL);
+    });
+  });
+
+  describe("order book", () => {
+    it("mints complete sets when a YES bid and a NO bid cross", async () => {
+      const marketId = new BN(Date.now());
+      const market = marketPda(marketId);
+      const resolutionTime = Math.floor(Date.now() / 1000) + 60;
+      const positionPda = (user: PublicKey) =>
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("position"), market.toBuffer(), user.toBuffer()],
+          program.programId
+        )[0];
+
+      await program.methods
+        .initializeMarket(
+          marketId,
+          "Order book",
+          "Limit order matching test",
+          "Test",
+          new BN(resolutionTime),
+          { degen: {} },
+          0,
+          new BN(resolutionTime + MIN_CLAIM_PERIOD),
+          null,
+          null,
+          null,
+          null,
+          null
+        )
+        .accountsPartial({
+          market,
+          marketVault: null,
+          usdcMint: null,
+          parentMarket: null,
+          creatorPosition: null,
+          creatorTokenAccount: null,
+          authority: admin.publicKey,
+          tokenProgram: null,
+        })
+        .rpc();
+      await program.methods.initializeOrderBook().accountsPartial({ market, payer: admin.publicKey }).rpc();
+
+      const alice = await fundedUser();
+      const bob = await fundedUser();
+      const size = new BN(1_000_000);
+      const placeOrder = (user: Keypair, side: object, priceBps: number, makers: PublicKey[]) =>
+        program.methods
+          .placeOrder(side as any, { bid: {} }, priceBps, size)
+          .accountsPartial({
+            market,
+            user: user.publicKey,
+            userTokenAccount: null,
+            marketVault: null,
+            tokenProgram: null,
+          })
+          .remainingAccounts(makers.map((pubkey) => ({ pubkey, isWritable: true, isSigner: false })))
+          .signers([user])
+          .rpc();
+
+      await placeOrder(alice, { yes: {} }, 6_000, []);
+      const resting = await program.account.market.fetch(market);
+      assert.equal(resting.orderEscrow.toNumber(), 600_000);
+
+      await placeOrder(bob, { no: {} }, 4_000, [positionPda(alice.publicKey)]);
+
+      const matched = await program.account.market.fetch(market);
+      assert.equal(matched.orderEscrow.toNumber(), 0);
+      assert.isTrue(matched.totalCompleteSets.eq(size));
+
+      const alicePosition = await program.account.position.fetch(positionPda(alice.publicKey));
+      const bobPosition = await program.account.position.fetch(positionPda(bob.publicKey));
+      assert.isTrue(alicePosition.setYesShares.eq(size));
+      assert.isTrue(bobPosition.setNoShares.eq(size));
+      assert.equal(alicePosition.openOrders, 0);
+    });
+
+    it("holds claims until resting asks are cancelled", async () => {
+      const market = await createDegenMarket("Order book claims", 0, 10);
+      await program.methods.initializeOrderBook().accountsPartial({ market, payer: admin.publicKey }).rpc();
+
+      const user = await fundedUser();
+      const size = new BN(1_000_000);
+      const orderAccounts = {
+        market,
+        user: user.publicKey,
+        userTokenAccount: null,
+        marketVault: null,
+        tokenProgram: null,
+      };
+      await program.methods
+        .split(size)
+        .accountsPartial({ ...orderAccounts, position: positionPda(market, user.publicKey) })
+        .signers([user])
+        .rpc();
+      await program.methods
+        .placeOrder({ yes: {} }, { ask: {} }, 6_000, size)
+        .accountsPartial(orderAccounts)
+        .signers([user])
+        .rpc();
+
+      await sleep(11_000);
+      await resolveDegenMarket(market, true);
+
+      // The escrowed YES shares would be stranded if the position were claimed first
+      try {
+        await claimDegenWinnings(market, user);
+        assert.fail("claims wait for resting orders");
+      } catch (err) {
+        assert.include(String(err), "OpenOrdersOutstanding");
+      }
+
+      await program.methods.cancelOrder(new BN(0)).accountsPartial(orderAccounts).signers([user]).rpc();
+      const before = await connection.getBalance(user.publicKey);
+      await claimDegenWinnings(market, user);
+      assert.equal(await connection.getBalance(user.publicKey), before + size.toNumber());
+    });
+  });
+
+  describe("signed quotes", () => {
+    it("fills a maker-signed ask once and rejects a replay", async () => {
+      const marketId = new BN(Date.now());
+      const market = marketPda(marketId);
+      const resolutionTime = Math.floor(Date.now() / 1000) + 60;
+      const positionPda = (user: PublicKey) =>
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("position"), market.toBuffer(), user.toBuffer()],
+          program.programId
+        )[0];
+
+      await program.methods
+        .initializeMarket(
+          marketId,
+          "Quotes",
+          "Signed RFQ quote test",
+          "Test",
+          new BN(resolutionTime),
+          { degen: {} },
+          0,
+          new BN(resolutionTime + MIN_CLAIM_PERIOD),
+          null,
+          null,
+          null,
+          null,
+          null
+        )
+        .accountsPartial({
+          market,
+          marketVault: null,
+          usdcMint: null,
+          parentMarket: null,
+          creatorPosition: null,
+          creatorTokenAccount: null,
+          authority: admin.publicKey,
+          tokenProgram: null,
+        })
+        .rpc();
+
+      const maker = await fundedUser();
+      const taker = await fundedUser();
+      const size = new BN(1_000_000);
+      await program.methods
+        .split(size)
+        .accountsPartial({ market, user: maker.publicKey, userTokenAccount: null, marketVault: null, tokenProgram: null })
+        .signers([maker])
+        .rpc();
+
+      const quote = {
+        maker: maker.publicKey,
+        market,
+        side: { yes: {} },
+        kind: { ask: {} },
+        priceBps: 7_000,
+        size,
+        expiresAt: new BN(resolutionTime),
+        nonce: new BN(1),
+      };
+      const message = program.coder.types.encode("quote", quote);
+      const fill = () =>
+        program.methods
+          .fillQuote(quote as any)
+          .accountsPartial({
+            market,
+            taker: taker.publicKey,
+            takerTokenAccount: null,
+            marketVault: null,
+            tokenProgram: null,
+          })
+          .preInstructions([Ed25519Program.createInstructionWithPrivateKey({ privateKey: maker.secretKey, message })])
+          .signers([taker])
+          .rpc();
+
+      await fill();
+
+      const makerPosition = await program.account.position.fetch(positionPda(maker.publicKey));
+      const takerPosition = await program.account.position.fetch(positionPda(taker.publicKey));
+      assert.equal(makerPosition.setYesShares.toNumber(), 0);
+      assert.isTrue(takerPosition.setYesShares.eq(size));
+      assert.equal(makerPosition.orderProceeds.toNumber(), 700_000);
+
+      try {
+        await fill();
+        assert.fail("a quote should only fill once");
+      } catch (err) {
+        assert.include(String(err), "already in use");
+      }
+    });
+
+    it("pays a maker-signed bid from the maker's deposit", async () => {
+      const marketId = new BN(Date.now());
+      const market = marketPda(marketId);
+      const resolutionTime = Math.floor(Date.now() / 1000) + 60;
+
+      await program.methods
+        .initializeMarket(
+          marketId,
+          "Quote bids",
+          "Signed RFQ bid test",
+          "Test",
+          new BN(resolutionTime),
+          { degen: {} },
+          0,
+          new BN(resolutionTime + MIN_CLAIM_PERIOD),
+          null,
+          null,
+          null,
+          null,
+          null
+        )
+        .accountsPartial({
+          market,
+          marketVault: null,
+          usdcMint: null,
+          parentMarket: null,
+          creatorPosition: null,
+          creatorTokenAccount: null,
+          authority: admin.publicKey,
+          tokenProgram: null,
+        })
+        .rpc();
+
+      const maker = await fundedUser();
+      const taker = await fundedUser();
+      const size = new BN(1_000_000);
+      await program.methods
+        .split(size)
+        .accountsPartial({ market, user: taker.publicKey, userTokenAccount: null, marketVault: null, tokenProgram: null })
+        .signers([taker])
+        .rpc();
+
+      const quote = {
+        maker: maker.publicKey,
+        market,
+        side: { yes: {} },
+        kind: { bid: {} },
+        priceBps: 6_000,
+        size,
+        expiresAt: new BN(resolutionTime),
+        nonce: new BN(1),
+      };
+      const message = program.coder.types.encode("quote", quote);
+      const fill = () =>
+        program.methods
+          .fillQuote(quote as any)
+          .accountsPartial({
+            market,
+            taker: taker.publicKey,
+            takerTokenAccount: null,
+            marketVault: null,
+            tokenProgram: null,
+          })
+          .preInstructions([Ed25519Program.createInstructionWithPrivateKey({ privateKey: maker.secretKey, message })])
+          .signers([taker])
+          .rpc();
+
+      // The maker's position is created by the deposit, so an unfunded bid can't fill
+      await program.methods
+        .depositQuoteCollateral(new BN(500_000))
+        .accountsPartial({ market, user: maker.publicKey, userTokenAccount: null, marketVault: null, tokenProgram: null })
+        .signers([maker])
+        .rpc();
+      try {
+        await fill();
+        assert.fail("the bid is only partly funded");
+      } catch (err) {
+        assert.include(String(err), "InsufficientFunds");
+      }
+
+      await program.methods
+        .depositQuoteCollateral(new BN(100_000))
+        .accountsPartial({ market, user: maker.publicKey, userTokenAccount: null, marketVault: null, tokenProgram: null })
+        .signers([maker])
+        .rpc();
+      await fill();
+
+      const makerPosition = await program.account.position.fetch(positionPda(market, maker.publicKey));
+      const takerPosition = await program.account.position.fetch(positionPda(market, taker.publicKey));
+      assert.isTrue(makerPosition.setYesShares.eq(size));
+      assert.equal(makerPosition.orderProceeds.toNumber(), 0);
+      assert.equal(takerPosition.setYesShares.toNumber(), 0);
+      assert.equal((await program.account.market.fetch(market)).orderEscrow.toNumber(), 0);
+    });
+  });
+
+  describe("challenges", () => {
+    it("pays the winner both stakes at the challenge odds", async () => {
+      const marketId = new BN(Date.now());
+      const market = marketPda(marketId);
+      const resolutionTime = Math.floor(Date.now() / 1000) + 4;
+
+      await program.methods
+        .initializeMarket(
+          marketId,
+          "Challenge",
+          "Peer-to-peer challenge test",
+          "Test",
+          new BN(resolutionTime),
+          { degen: {} },
+          0,
+          new BN(resolutionTime + MIN_CLAIM_PERIOD),
+          null,
+          null,
+          null,
+          null,
+          null
+        )
+        .accountsPartial({
+          market,
+          marketVault: null,
+          usdcMint: null,
+          parentMarket: null,
+          creatorPosition: null,
+          creatorTokenAccount: null,
+          authority: admin.publicKey,
+          tokenProgram: null,
+        })
+        .rpc();
+
+      const creator = await fundedUser();
+      const acceptor = await fundedUser();
+      const stranger = await fundedUser();
+      const challengeId = new BN(1);
+      const [challenge] = PublicKey.findProgramAddressSync(
+        [Buffer.from("challenge"), creator.publicKey.toBuffer(), challengeId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+
+      // 1 SOL on YES at 2.5x: the acceptor puts up 1.5 SOL
+      await program.methods
+        .createChallenge(
+          challengeId,
+          { yes: {} },
+          new BN(LAMPORTS_PER_SOL),
+          new BN(25_000),
+          acceptor.publicKey,
+          new BN(resolutionTime)
+        )
+        .accountsPartial({
+          challenge,
+          market,
+          creator: creator.publicKey,
+          creatorTokenAccount: null,
+          challengeVault: null,
+          usdcMint: null,
+          tokenProgram: null,
+        })
+        .signers([creator])
+        .rpc();
+
+      const accept = (user: Keypair) =>
+        program.methods
+          .acceptChallenge()
+          .accountsPartial({
+            challenge,
+            market,
+            acceptor: user.publicKey,
+            acceptorTokenAccount: null,
+            challengeVault: null,
+            tokenProgram: null,
+          })
+          .signers([user])
+          .rpc();
+
+      try {
+        await accept(stranger);
+        assert.fail("only the designated counterparty may accept");
+      } catch (err) {
+        assert.include(String(err), "InvalidChallengeCounterparty");
+      }
+      await accept(acceptor);
+
+      await sleep(5_000);
+      await program.methods.resolveMarket(true).accountsPartial({ market, parentMarket: null, marketGroup: null, housePool: null }).rpc();
+
+      // Settlement reads the market, so it stays open until the challenge settles
+      const closeMarket = () =>
+        program.methods
+          .closeMarket()
+          .accountsPartial({ market, authority: admin.publicKey, config: configPda, treasuryTokenAccount: null, marketVault: null, tokenProgram: null })
+          .rpc();
+      assert.equal((await program.account.market.fetch(market)).openChallenges.toNumber(), 1);
+      try {
+        await closeMarket();
+        assert.fail("the market cannot close under an open challenge");
+      } catch (err) {
+        assert.include(String(err), "ChallengesOutstanding");
+      }
+
+      const challengeRent = await connection.getMinimumBalanceForRentExemption(
+        (await connection.getAccountInfo(challenge))!.data.length
+      );
+      const before = await connection.getBalance(creator.publicKey);
+      await program.methods
+        .settleChallenge()
+        .accountsPartial({
+          challenge,
+          market,
+          creator: creator.publicKey,
+          acceptor: acceptor.publicKey,
+          caller: admin.publicKey,
+          creatorTokenAccount: null,
+          acceptorTokenAccount: null,
+          challengeVault: null,
+          tokenProgram: null,
+        })
+        .rpc();
+
+      assert.equal(await connection.getBalance(creator.publicKey), before + 2.5 * LAMPORTS_PER_SOL + challengeRent);
+      assert.isNull(await connection.getAccountInfo(challenge));
+
+      await closeMarket();
+      assert.isNull(await connection.getAccountInfo(market));
+    });
+  });
+
+  describe("slippage protection", () => {
+    it("rejects a bet that would push the price above the maximum", async () => {
+      const marketId = new BN(Date.now());
+      const market = marketPda(marketId);
+      const resolutionTime = Math.floor(Date.now() / 1000) + 60;
+
+      await program.methods
+        .initializeMarket(
+          marketId,
+          "Slippage",
+          "Place bet price bounds test",
+          "Test",
+          new BN(resolutionTime),
+          { degen: {} },
+          0,
+          new BN(resolutionTime + MIN_CLAIM_PERIOD),
+          null,
+          null,
+          null,
+          null,
+          null
+        )
+        .accountsPartial({
+          market,
+          marketVault: null,
+          usdcMint: null,
+          parentMarket: null,
+          creatorPosition: null,
+          creatorTokenAccount: null,
+          authority: admin.publicKey,
+          tokenProgram: null,
+        })
+        .rpc();
+
+      const user = await fundedUser();
+      const bet = (maxImpliedProbabilityBps: number | null, expiresAt: BN | null) =>
+        program.methods
+          .placeBet({ yes: {} }, new BN(LAMPORTS_PER_SOL), null, maxImpliedProbabilityBps, expiresAt)
+          .accountsPartial({
+            market,
+            user: user.publicKey,
+            userTokenAccount: null,
+            marketVault: null,
+            outcomeMint: null,
+            userOutcomeTokenAccount: null,
+            housePool: null,
+            houseVault: null,
+            tokenProgram: null,
+          })
+          .signers([user])
+          .rpc();
+
+      // A lone YES bet prices YES at 100%
+      try {
+        await bet(5_000, null);
+        assert.fail("bet should exceed the maximum implied probability");
+      } catch (err) {
+        assert.include(String(err), "ImpliedProbabilityTooHigh");
+      }
+
+      try {
+        await bet(null, new BN(Math.floor(Date.now() / 1000) - 10));
+        assert.fail("expired bet request should be rejected");
+      } catch (err) {
+        assert.include(String(err), "BetRequestExpired");
+      }
+
+      await bet(10_000, new BN(resolutionTime));
+      const filled = await program.account.market.fetch(market);
+      assert.equal(filled.totalYesAmount.toNumber(), LAMPORTS_PER_SOL);
+    });
+  });
+
+  describe("market limits", () => {
+    const createLimitedMarket = async (maxPosition: number) => {
+      const marketId = new BN(Date.now());
+      const market = marketPda(marketId);
+      const resolutionTime = Math.floor(Date.now() / 1000) + 60;
+
+      await program.methods
+        .initializeMarket(
+          marketId,
+          "Limits",
+          "Per-market limits test",
+          "Test",
+          new BN(resolutionTime),
+          { degen: {} },
+          0,
+          new BN(resolutionTime + MIN_CLAIM_PERIOD),
+          null,
+          null,
+          null,
+          null,
+          {
+            minBet: new BN(1_000_000),
+            maxBet: new BN(LAMPORTS_PER_SOL),
+            maxPosition: new BN(maxPosition),
+            maxSideShareBps: 10_000,
+            maxVolume: new BN(100 * LAMPORTS_PER_SOL),
+          }
+        )
+        .accountsPartial({
+          market,
+          marketVault: null,
+          usdcMint: null,
+          parentMarket: null,
+          creatorPosition: null,
+          creatorTokenAccount: null,
+          authority: admin.publicKey,
+          tokenProgram: null,
+        })
+        .rpc();
+      return market;
+    };
+
+    it("caps a wallet's cumulative position", async () => {
+      const market = await createLimitedMarket(1.5 * LAMPORTS_PER_SOL);
+      const user = await fundedUser();
+      const bet = () =>
+        program.methods
+          .placeBet({ yes: {} }, new BN(LAMPORTS_PER_SOL), null, null, null)
+          .accountsPartial({
+            market,
+            user: user.publicKey,
+            userTokenAccount: null,
+            marketVault: null,
+            outcomeMint: null,
+            userOutcomeTokenAccount: null,
+            housePool: null,
+            houseVault: null,
+            tokenProgram: null,
+          })
+          .signers([user])
+          .rpc();
+
+      await bet();
+      try {
+        await bet();
+        assert.fail("second bet should exceed the position limit");
+      } catch (err) {
+        assert.include(String(err), "PositionLimitExceeded");
+      }
+    });
+
+    it("counts complete sets and transferred shares towards the cap", async () => {
+      const market = await createLimitedMarket(1.5 * LAMPORTS_PER_SOL);
+      const alice = await fundedUser();
+      const bob = await fundedUser();
+      await placeDegenBet(market, alice, { yes: {} }, LAMPORTS_PER_SOL);
+
+      const split = (amount: number) =>
+        program.methods
+          .split(new BN(amount))
+          .accountsPartial({
+            market,
+            position: positionPda(market, alice.publicKey),
+            user: alice.publicKey,
+            userTokenAccount: null,
+            marketVault: null,
+            tokenProgram: null,
+          })
+          .signers([alice])
+          .rpc();
+
+      // A complete set is worth one unit at resolution, so it is stake like any bet
+      await split(0.5 * LAMPORTS_PER_SOL);
+      try {
+        await split(0.1 * LAMPORTS_PER_SOL);
+        assert.fail("split should exceed the position limit");
+      } catch (err) {
+        assert.include(String(err), "PositionLimitExceeded");
+      }
+
+      // Bob is already at the cap, so receiving Alice's shares would put him over it
+      await placeDegenBet(market, bob, { no: {} }, LAMPORTS_PER_SOL);
+      await placeDegenBet(market, bob, { yes: {} }, 0.5 * LAMPORTS_PER_SOL);
+      try {
+        await program.methods
+          .transferPosition(new BN(0.5 * LAMPORTS_PER_SOL), new BN(0))
+          .accountsPartial({
+            market,
+            fromPosition: positionPda(market, alice.publicKey),
+            toPosition: positionPda(market, bob.publicKey),
+            recipient: bob.publicKey,
+            user: alice.publicKey,
+          })
+          .signers([alice])
+          .rpc();
+        assert.fail("transfer should exceed the recipient's position limit");
+      } catch (err) {
+        assert.include(String(err), "PositionLimitExceeded");
+      }
+    });
+  });
+
+  describe("stable mint config", () => {
+    it("lets the admin point Stable products at a local mint", async () => {
+      const mint = await createMint(6);
+
+      await program.methods
+        .setStableMint()
+        .accountsPartial({ config: configPda, stableMint: mint, admin: admin.publicKey })
+        .rpc();
+
+      const config = await program.account.protocolConfig.fetch(configPda);
+      assert.ok(config.stableMint.equals(mint));
+      assert.equal(config.stableDecimals, 6);
+    });
+
+    it("rejects updates from anyone but the admin", async () => {
+      const mint = await createMint(6);
+      const outsider = await fundedUser();
+
+      try {
+        await program.methods
+          .setStableMint()
+          .accountsPartial({ config: configPda, stableMint: mint, admin: outsider.publicKey })
+          .signers([outsider])
+          .rpc();
+        assert.fail("non-admin should not update the stable mint");
+      } catch (err) {
+        assert.include(String(err), "UnauthorizedAdmin");
+      }
+    });
+  });
+
+  describe("stable payouts", () => {
+    const DECIMALS = 6;
+    const UNIT = 10 ** DECIMALS;
+    let stableMint: PublicKey;
+
+    const vaultPda = (market: PublicKey) =>
+      PublicKey.findProgramAddressSync([Buffer.from("vault"), market.toBuffer()], program.programId)[0];
+
+    const createStableMarket = async (title: string, seed?: { amount: number; tokenAccount: PublicKey }) => {
+      const marketId = new BN(Date.now());
+      const market = marketPda(marketId);
+      const resolutionTime = Math.floor(Date.now() / 1000) + 3;
+
+      await program.methods
+        .initializeMarket(
+          marketId,
+          title,
+          "Stable vault payout test",
+          "Test",
+          new BN(resolutionTime),
+          { stable: {} },
+          0,
+          new BN(resolutionTime + MIN_CLAIM_PERIOD),
+          null,
+          null,
+          null,
+          seed ? { amount: new BN(seed.amount), yesProbabilityBps: 5_000 } : null,
+          null
+        )
+        .accountsPartial({
+          market,
+          marketVault: vaultPda(market),
+          usdcMint: stableMint,
+          parentMarket: null,
+          creatorPosition: seed ? positionPda(market, admin.publicKey) : null,
+          creatorTokenAccount: seed ? seed.tokenAccount : null,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+      return market;
+    };
+
+    const fundedBettor = async (amount: number) => {
+      const user = await fundedUser();
+      const tokenAccount = await createTokenAccountFor(stableMint, user.publicKey);
+      await mintTo(stableMint, tokenAccount, new BN(amount));
+      return { user, tokenAccount };
+    };
+
+    const bet = (market: PublicKey, bettor: { user: Keypair; tokenAccount: PublicKey }, side: object, amount: number) =>
+      program.methods
+        .placeBet(side as any, new BN(amount), null, null, null)
+        .accountsPartial({
+          market,
+          user: bettor.user.publicKey,
+          userTokenAccount: bettor.tokenAccount,
+          marketVault: vaultPda(market),
+          outcomeMint: null,
+          userOutcomeTokenAccount: null,
+          housePool: null,
+          houseVault: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([bettor.user])
+        .rpc();
+
+    before(async () => {
+      stableMint = await createMint(DECIMALS);
+      await program.methods
+        .setStableMint()
+        .accountsPartial({ config: configPda, stableMint, admin: admin.publicKey })
+        .rpc();
+    });
+
+    it("scales the minimum initial liquidity to the mint's decimals", async () => {
+      const adminTokenAccount = await createTokenAccountFor(stableMint, admin.publicKey);
+      await mintTo(stableMint, adminTokenAccount, new BN(10 * UNIT));
+
+      // 2 whole tokens, in thousandths
+      const setMinimum = (amount: number) =>
+        program.methods
+          .setMinInitialLiquidity(new BN(amount))
+          .accountsPartial({ config: configPda, admin: admin.publicKey })
+          .rpc();
+      await setMinimum(2_000);
+
+      try {
+        try {
+          await createStableMarket("Thin seed", { amount: 1.5 * UNIT, tokenAccount: adminTokenAccount });
+          assert.fail("seed below the scaled minimum should be rejected");
+        } catch (err) {
+          assert.include(String(err), "InitialLiquidityTooLow");
+        }
+
+        const market = await createStableMarket("Seeded", { amount: 2 * UNIT, tokenAccount: adminTokenAccount });
+        assert.equal((await tokenBalance(vaultPda(market))).toNumber(), 2 * UNIT);
+      } finally {
+        await setMinimum(0);
+      }
+    });
+
+    it("pays Stable winnings out of the market vault", async () => {
+      const market = await createStableMarket("Stable claim");
+      const vault = vaultPda(market);
+      const alice = await fundedBettor(10 * UNIT);
+      const bob = await fundedBettor(10 * UNIT);
+
+      await bet(market, alice, { yes: {} }, 4 * UNIT);
+      await bet(market, bob, { no: {} }, 6 * UNIT);
+      assert.equal((await tokenBalance(vault)).toNumber(), 10 * UNIT);
+
+      const created = await program.account.market.fetch(market);
+      assert.equal(
+        created.vaultBump,
+        PublicKey.findProgramAddressSync([Buffer.from("vault"), market.toBuffer()], program.programId)[1]
+      );
+
+      await sleep(4_000);
+      await program.methods.resolveMarket(true).accountsPartial({ market, parentMarket: null, marketGroup: null, housePool: null }).rpc();
+
+      const aliceBefore = await tokenBalance(alice.tokenAccount);
+      await program.methods
+        .claimWinnings()
+        .accountsPartial({
+          market,
+          user: alice.user.publicKey,
+          userTokenAccount: alice.tokenAccount,
+          marketVault: vault,
+          winningMint: null,
+          userOutcomeTokenAccount: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([alice.user])
+        .rpc();
+
+      const settled = await program.account.market.fetch(market);
+      const received = (await tokenBalance(alice.tokenAccount)).sub(aliceBefore);
+      assert.isAbove(received.toNumber(), 4 * UNIT, "winner should collect the losing side's stake");
+      assert.isTrue(received.eq(settled.totalPaidOut));
+      assert.isTrue((await tokenBalance(vault)).eq(new BN(10 * UNIT).sub(received)));
+    });
+
+    it("refunds Stable stakes out of the market vault", async () => {
+      const market = await createStableMarket("Stable refund");
+      const vault = vaultPda(market);
+      const alice = await fundedBettor(10 * UNIT);
+
+      await bet(market, alice, { yes: {} }, 3 * UNIT);
+      assert.equal((await tokenBalance(alice.tokenAccount)).toNumber(), 7 * UNIT);
+
+      await program.methods.cancelMarket().accountsPartial({ market, housePool: null, authority: admin.publicKey }).rpc();
+
+      await program.methods
+        .claimRefund()
+        .accountsPartial({
+          market,
+          user: alice.user.publicKey,
+          userTokenAccount: alice.tokenAccount,
+          marketVault: vault,
+          yesMint: null,
+          noMint: null,
+          userYesTokenAccount: null,
+          userNoTokenAccount: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([alice.user])
+        .rpc();
+
+      assert.equal((await tokenBalance(alice.tokenAccount)).toNumber(), 10 * UNIT);
+      assert.equal((await tokenBalance(vault)).toNumber(), 0);
+    });
+  });
+
+  describe("creator fees", () => {
+    it("withholds the creator fee from winnings and pays it to the creator", async () => {
+      const market = await createDegenMarket("Creator fee", 500);
+      const alice = await fundedUser();
+      const bob = await fundedUser();
+
+      await placeDegenBet(market, alice, { yes: {} }, LAMPORTS_PER_SOL);
+      await placeDegenBet(market, bob, { no: {} }, LAMPORTS_PER_SOL);
+
+      await sleep(4_000);
+      await resolveDegenMarket(market, true);
+
+      const aliceBefore = await connection.getBalance(alice.publicKey);
+      await claimDegenWinnings(market, alice);
+      const aliceAfter = await connection.getBalance(alice.publicKey);
+
+      // 2 SOL pot, 5% to the creator; the claimant pays the transaction fee
+      const fee = (2 * LAMPORTS_PER_SOL * 500) / 10_000;
+      const claimed = await program.account.market.fetch(market);
+      assert.equal(claimed.creatorFeesAccrued.toNumber(), fee);
+      assert.approximately(aliceAfter - aliceBefore, 2 * LAMPORTS_PER_SOL - fee, 10_000);
+
+      const marketBefore = await connection.getBalance(market);
+      await program.methods
+        .withdrawCreatorFees()
+        .accountsPartial({
+          market,
+          authority: admin.publicKey,
+          authorityTokenAccount: null,
+          marketVault: null,
+          tokenProgram: null,
+        })
+        .rpc();
+
+      const withdrawn = await program.account.market.fetch(market);
+      assert.equal(withdrawn.creatorFeesAccrued.toNumber(), 0);
+      assert.equal(await connection.getBalance(market), marketBefore - fee);
+    });
+  });
+
+  describe("complete sets", () => {
+    it("splits collateral into YES+NO sets and merges them back at par", async () => {
+      const market = await createDegenMarket("Split merge");
+      const user = await fundedUser();
+      const position = positionPda(market, user.publicKey);
+      const setAccounts = {
+        market,
+        position,
+        user: user.publicKey,
+        userTokenAccount: null,
+        marketVault: null,
+        tokenProgram: null,
+      };
+
+      const marketStart = await connection.getBalance(market);
+      await program.methods.split(new BN(LAMPORTS_PER_SOL)).accountsPartial(setAccounts).signers([user]).rpc();
+
+      let held = await program.account.position.fetch(position);
+      assert.equal(held.setYesShares.toNumber(), LAMPORTS_PER_SOL);
+      assert.equal(held.setNoShares.toNumber(), LAMPORTS_PER_SOL);
+      assert.equal(await connection.getBalance(market), marketStart + LAMPORTS_PER_SOL);
+
+      await program.methods.merge(new BN(0.4 * LAMPORTS_PER_SOL)).accountsPartial(setAccounts).signers([user]).rpc();
+
+      held = await program.account.position.fetch(position);
+      assert.equal(held.setYesShares.toNumber(), 0.6 * LAMPORTS_PER_SOL);
+      assert.equal(held.setNoShares.toNumber(), 0.6 * LAMPORTS_PER_SOL);
+      const merged = await program.account.market.fetch(market);
+      assert.equal(merged.totalCompleteSets.toNumber(), 0.6 * LAMPORTS_PER_SOL);
+      assert.equal(await connection.getBalance(market), marketStart + 0.6 * LAMPORTS_PER_SOL);
+
+      try {
+        await program.methods.merge(new BN(LAMPORTS_PER_SOL)).accountsPartial(setAccounts).signers([user]).rpc();
+        assert.fail("cannot merge more sets than are held");
+      } catch (err) {
+        assert.include(String(err), "InsufficientShares");
+      }
+    });
+  });
+
+  describe("outcome tokens", () => {
+    it("mints YES tokens for bets and burns them on claim", async () => {
+      const market = await createDegenMarket("Outcome tokens");
+      const [yesMint] = PublicKey.findProgramAddressSync([Buffer.from("yes_mint"), market.toBuffer()], program.programId);
+      const [noMint] = PublicKey.findProgramAddressSync([Buffer.from("no_mint"), market.toBuffer()], program.programId);
+
+      await program.methods
+        .createOutcomeMints()
+        .accountsPartial({ market, yesMint, noMint, authority: admin.publicKey, tokenProgram: TOKEN_PROGRAM_ID })
+        .rpc();
+
+      const alice = await fundedUser();
+      const bob = await fundedUser();
+      const aliceYes = await createTokenAccountFor(yesMint, alice.publicKey);
+      const bobNo = await createTokenAccountFor(noMint, bob.publicKey);
+
+      for (const [user, side, mint, tokenAccount] of [
+        [alice, { yes: {} }, yesMint, aliceYes],
+        [bob, { no: {} }, noMint, bobNo],
+      ] as [Keypair, object, PublicKey, PublicKey][]) {
+        await program.methods
+          .placeBet(side as any, new BN(LAMPORTS_PER_SOL), null, null, null)
+          .accountsPartial({
+            market,
+            user: user.publicKey,
+            userTokenAccount: null,
+            marketVault: null,
+            outcomeMint: mint,
+            userOutcomeTokenAccount: tokenAccount,
+            housePool: null,
+            houseVault: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([user])
+          .rpc();
+      }
+      assert.equal((await tokenBalance(aliceYes)).toNumber(), LAMPORTS_PER_SOL);
+      assert.equal((await tokenBalance(bobNo)).toNumber(), LAMPORTS_PER_SOL);
+
+      await sleep(4_000);
+      await resolveDegenMarket(market, true);
+
+      const aliceBefore = await connection.getBalance(alice.publicKey);
+      await program.methods
+        .claimWinnings()
+        .accountsPartial({
+          market,
+          position: positionPda(market, alice.publicKey),
+          user: alice.publicKey,
+          userTokenAccount: null,
+          marketVault: null,
+          winningMint: yesMint,
+          userOutcomeTokenAccount: aliceYes,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([alice])
+        .rpc();
+
+      assert.equal((await tokenBalance(aliceYes)).toNumber(), 0);
+      assert.approximately((await connection.getBalance(alice.publicKey)) - aliceBefore, 2 * LAMPORTS_PER_SOL, 10_000);
+    });
+  });
+
+  describe("position transfers", () => {
+    it("moves shares and their cost basis to another wallet", async () => {
+      const market = await createDegenMarket("Transfer position");
+      const alice = await fundedUser();
+      const bob = await fundedUser();
+      await placeDegenBet(market, alice, { yes: {} }, LAMPORTS_PER_SOL);
+
+      const fromPosition = positionPda(market, alice.publicKey);
+      const toPosition = positionPda(market, bob.publicKey);
+      await program.methods
+        .transferPosition(new BN(0.4 * LAMPORTS_PER_SOL), new BN(0))
+        .accountsPartial({ market, fromPosition, toPosition, recipient: bob.publicKey, user: alice.publicKey })
+        .signers([alice])
+        .rpc();
+
+      const from = await program.account.position.fetch(fromPosition);
+      const to = await program.account.position.fetch(toPosition);
+      assert.equal(from.yesShares.toNumber(), 0.6 * LAMPORTS_PER_SOL);
+      assert.equal(from.totalInvested.toNumber(), 0.6 * LAMPORTS_PER_SOL);
+      assert.ok(to.user.equals(bob.publicKey));
+      assert.equal(to.yesShares.toNumber(), 0.4 * LAMPORTS_PER_SOL);
+      assert.equal(to.totalInvested.toNumber(), 0.4 * LAMPORTS_PER_SOL);
+
+      try {
+        await program.methods
+          .transferPosition(new BN(LAMPORTS_PER_SOL), new BN(0))
+          .accountsPartial({ market, fromPosition, toPosition, recipient: bob.publicKey, user: alice.publicKey })
+          .signers([alice])
+          .rpc();
+        assert.fail("cannot transfer more shares than are held");
+      } catch (err) {
+        assert.include(String(err), "InsufficientShares");
+      }
+    });
+  });
+
+  describe("closing accounts", () => {
+    it("closes settled positions, then the market once creator fees are withdrawn", async () => {
+      const market = await createDegenMarket("Close accounts", 500);
+      const alice = await fundedUser();
+      const bob = await fundedUser();
+      await placeDegenBet(market, alice, { yes: {} }, LAMPORTS_PER_SOL);
+      await placeDegenBet(market, bob, { no: {} }, LAMPORTS_PER_SOL);
+
+      const closePosition = (user: Keypair) =>
+        program.methods
+          .closePosition()
+          .accountsPartial({ market, position: positionPda(market, user.publicKey), user: user.publicKey })
+          .signers([user])
+          .rpc();
+      const closeMarket = () =>
+        program.methods
+          .closeMarket()
+          .accountsPartial({
+            market,
+            authority: admin.publicKey,
+            config: configPda,
+            treasuryTokenAccount: null,
+            marketVault: null,
+            tokenProgram: null,
+          })
+          .rpc();
+
+      try {
+        await closePosition(alice);
+        assert.fail("an unsettled position cannot be closed");
+      } catch (err) {
+        assert.include(String(err), "PositionNotSettled");
+      }
+
+      await sleep(4_000);
+      await resolveDegenMarket(market, true);
+      await claimDegenWinnings(market, alice);
+
+      // Bob lost, so that position has nothing to claim and closes as-is
+      await closePosition(bob);
+      await closePosition(alice);
+      assert.isNull(await connection.getAccountInfo(positionPda(market, alice.publicKey)));
+      assert.equal((await program.account.market.fetch(market)).openPositions.toNumber(), 0);
+
+      try {
+        await closeMarket();
+        assert.fail("creator fees must be withdrawn first");
+      } catch (err) {
+        assert.include(String(err), "CreatorFeesOutstanding");
+      }
+
+      await program.methods
+        .withdrawCreatorFees()
+        .accountsPartial({
+          market,
+          authority: admin.publicKey,
+          authorityTokenAccount: null,
+          marketVault: null,
+          tokenProgram: null,
+        })
+        .rpc();
+      await closeMarket();
+      assert.isNull(await connection.getAccountInfo(market));
+    });
+  });
+
+  describe("unclaimed sweep", () => {
+    // The claim deadline is at least MIN_CLAIM_PERIOD after resolution, which a local
+    // validator cannot reach, so this covers the guards around the sweep
+    it("keeps unclaimed winnings in the market until the claim deadline", async () => {
+      const market = await createDegenMarket("Unclaimed sweep");
+      const alice = await fundedUser();
+      await placeDegenBet(market, alice, { yes: {} }, LAMPORTS_PER_SOL);
+
+      await sleep(4_000);
+      await resolveDegenMarket(market, true);
+
+      const sweep = (signer: Keypair | null) =>
+        program.methods
+          .sweepUnclaimed()
+          .accountsPartial({
+            market,
+            config: configPda,
+            admin: signer ? signer.publicKey : admin.publicKey,
+            treasury: treasury.publicKey,
+            treasuryTokenAccount: null,
+            marketVault: null,
+            tokenProgram: null,
+          })
+          .signers(signer ? [signer] : [])
+          .rpc();
+
+      try {
+        await sweep(null);
+        assert.fail("cannot sweep before the claim deadline");
+      } catch (err) {
+        assert.include(String(err), "ClaimPeriodNotEnded");
+      }
+
+      const outsider = await fundedUser();
+      try {
+        await sweep(outsider);
+        assert.fail("only the admin can sweep");
+      } catch (err) {
+        assert.include(String(err), "UnauthorizedAdmin");
+      }
+
+      // The winner can still claim everything
+      await claimDegenWinnings(market, alice);
+      const settled = await program.account.market.fetch(market);
+      assert.isTrue(settled.totalClaimed.eq(settled.totalOwed));
+    });
+
+    it("pushes the claim deadline back when a market resolves late", async () => {
+      const market = await createDegenMarket("Late resolution");
+      const created = await program.account.market.fetch(market);
+
+      await sleep(4_000);
+      const beforeResolve = await connection.getBlockTime(await connection.getSlot());
+      await resolveDegenMarket(market, true);
+
+      // Resolved after its resolution time, so the full claim period starts at resolution instead
+      const resolved = await program.account.market.fetch(market);
+      assert.isAbove(resolved.claimDeadline.toNumber(), created.claimDeadline.toNumber());
+      assert.isAtLeast(resolved.claimDeadline.toNumber(), beforeResolve + MIN_CLAIM_PERIOD);
+    });
+  });
+
+  describe("batch bets and claims", () => {
+    it("places a slate of bets in one transaction and claims them together", async () => {
+      const first = await createDegenMarket("Batch first");
+      const second = await createDegenMarket("Batch second");
+      const alice = await fundedUser();
+      const bob = await fundedUser();
+      const unused = { pubkey: SystemProgram.programId, isSigner: false, isWritable: false };
+      const triple = (market: PublicKey, user: PublicKey) => [
+        { pubkey: market, isSigner: false, isWritable: true },
+        { pubkey: positionPda(market, user), isSigner: false, isWritable: true },
+        unused,
+      ];
+
+      // Lamports sent to a position address ahead of time must not block its creation
+      await provider.sendAndConfirm(
+        new Transaction().add(
+          SystemProgram.transfer({
+            fromPubkey: admin.publicKey,
+            toPubkey: positionPda(second, alice.publicKey),
+            lamports: 1_000_000,
+          })
+        )
+      );
+
+      const firstBefore = await connection.getBalance(first);
+      const secondBefore = await connection.getBalance(second);
+      await program.methods
+        .placeBets([
+          { marketIndex: 0, side: { yes: {} }, amount: new BN(0.5 * LAMPORTS_PER_SOL) },
+          { marketIndex: 1, side: { no: {} }, amount: new BN(0.3 * LAMPORTS_PER_SOL) },
+          { marketIndex: 0, side: { yes: {} }, amount: new BN(0.2 * LAMPORTS_PER_SOL) },
+        ])
+        .accountsPartial({ user: alice.publicKey, userTokenAccount: null, tokenProgram: null })
+        .remainingAccounts([...triple(first, alice.publicKey), ...triple(second, alice.publicKey)])
+        .signers([alice])
+        .rpc();
+
+      assert.equal(await connection.getBalance(first), firstBefore + 0.7 * LAMPORTS_PER_SOL);
+      assert.equal(await connection.getBalance(second), secondBefore + 0.3 * LAMPORTS_PER_SOL);
+      const firstPosition = await program.account.position.fetch(positionPda(first, alice.publicKey));
+      const secondPosition = await program.account.position.fetch(positionPda(second, alice.publicKey));
+      assert.equal(firstPosition.yesShares.toNumber(), 0.7 * LAMPORTS_PER_SOL);
+      assert.equal(secondPosition.noShares.toNumber(), 0.3 * LAMPORTS_PER_SOL);
+
+      await placeDegenBet(first, bob, { no: {} }, 0.3 * LAMPORTS_PER_SOL);
+      await placeDegenBet(second, bob, { yes: {} }, 0.3 * LAMPORTS_PER_SOL);
+
+      await sleep(4_000);
+      await resolveDegenMarket(first, true);
+      await resolveDegenMarket(second, false);
+
+      const aliceBefore = await connection.getBalance(alice.publicKey);
+      await program.methods
+        .claimMany()
+        .accountsPartial({ user: alice.publicKey, userTokenAccount: null, tokenProgram: null })
+        .remainingAccounts([...triple(first, alice.publicKey), ...triple(second, alice.publicKey)])
+        .signers([alice])
+        .rpc();
+
+      // Alice holds every winning share in both markets, so both pots go to that wallet
+      assert.approximately(
+        (await connection.getBalance(alice.publicKey)) - aliceBefore,
+        1.6 * LAMPORTS_PER_SOL,
+        10_000
+      );
+      for (const market of [first, second]) {
+        const position = await program.account.position.fetch(positionPda(market, alice.publicKey));
+        assert.isTrue(position.claimed);
+      }
+    });
+  });
+
+  describe("parlays", () => {
+    const [parlayPool] = PublicKey.findProgramAddressSync([Buffer.from("parlay_pool"), Buffer.alloc(32)], program.programId);
+    const parlayPda = (user: PublicKey, parlayId: BN) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("parlay"), user.toBuffer(), parlayId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+
+    before(async () => {
+      await program.methods
+        .initializeParlayPool({ degen: {} })
+        .accountsPartial({
+          parlayPool,
+          poolVault: null,
+          usdcMint: null,
+          config: configPda,
+          admin: admin.publicKey,
+          tokenProgram: null,
+        })
+        .rpc();
+      await program.methods
+        .fundParlayPool(new BN(5 * LAMPORTS_PER_SOL))
+        .accountsPartial({ parlayPool, config: configPda, admin: admin.publicKey, adminTokenAccount: null, poolVault: null, tokenProgram: null })
+        .rpc();
+    });
+
+    const placeParlay = (user: Keypair, parlayId: BN, markets: PublicKey[], stake: number) =>
+      program.methods
+        .placeParlay(parlayId, markets.map(() => ({ yes: {} })), new BN(stake))
+        .accountsPartial({
+          parlayPool,
+          parlay: parlayPda(user.publicKey, parlayId),
+          user: user.publicKey,
+          userTokenAccount: null,
+          poolVault: null,
+          tokenProgram: null,
+        })
+        .remainingAccounts(markets.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })))
+        .signers([user])
+        .rpc();
+
+    it("pays the combined odds when every leg wins", async () => {
+      const bookmaker = await fundedUser();
+      const markets = [await createDegenMarket("Parlay leg one"), await createDegenMarket("Parlay leg two")];
+      for (const market of markets) {
+        await placeDegenBet(market, bookmaker, { yes: {} }, LAMPORTS_PER_SOL);
+        await placeDegenBet(market, bookmaker, { no: {} }, LAMPORTS_PER_SOL);
+      }
+
+      const user = await fundedUser();
+      const parlayId = new BN(Date.now());
+      const stake = 0.1 * LAMPORTS_PER_SOL;
+      await placeParlay(user, parlayId, markets, stake);
+
+      // Two even legs are 4.0x, less the 5% house edge
+      const parlay = await program.account.parlay.fetch(parlayPda(user.publicKey, parlayId));
+      assert.equal(parlay.potentialPayout.toNumber(), (stake * 38) / 10);
+
+      await sleep(4_000);
+      for (const market of markets) {
+        await resolveDegenMarket(market, true);
+      }
+
+      // Settlement reads every leg, so the leg markets stay open until it happens
+      assert.equal((await program.account.market.fetch(markets[0])).openParlays.toNumber(), 1);
+      try {
+        await program.methods
+          .closeMarket()
+          .accountsPartial({ market: markets[0], authority: admin.publicKey, config: configPda, treasuryTokenAccount: null, marketVault: null, tokenProgram: null })
+          .rpc();
+        assert.fail("a leg market cannot close under an open parlay");
+      } catch (err) {
+        assert.include(String(err), "ParlaysOutstanding");
+      }
+
+      // Anyone can settle; the payout still goes to the parlay's owner
+      const before = await connection.getBalance(user.publicKey);
+      await program.methods
+        .settleParlay()
+        .accountsPartial({
+          parlayPool,
+          parlay: parlayPda(user.publicKey, parlayId),
+          user: user.publicKey,
+          caller: admin.publicKey,
+          userTokenAccount: null,
+          poolVault: null,
+          tokenProgram: null,
+        })
+        .remainingAccounts(markets.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })))
+        .rpc();
+      assert.equal((await connection.getBalance(user.publicKey)) - before, (stake * 38) / 10);
+      for (const market of markets) {
+        assert.equal((await program.account.market.fetch(market)).openParlays.toNumber(), 0);
+      }
+    });
+
+    it("rejects legs on markets without depth on both sides", async () => {
+      const bookmaker = await fundedUser();
+      const deep = await createDegenMarket("Parlay deep leg");
+      await placeDegenBet(deep, bookmaker, { yes: {} }, LAMPORTS_PER_SOL);
+      await placeDegenBet(deep, bookmaker, { no: {} }, LAMPORTS_PER_SOL);
+
+      // A dust bet on an empty side would otherwise price the YES leg at huge odds
+      const thin = await createDegenMarket("Parlay thin leg");
+      await placeDegenBet(thin, bookmaker, { yes: {} }, 1_000_000);
+      await placeDegenBet(thin, bookmaker, { no: {} }, 2 * LAMPORTS_PER_SOL);
+
+      const user = await fundedUser();
+      try {
+        await placeParlay(user, new BN(Date.now()), [deep, thin], 0.1 * LAMPORTS_PER_SOL);
+        assert.fail("thin legs should be rejected");
+      } catch (err) {
+        assert.include(String(err), "ParlayMarketTooThin");
+      }
+    });
+  });
+
+  describe("price rounds", () => {
+    it("refunds tied rounds, then closes positions and guards the sweep", async () => {
+      const seriesId = new BN(Date.now());
+      const [roundSeries] = PublicKey.findProgramAddressSync(
+        [Buffer.from("round_series"), seriesId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      const roundPda = (epoch: number) =>
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("round"), roundSeries.toBuffer(), new BN(epoch).toArrayLike(Buffer, "le", 8)],
+          program.programId
+        )[0];
+
+      await program.methods
+        .initializeRoundSeries(seriesId, { degen: {} }, new BN(60), new BN(30))
+        .accountsPartial({
+          roundSeries,
+          seriesVault: null,
+          usdcMint: null,
+          priceFeed: PRICE_FEED,
+          config: configPda,
+          authority: admin.publicKey,
+          tokenProgram: null,
+        })
+        .rpc();
+
+      const crank = (epoch: number) =>
+        program.methods
+          .crankRound()
+          .accountsPartial({
+            roundSeries,
+            priceFeed: PRICE_FEED,
+            currentRound: epoch > 0 ? roundPda(epoch) : null,
+            previousRound: epoch > 1 ? roundPda(epoch - 1) : null,
+            nextRound: roundPda(epoch + 1),
+            cranker: admin.publicKey,
+          })
+          .rpc();
+
+      await crank(0);
+      const round = roundPda(1);
+      const user = await fundedUser();
+      const position = PublicKey.findProgramAddressSync(
+        [Buffer.from("position"), round.toBuffer(), user.publicKey.toBuffer()],
+        program.programId
+      )[0];
+      await program.methods
+        .placeRoundBet({ yes: {} }, new BN(LAMPORTS_PER_SOL))
+        .accountsPartial({
+          roundSeries,
+          round,
+          position,
+          user: user.publicKey,
+          userTokenAccount: null,
+          seriesVault: null,
+          tokenProgram: null,
+        })
+        .signers([user])
+        .rpc();
+
+      const closePosition = () =>
+        program.methods
+          .closeRoundPosition()
+          .accountsPartial({ round, position, user: user.publicKey })
+          .signers([user])
+          .rpc();
+      try {
+        await closePosition();
+        assert.fail("an open round position holds funds");
+      } catch (err) {
+        assert.include(String(err), "PositionNotSettled");
+      }
+
+      // Lock round 1, then close it at the same price, which cancels it as a tie
+      await sleep(61_000);
+      await crank(1);
+      await sleep(61_000);
+      await crank(2);
+      const finished = await program.account.round.fetch(round);
+      assert.deepEqual(finished.state, { cancelled: {} });
+      assert.equal(finished.claimDeadline.toNumber(), finished.closeTime.toNumber() + 30 + MIN_CLAIM_PERIOD);
+
+      try {
+        await program.methods
+          .sweepRound()
+          .accountsPartial({
+            roundSeries,
+            round,
+            config: configPda,
+            admin: admin.publicKey,
+            treasury: treasury.publicKey,
+            treasuryTokenAccount: null,
+            seriesVault: null,
+            tokenProgram: null,
+          })
+          .rpc();
+        assert.fail("cannot sweep before the claim deadline");
+      } catch (err) {
+        assert.include(String(err), "ClaimPeriodNotEnded");
+      }
+
+      const before = await connection.getBalance(user.publicKey);
+      await program.methods
+        .claimRound()
+        .accountsPartial({
+          roundSeries,
+          round,
+          position,
+          user: user.publicKey,
+          userTokenAccount: null,
+          seriesVault: null,
+          tokenProgram: null,
+        })
+        .signers([user])
+        .rpc();
+      assert.equal(await connection.getBalance(user.publicKey), before + LAMPORTS_PER_SOL);
+      assert.equal((await program.account.round.fetch(round)).totalClaimed.toNumber(), LAMPORTS_PER_SOL);
+
+      await closePosition();
+      assert.isNull(await connection.getAccountInfo(position));
+    });
+  });
+
+  describe("house pool", () => {
+    it("prices deposits so a market resolving cannot be farmed", async () => {
+      const [housePool] = PublicKey.findProgramAddressSync([Buffer.from("house_pool"), Buffer.alloc(32)], program.programId);
+      await program.methods
+        .initializeHousePool({ degen: {} })
+        .accountsPartial({ housePool, houseVault: null, usdcMint: null, config: configPda, admin: admin.publicKey, tokenProgram: null })
+        .rpc();
+
+      const houseDeposit = (owner: PublicKey) =>
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("house_deposit"), housePool.toBuffer(), owner.toBuffer()],
+          program.programId
+        )[0];
+      const deposit = (depositor: Keypair, amount: number) =>
+        program.methods
+          .depositHouseLiquidity(new BN(amount))
+          .accountsPartial({
+            housePool,
+            houseDeposit: houseDeposit(depositor.publicKey),
+            depositor: depositor.publicKey,
+            depositorTokenAccount: null,
+            houseVault: null,
+            tokenProgram: null,
+          })
+          .signers([depositor])
+          .rpc();
+
+      const early = await fundedUser();
+      await deposit(early, 4 * LAMPORTS_PER_SOL);
+
+      const marketId = new BN(Date.now());
+      const market = marketPda(marketId);
+      const resolutionTime = Math.floor(Date.now() / 1000) + 3;
+      await program.methods
+        .initializeMarket(
+          marketId,
+          "House odds",
+          "House pool pricing test",
+          "Test",
+          new BN(resolutionTime),
+          { degen: {} },
+          0,
+          new BN(resolutionTime + MIN_CLAIM_PERIOD),
+          null,
+          null,
+          { yesOddsBps: new BN(20_000), noOddsBps: new BN(20_000), maxExposure: new BN(2 * LAMPORTS_PER_SOL) },
+          null,
+          null
+        )
+        .accountsPartial({
+          market,
+          marketVault: null,
+          usdcMint: null,
+          parentMarket: null,
+          creatorPosition: null,
+          creatorTokenAccount: null,
+          authority: admin.publicKey,
+          tokenProgram: null,
+        })
+        .rpc();
+
+      // A YES bet at 2.0x reserves 2 SOL in the worst case and nothing if NO wins
+      const bettor = await fundedUser();
+      await program.methods
+        .placeBet({ yes: {} }, new BN(LAMPORTS_PER_SOL), null, null, null)
+        .accountsPartial({
+          market,
+          user: bettor.publicKey,
+          userTokenAccount: null,
+          marketVault: null,
+          outcomeMint: null,
+          userOutcomeTokenAccount: null,
+          housePool,
+          houseVault: null,
+          tokenProgram: null,
+        })
+        .signers([bettor])
+        .rpc();
+
+      // Shares are priced at the 5 SOL the pool holds if NO wins, not the 3 SOL left if YES wins
+      const late = await fundedUser();
+      await deposit(late, 5 * LAMPORTS_PER_SOL);
+      const lateShares = (await program.account.houseDeposit.fetch(houseDeposit(late.publicKey))).shares;
+      assert.equal(lateShares.toNumber(), 4 * LAMPORTS_PER_SOL);
+
+      await sleep(4_000);
+      await program.methods
+        .resolveMarket(false)
+        .accountsPartial({ market, parentMarket: null, marketGroup: null, housePool })
+        .rpc();
+
+      const before = await connection.getBalance(late.publicKey);
+      await program.methods
+        .withdrawHouseLiquidity(lateShares)
+        .accountsPartial({
+          housePool,
+          houseDeposit: houseDeposit(late.publicKey),
+          depositor: late.publicKey,
+          depositorTokenAccount: null,
+          houseVault: null,
+          tokenProgram: null,
+        })
+        .signers([late])
+        .rpc();
+      assert.equal(await connection.getBalance(late.publicKey), before + 5 * LAMPORTS_PER_SOL);
+    });
+  });
+
+  describe("lp pool", () => {
+    it("makes a market with pool liquidity and harvests its P&L", async () => {
+      const [lpPool] = PublicKey.findProgramAddressSync([Buffer.from("lp_pool"), Buffer.alloc(32)], program.programId);
+      const [lpMint] = PublicKey.findProgramAddressSync([Buffer.from("lp_mint"), lpPool.toBuffer()], program.programId);
+      const [lpEscrow] = PublicKey.findProgramAddressSync([Buffer.from("lp_escrow"), lpPool.toBuffer()], program.programId);
+      await program.methods
+        .initializeLpPool({ degen: {} })
+        .accountsPartial({
+          config: configPda,
+          lpPool,
+          lpMint,
+          lpEscrow,
+          lpVault: null,
+          usdcMint: null,
+          admin: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+      const lp = await fundedUser();
+      const lpTokens = await createTokenAccountFor(lpMint, lp.publicKey);
+      await program.methods
+        .depositLp(new BN(2 * LAMPORTS_PER_SOL))
+        .accountsPartial({
+          lpPool,
+          lpMint,
+          depositorLpTokenAccount: lpTokens,
+          depositor: lp.publicKey,
+          depositorTokenAccount: null,
+          lpVault: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([lp])
+        .rpc();
+      assert.isTrue((await tokenBalance(lpTokens)).eqn(2 * LAMPORTS_PER_SOL));
+
+      // A 60% YES seed holds 1 SOL of complete sets, with reserves weighted 40:60
+      const market = await createDegenMarket("AMM market", 0, 10);
+      const poolPosition = positionPda(market, lpPool);
+      await program.methods
+        .seedMarket(new BN(LAMPORTS_PER_SOL), 6_000)
+        .accountsPartial({
+          lpPool,
+          market,
+          position: poolPosition,
+          config: configPda,
+          admin: admin.publicKey,
+          lpVault: null,
+          marketVault: null,
+          tokenProgram: null,
+        })
+        .rpc();
+      let seeded = await program.account.market.fetch(market);
+      assert.equal(seeded.ammYesReserve.toNumber(), 666_666_666);
+      assert.equal(seeded.ammNoReserve.toNumber(), LAMPORTS_PER_SOL);
+
+      const trader = await fundedUser();
+      const traderPosition = positionPda(market, trader.publicKey);
+      const trade = {
+        market,
+        poolPosition,
+        position: traderPosition,
+        user: trader.publicKey,
+        userTokenAccount: null,
+        marketVault: null,
+        tokenProgram: null,
+      };
+
+      // 0.1 SOL less the 1% fee mints 0.099 SOL of sets, then YES comes out at constant product
+      const yes = BigInt(seeded.ammYesReserve.toString());
+      const no = BigInt(seeded.ammNoReserve.toString());
+      const net = 99_000_000n;
+      const yesAfter = (yes * no + no + net - 1n) / (no + net);
+      const bought = Number(yes + net - yesAfter);
+      await program.methods
+        .ammBuy({ yes: {} }, new BN(0.1 * LAMPORTS_PER_SOL), new BN(bought))
+        .accountsPartial(trade)
+        .signers([trader])
+        .rpc();
+      assert.equal((await program.account.position.fetch(traderPosition)).setYesShares.toNumber(), bought);
+      seeded = await program.account.market.fetch(market);
+      assert.isAbove(seeded.ammNoReserve.toNumber() / (seeded.ammYesReserve.toNumber() + seeded.ammNoReserve.toNumber()), 0.6);
+
+      // Selling back returns collateral less the fee, paid for with YES shares
+      const before = await connection.getBalance(trader.publicKey);
+      await program.methods
+        .ammSell({ yes: {} }, new BN(0.05 * LAMPORTS_PER_SOL), new BN(bought))
+        .accountsPartial(trade)
+        .signers([trader])
+        .rpc();
+      assert.equal(await connection.getBalance(trader.publicKey), before + 49_500_000);
+      const kept = (await program.account.position.fetch(traderPosition)).setYesShares.toNumber();
+      assert.isBelow(kept, bought);
+
+      await sleep(11_000);
+      await resolveDegenMarket(market, true);
+
+      // The pool's YES set shares, reserves included, redeem at par
+      const poolYes = (await program.account.position.fetch(poolPosition)).setYesShares.toNumber();
+      await program.methods
+        .harvestLpMarket()
+        .accountsPartial({
+          lpPool,
+          market,
+          position: poolPosition,
+          caller: admin.publicKey,
+          lpVault: null,
+          marketVault: null,
+          tokenProgram: null,
+        })
+        .rpc();
+      const pool = await program.account.lpPool.fetch(lpPool);
+      assert.equal(pool.idleLiquidity.toNumber(), LAMPORTS_PER_SOL + poolYes);
+      assert.lengthOf(pool.markets, 0);
+
+      const traderBefore = await connection.getBalance(trader.publicKey);
+      await claimDegenWinnings(market, trader);
+      assert.equal(await connection.getBalance(trader.publicKey), traderBefore + kept);
+    });
   });
 });